@@ -1,4 +1,4 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
 use quote::{quote, quote_spanned};
 use syn::spanned::Spanned;
 use syn::{parse_macro_input, Data, DeriveInput, Fields};
@@ -7,15 +7,183 @@ use syn::{parse_macro_input, Data, DeriveInput, Fields};
 pub fn derive_opcode_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
-    let code = &input.attrs.last().expect("missing `#[opcode(N)]`").tokens;
+    if let Data::Enum(data) = &input.data {
+        let arms = data.variants.iter().map(|variant| {
+            let name = &variant.ident;
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                assert_eq!(fields.unnamed.len(), 1);
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() => Self::#name(_) => <#op as crate::instruction::Opcode>::CODE, }
+        });
+        let name_arms = data.variants.iter().map(|variant| {
+            let name = &variant.ident;
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() => Self::#name(_) => <#op as crate::instruction::Opcode>::NAME, }
+        });
+        let opcode_name_arms = data.variants.iter().map(|variant| {
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() =>
+                <#op as crate::instruction::Opcode>::CODE => Some(<#op as crate::instruction::Opcode>::NAME),
+            }
+        });
+        let opcode_deprecated_arms = data.variants.iter().map(|variant| {
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() =>
+                <#op as crate::instruction::Opcode>::CODE => <#op as crate::instruction::Opcode>::DEPRECATED,
+            }
+        });
+        let deprecated_arms = data.variants.iter().map(|variant| {
+            let name = &variant.ident;
+            let op = if let Fields::Unnamed(fields) = &variant.fields {
+                &fields.unnamed.iter().next().expect("unreachable").ty
+            } else {
+                unimplemented!()
+            };
+            quote_spanned! { variant.span() => Self::#name(_) => <#op as crate::instruction::Opcode>::DEPRECATED, }
+        });
+        let expanded = quote! {
+            impl #name {
+                /// Returns the opcode of this instruction.
+                pub fn opcode(&self) -> u8 {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+
+                /// Returns the name of this instruction as defined in `genop.tab` (e.g., `"is_nil"`).
+                pub fn name(&self) -> &'static str {
+                    match self {
+                        #(#name_arms)*
+                    }
+                }
+
+                /// Returns the name of the instruction with the given opcode as defined in `genop.tab`.
+                pub fn opcode_name(opcode: u8) -> Option<&'static str> {
+                    match opcode {
+                        #(#opcode_name_arms)*
+                        _ => None,
+                    }
+                }
+
+                /// Returns `true` if the instruction with the given opcode is no longer emitted by
+                /// the compiler (`false` for unknown opcodes).
+                pub fn is_deprecated_opcode(opcode: u8) -> bool {
+                    match opcode {
+                        #(#opcode_deprecated_arms)*
+                        _ => false,
+                    }
+                }
+
+                /// Returns `true` if this instruction is no longer emitted by the compiler
+                /// (see [`Opcode::DEPRECATED`](crate::instruction::Opcode::DEPRECATED)).
+                pub fn is_deprecated(&self) -> bool {
+                    match self {
+                        #(#deprecated_arms)*
+                    }
+                }
+            }
+        };
+        return proc_macro::TokenStream::from(expanded);
+    }
+
+    let attr = match OpcodeAttr::parse(&input.attrs, &name) {
+        Ok(attr) => attr,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let code = &attr.code;
+    let opcode_name = &attr.name;
+    let deprecated = attr.deprecated;
     let expanded = quote! {
+        impl crate::instruction::private::Sealed for #name {}
+
         impl crate::instruction::Opcode for #name {
             const CODE: u8 = #code;
+            const NAME: &'static str = #opcode_name;
+            const DEPRECATED: bool = #deprecated;
         }
     };
     proc_macro::TokenStream::from(expanded)
 }
 
+/// The contents of `#[opcode(N)]`, optionally followed by `name = "..."` and `deprecated`.
+struct OpcodeAttr {
+    code: syn::LitInt,
+
+    /// The `genop.tab` name (the snake case of the type name if not overridden).
+    name: String,
+
+    deprecated: bool,
+}
+
+impl OpcodeAttr {
+    fn parse(attrs: &[syn::Attribute], ident: &syn::Ident) -> syn::Result<Self> {
+        let attr = attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("opcode"))
+            .ok_or_else(|| syn::Error::new(ident.span(), "missing `#[opcode(N)]`"))?;
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            meta => return Err(syn::Error::new(meta.span(), "expected `#[opcode(N)]`")),
+        };
+        let mut code = None;
+        let mut name = None;
+        let mut deprecated = false;
+        for item in &list.nested {
+            match item {
+                syn::NestedMeta::Lit(syn::Lit::Int(x)) if code.is_none() => {
+                    code = Some(x.clone());
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(x),
+                    ..
+                })) if path.is_ident("name") => {
+                    name = Some(x.value());
+                }
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("deprecated") => {
+                    deprecated = true;
+                }
+                _ => return Err(syn::Error::new(item.span(), "unexpected opcode attribute")),
+            }
+        }
+        Ok(Self {
+            code: code.ok_or_else(|| syn::Error::new(list.span(), "missing opcode number"))?,
+            name: name.unwrap_or_else(|| to_snake_case(&ident.to_string())),
+            deprecated,
+        })
+    }
+}
+
+fn to_snake_case(s: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
 #[proc_macro_derive(Decode)]
 pub fn derive_decode_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -123,3 +291,469 @@ fn generate_encode_fun_body(data: &Data) -> TokenStream {
         _ => unimplemented!(),
     }
 }
+
+#[proc_macro_derive(Walk)]
+pub fn derive_walk_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let (accept, walk) = generate_walk_fun_bodies(&input.data, false);
+    let expanded = quote! {
+        impl crate::visit::Walk for #name {
+            #accept
+
+            fn walk<V: crate::visit::Visit + ?Sized>(&self, visitor: &mut V) {
+                #walk
+            }
+        }
+    };
+    proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(WalkMut)]
+pub fn derive_walk_mut_trait(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let (accept, walk) = generate_walk_fun_bodies(&input.data, true);
+    let expanded = quote! {
+        impl crate::visit::WalkMut for #name {
+            #accept
+
+            fn walk_mut<V: crate::visit::VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+                #walk
+            }
+        }
+    };
+    proc_macro::TokenStream::from(expanded)
+}
+
+// Enums are assumed to be `Instruction`, so that `accept()` dispatches to `visit_instruction()`.
+// Structs are opcodes, which have no dedicated callback and simply walk their fields.
+fn generate_walk_fun_bodies(data: &Data, mutable: bool) -> (TokenStream, TokenStream) {
+    match *data {
+        Data::Enum(ref data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let name = &variant.ident;
+                if let Fields::Unnamed(fields) = &variant.fields {
+                    assert_eq!(fields.unnamed.len(), 1);
+                } else {
+                    unimplemented!();
+                }
+                if mutable {
+                    quote_spanned! { variant.span() => Self::#name(x) => crate::visit::WalkMut::walk_mut(x, visitor), }
+                } else {
+                    quote_spanned! { variant.span() => Self::#name(x) => crate::visit::Walk::walk(x, visitor), }
+                }
+            });
+            let accept = if mutable {
+                quote! {
+                    fn accept_mut<V: crate::visit::VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+                        visitor.visit_instruction_mut(self);
+                    }
+                }
+            } else {
+                quote! {
+                    fn accept<V: crate::visit::Visit + ?Sized>(&self, visitor: &mut V) {
+                        visitor.visit_instruction(self);
+                    }
+                }
+            };
+            let walk = quote! {
+                match self {
+                    #(#arms)*
+                }
+            };
+            (accept, walk)
+        }
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let walk = fields.named.iter().map(|f| {
+                    let name = &f.ident;
+                    if mutable {
+                        quote_spanned! { f.span() => crate::visit::WalkMut::accept_mut(&mut self.#name, visitor) }
+                    } else {
+                        quote_spanned! { f.span() => crate::visit::Walk::accept(&self.#name, visitor) }
+                    }
+                });
+                let walk = if fields.named.is_empty() {
+                    quote! { let _ = visitor; }
+                } else {
+                    quote! { #(#walk ;)* }
+                };
+                (quote! {}, walk)
+            }
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+#[proc_macro_derive(Operands)]
+pub fn derive_operands(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let operands = generate_operands_fun_body(&input.data, false);
+    let operands_mut = generate_operands_fun_body(&input.data, true);
+    let expanded = quote! {
+        impl #name {
+            /// Returns an iterator over the operands in declaration order.
+            pub fn operands(&self) -> crate::operand::Operands<'_> {
+                #operands
+            }
+
+            /// Returns an iterator over the mutable operands in declaration order.
+            pub fn operands_mut(&mut self) -> crate::operand::OperandsMut<'_> {
+                #operands_mut
+            }
+        }
+    };
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn generate_operands_fun_body(data: &Data, mutable: bool) -> TokenStream {
+    match *data {
+        Data::Enum(ref data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let name = &variant.ident;
+                if let Fields::Unnamed(fields) = &variant.fields {
+                    assert_eq!(fields.unnamed.len(), 1);
+                } else {
+                    unimplemented!();
+                }
+                if mutable {
+                    quote_spanned! { variant.span() => Self::#name(x) => x.operands_mut(), }
+                } else {
+                    quote_spanned! { variant.span() => Self::#name(x) => x.operands(), }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let items = fields.named.iter().enumerate().map(|(i, f)| {
+                    let name = &f.ident;
+                    if mutable {
+                        quote_spanned! { f.span() => items[#i] = Some(crate::operand::OperandMut::from(&mut self.#name)) }
+                    } else {
+                        quote_spanned! { f.span() => items[#i] = Some(crate::operand::Operand::from(&self.#name)) }
+                    }
+                });
+                let (ty, iter) = if mutable {
+                    (
+                        quote! { crate::operand::OperandMut },
+                        quote! { crate::operand::OperandsMut },
+                    )
+                } else {
+                    (
+                        quote! { crate::operand::Operand },
+                        quote! { crate::operand::Operands },
+                    )
+                };
+                let init = if fields.named.is_empty() {
+                    quote! { let items = Default::default(); }
+                } else {
+                    quote! { let mut items: [Option<#ty>; crate::operand::MAX_OPERANDS] = Default::default(); }
+                };
+                quote! {
+                    #init
+                    #(#items ;)*
+                    #iter::new(items)
+                }
+            }
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+#[proc_macro_derive(Emit)]
+pub fn derive_emit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    let method = match OpcodeAttr::parse(&input.attrs, &name) {
+        Ok(attr) => attr.name,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let doc = format!("Emits a `{}` instruction.", method);
+    let method = if matches!(method.as_str(), "move" | "return" | "try") {
+        quote::format_ident!("{}_", method)
+    } else {
+        quote::format_ident!("{}", method)
+    };
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    };
+    // List fields take a `Vec` of their item type (or `EmptyList`) so that `beam_asm!` lists
+    // are typed by the field.
+    let params = fields.iter().map(|f| {
+        let name = &f.ident;
+        let ty = &f.ty;
+        if is_usize(ty) {
+            quote_spanned! { f.span() => #name: usize }
+        } else if let Some(item) = list_item_type(ty) {
+            quote_spanned! { f.span() => #name: impl crate::builder::ListOperand<#item> }
+        } else {
+            quote_spanned! { f.span() => #name: impl Into<#ty> }
+        }
+    });
+    let inits = fields.iter().map(|f| {
+        let name = &f.ident;
+        if is_usize(&f.ty) {
+            quote_spanned! { f.span() => #name }
+        } else if is_vec(&f.ty) {
+            quote_spanned! { f.span() => #name: #name.into_items() }
+        } else if list_item_type(&f.ty).is_some() {
+            quote_spanned! { f.span() => #name: #name.into_items().into() }
+        } else {
+            quote_spanned! { f.span() => #name: #name.into() }
+        }
+    });
+    let expanded = quote! {
+        impl crate::builder::CodeBuilder {
+            #[doc = #doc]
+            #[allow(clippy::too_many_arguments)]
+            pub fn #method(&mut self, #(#params),*) -> &mut Self {
+                self.push(crate::instruction::Instruction::#name(#name { #(#inits),* }))
+            }
+        }
+    };
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn is_usize(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.is_ident("usize"))
+}
+
+fn is_vec(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.segments.last().is_some_and(|x| x.ident == "Vec"))
+}
+
+// Returns the item type of `Vec<T>`, `List` or `List<T>`.
+fn list_item_type(ty: &syn::Type) -> Option<TokenStream> {
+    let segment = match ty {
+        syn::Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    let item = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(item)) => Some(quote! { #item }),
+            _ => None,
+        },
+        _ => None,
+    };
+    match segment.ident.to_string().as_str() {
+        "Vec" => item,
+        "List" => Some(item.unwrap_or_else(|| quote! { crate::term::Term })),
+        _ => None,
+    }
+}
+
+/// Assembles instructions written in a syntax similar to Erlang's `.S` files.
+///
+/// See `beamcode::beam_asm!` for the details.
+#[proc_macro]
+pub fn beam_asm(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match generate_asm(TokenStream::from(input)) {
+        Ok(expanded) => proc_macro::TokenStream::from(expanded),
+        Err(e) => proc_macro::TokenStream::from(e.to_compile_error()),
+    }
+}
+
+fn generate_asm(input: TokenStream) -> syn::Result<TokenStream> {
+    let mut atoms = Vec::new();
+    let mut statements = Vec::new();
+    for tokens in split_tokens(input, ';') {
+        if !tokens.is_empty() {
+            statements.push(generate_asm_statement(&tokens, &mut atoms)?);
+        }
+    }
+    Ok(quote! {{
+        let mut builder = ::beamcode::builder::CodeBuilder::new();
+        #(#statements)*
+        builder.into_instructions()
+    }})
+}
+
+fn generate_asm_statement(
+    tokens: &[TokenTree],
+    atoms: &mut Vec<String>,
+) -> syn::Result<TokenStream> {
+    let op = match &tokens[0] {
+        TokenTree::Ident(op) => op,
+        token => return Err(syn::Error::new(token.span(), "expected an opcode name")),
+    };
+    let name = op.to_string();
+    if name == "label" {
+        // `CodeBuilder` has no emit method for `label` (see `CodeBuilder::bind()`), but
+        // labels in the assembly are numbered by hand.
+        return match &tokens[1..] {
+            [TokenTree::Literal(literal)] => Ok(quote_spanned! { op.span() =>
+                builder.push(::beamcode::instruction::Instruction::Label(
+                    ::beamcode::instruction::Label { literal: #literal },
+                ));
+            }),
+            _ => Err(syn::Error::new(op.span(), "expected `label N`")),
+        };
+    }
+    let method = if matches!(name.as_str(), "move" | "return" | "try") {
+        quote::format_ident!("{}_", name, span = op.span())
+    } else {
+        op.clone()
+    };
+    let operands = tokens[1..]
+        .iter()
+        .map(|token| generate_asm_operand(token, atoms))
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(quote_spanned! { op.span() => builder.#method(#(#operands),*); })
+}
+
+fn generate_asm_operand(token: &TokenTree, atoms: &mut Vec<String>) -> syn::Result<TokenStream> {
+    match token {
+        TokenTree::Literal(literal) => Ok(quote! { #literal }),
+        TokenTree::Ident(name) if name == "nil" => {
+            Ok(quote! { ::beamcode::term::Atom { value: 0 } })
+        }
+        TokenTree::Ident(name) => {
+            let name = name.to_string();
+            let value = match atoms.iter().position(|atom| *atom == name) {
+                Some(i) => i + 1,
+                None => {
+                    atoms.push(name);
+                    atoms.len()
+                }
+            };
+            Ok(quote! { ::beamcode::term::Atom { value: #value } })
+        }
+        TokenTree::Group(group)
+            if group.delimiter() == Delimiter::Bracket && group.stream().is_empty() =>
+        {
+            Ok(quote! { ::beamcode::builder::EmptyList })
+        }
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+            generate_asm_list(group, atoms)
+        }
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+            let parts = split_tokens(group.stream(), ',');
+            let tag = match parts.first().map(|part| part.as_slice()) {
+                Some([TokenTree::Ident(tag)]) if parts.len() == 2 => tag.to_string(),
+                _ => return Err(syn::Error::new(group.span(), "expected `{tag, value}`")),
+            };
+            let value = parts[1].iter().cloned().collect::<TokenStream>();
+            match tag.as_str() {
+                "x" => Ok(quote! { ::beamcode::builder::x(#value) }),
+                "y" => Ok(quote! { ::beamcode::builder::y(#value) }),
+                "fr" => Ok(quote! { ::beamcode::term::FloatingPointRegister { value: #value } }),
+                "f" => Ok(quote! { ::beamcode::term::Label { value: #value } }),
+                "atom" => Ok(quote! { ::beamcode::term::Atom { value: #value } }),
+                "literal" => Ok(quote! { ::beamcode::term::Literal { value: #value } }),
+                "integer" => generate_asm_integer(&parts[1], group),
+                "list" => match parts[1].as_slice() {
+                    [TokenTree::Group(list)] if list.delimiter() == Delimiter::Bracket => {
+                        let list = generate_asm_list(list, atoms)?;
+                        Ok(quote! {
+                            ::beamcode::term::List::<::beamcode::term::Term> { items: #list }
+                        })
+                    }
+                    _ => Err(syn::Error::new(group.span(), "expected `{list, [...]}`")),
+                },
+                "alloc" => match parts[1].as_slice() {
+                    [TokenTree::Group(list)] if list.delimiter() == Delimiter::Bracket => {
+                        generate_asm_alloc(list)
+                    }
+                    _ => Err(syn::Error::new(group.span(), "expected `{alloc, [...]}`")),
+                },
+                _ => Err(syn::Error::new(
+                    group.span(),
+                    format!("unknown tag `{tag}`"),
+                )),
+            }
+        }
+        _ => Err(syn::Error::new(token.span(), "unexpected operand")),
+    }
+}
+
+// Generates `vec![...]` whose item type is inferred from the operand field (see `Emit`).
+fn generate_asm_list(group: &Group, atoms: &mut Vec<String>) -> syn::Result<TokenStream> {
+    let mut items = Vec::new();
+    for tokens in split_tokens(group.stream(), ',') {
+        match tokens.as_slice() {
+            [] => {}
+            [token] => items.push(generate_asm_operand(token, atoms)?),
+            [token, ..] => return Err(syn::Error::new(token.span(), "expected a single operand")),
+        }
+    }
+    Ok(quote! { ::std::vec![#(::core::convert::From::from(#items)),*] })
+}
+
+// Integers are parsed here so that out-of-range values are neither truncated nor wrapped.
+fn generate_asm_integer(tokens: &[TokenTree], group: &Group) -> syn::Result<TokenStream> {
+    let (sign, literal) = match tokens {
+        [TokenTree::Punct(minus), TokenTree::Literal(x)] if minus.as_char() == '-' => ("-", x),
+        [TokenTree::Literal(x)] => ("", x),
+        _ => return Err(syn::Error::new(group.span(), "expected `{integer, N}`")),
+    };
+    let literal = syn::parse2::<syn::LitInt>(TokenTree::Literal(literal.clone()).into())?;
+    if !literal.suffix().is_empty() {
+        return Err(syn::Error::new(literal.span(), "unexpected integer suffix"));
+    }
+    let digits = format!("{sign}{}", literal.base10_digits());
+    if let Ok(value) = digits.parse::<i64>() {
+        Ok(quote! { ::beamcode::term::Term::Integer(::core::convert::From::from(#value)) })
+    } else {
+        Ok(quote! {
+            ::beamcode::term::Term::Integer(
+                ::core::str::FromStr::from_str(#digits).expect("unreachable"),
+            )
+        })
+    }
+}
+
+fn generate_asm_alloc(group: &Group) -> syn::Result<TokenStream> {
+    let mut items = Vec::new();
+    for tokens in split_tokens(group.stream(), ',') {
+        let item = match tokens.as_slice() {
+            [] => continue,
+            [TokenTree::Group(item)] if item.delimiter() == Delimiter::Brace => item,
+            [token, ..] => return Err(syn::Error::new(token.span(), "expected `{kind, n}`")),
+        };
+        let parts = split_tokens(item.stream(), ',');
+        let (kind, n) = match parts.as_slice() {
+            [kind, n] => (
+                kind.iter().cloned().collect::<TokenStream>().to_string(),
+                n.iter().cloned().collect::<TokenStream>(),
+            ),
+            _ => return Err(syn::Error::new(item.span(), "expected `{kind, n}`")),
+        };
+        let variant = match kind.as_str() {
+            "words" => quote! { Words },
+            "floats" => quote! { Floats },
+            "funs" => quote! { Funs },
+            _ => {
+                return Err(syn::Error::new(
+                    item.span(),
+                    format!("unknown allocation kind `{kind}`"),
+                ))
+            }
+        };
+        items.push(quote! { ::beamcode::term::AllocationListItem::#variant(#n) });
+    }
+    Ok(quote! { ::beamcode::term::AllocationList { items: ::std::vec![#(#items),*] } })
+}
+
+fn split_tokens(input: TokenStream, separator: char) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    for token in input {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == separator => parts.push(Vec::new()),
+            _ => parts.last_mut().expect("unreachable").push(token),
+        }
+    }
+    parts
+}
@@ -0,0 +1,64 @@
+use beamcode::beam::BeamFile;
+use beamcode::etf;
+use beamcode::interp::{Debugger, Module, Process, Value};
+use clap::Parser;
+use num::BigInt;
+
+/// Runs a function of a BEAM file in the step debugger (type `help` for the commands).
+#[derive(Parser)]
+struct Args {
+    beam_file_path: std::path::PathBuf,
+    function: String,
+
+    /// Integers or atoms.
+    args: Vec<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let beam = BeamFile::from_file(&args.beam_file_path)?;
+    let module = Module {
+        literals: beam
+            .literals
+            .iter()
+            .map(|x| value(&etf::decode(x)?))
+            .collect::<anyhow::Result<_>>()?,
+        code: beam.code,
+        atoms: beam.atoms,
+        imports: beam.imports,
+        strings: beam.strings,
+        ..Default::default()
+    };
+
+    let function_args = args
+        .args
+        .iter()
+        .map(|x| {
+            x.parse::<BigInt>()
+                .map_or_else(|_| Value::atom(x), Value::from)
+        })
+        .collect();
+    let mut process = Process::new(&module);
+    process
+        .start(&args.function, function_args)
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    Debugger::new(&mut process).run(std::io::stdin().lock(), std::io::stdout())?;
+    Ok(())
+}
+
+// Converts a literal (maps are not supported).
+fn value(term: &etf::Term) -> anyhow::Result<Value> {
+    let value = match term {
+        etf::Term::Atom(x) => Value::atom(x),
+        etf::Term::Integer(x) => Value::from(x.clone()),
+        etf::Term::Float(x) => Value::Float(*x),
+        etf::Term::Tuple(x) => Value::tuple(x.iter().map(value).collect::<anyhow::Result<_>>()?),
+        etf::Term::List(x, tail) => x.iter().rev().try_fold(value(tail)?, |tail, head| {
+            anyhow::Ok(Value::cons(value(head)?, tail))
+        })?,
+        etf::Term::Nil => Value::Nil,
+        etf::Term::Binary(x) => Value::binary(x),
+        etf::Term::Map(_) => anyhow::bail!("unsupported literal: {term}"),
+    };
+    Ok(value)
+}
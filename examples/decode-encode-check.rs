@@ -20,7 +20,7 @@ fn main() -> anyhow::Result<()> {
                 let end = chunk.bytecode.len() - reader.len();
                 let expected = &chunk.bytecode[start..end];
 
-                let encoded = beamcode::encode_instructions(&[instruction.clone()])?;
+                let encoded = beamcode::encode_instructions(std::slice::from_ref(&instruction))?;
                 assert_eq!(encoded, expected, "[{}] {:?}", i, instruction);
             }
             return Ok(());
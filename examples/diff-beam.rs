@@ -0,0 +1,39 @@
+use beamcode::beam::BeamFile;
+use beamcode::diff::{self, DiffOptions};
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Compares the code of two BEAM files instruction by instruction.
+#[derive(Parser)]
+struct Args {
+    old_beam_file_path: PathBuf,
+    new_beam_file_path: PathBuf,
+
+    /// Number of context lines.
+    #[clap(short = 'U', long, default_value_t = 3)]
+    context: usize,
+
+    /// Compares `line` instructions too.
+    #[clap(long)]
+    lines: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let old = BeamFile::from_file(&args.old_beam_file_path)?;
+    let new = BeamFile::from_file(&args.new_beam_file_path)?;
+    let options = DiffOptions { lines: args.lines };
+    let diff = diff::diff(&old.module(), &new.module(), &options);
+    print!(
+        "{}",
+        diff.unified(
+            &args.old_beam_file_path.display().to_string(),
+            &args.new_beam_file_path.display().to_string(),
+            args.context
+        )
+    );
+    if !diff.is_equal() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use beamcode::beam::BeamFile;
+use beamcode::repro::{self, Build, CheckOptions, Chunk};
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Checks whether two builds of a BEAM file are equivalent modulo non-deterministic parts.
+#[derive(Parser)]
+struct Args {
+    old_beam_file_path: PathBuf,
+    new_beam_file_path: PathBuf,
+
+    /// Chunks that are not compared.
+    #[clap(long)]
+    ignore: Vec<String>,
+}
+
+// Returns the chunks of `beam` in the form given to `repro::check()`.
+fn chunks(beam: &BeamFile) -> Vec<Chunk<'_>> {
+    beam.chunks
+        .iter()
+        .map(|x| Chunk::new(x.id, &x.data))
+        .collect()
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let options = CheckOptions {
+        ignored_chunks: args
+            .ignore
+            .iter()
+            .map(|x| {
+                x.as_bytes()
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("invalid chunk ID: {x:?}"))
+            })
+            .collect::<anyhow::Result<_>>()?,
+    };
+
+    let old = BeamFile::from_file(&args.old_beam_file_path)?;
+    let new = BeamFile::from_file(&args.new_beam_file_path)?;
+    let (old_chunks, new_chunks) = (chunks(&old), chunks(&new));
+    let differences = repro::check(
+        &Build {
+            chunks: &old_chunks,
+            module: old.module(),
+        },
+        &Build {
+            chunks: &new_chunks,
+            module: new.module(),
+        },
+        &options,
+    );
+    for difference in &differences {
+        println!("{difference}");
+    }
+    if !differences.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! Loading of `.beam` files (requires the `beam_file` feature).
+//!
+//! [`BeamFile`] keeps all chunks as they are and decodes the ones that the other modules of this
+//! crate work on (e.g., the code and the tables given to [`diff::diff()`]).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use beamcode::beam::BeamFile;
+//!
+//! let beam = BeamFile::from_file("foo.beam").unwrap();
+//! println!("{}: {} instructions", beam.module_name(), beam.code.len());
+//! ```
+use crate::diff;
+use crate::instruction::Instruction;
+use crate::module::{FunctionEntry, FunctionTables, Import, LambdaEntry};
+use crate::term::{Atom, Label};
+use crate::DecodeError;
+use beam_file::chunk::{Chunk as _, RawChunk, StandardChunk};
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unknown atom: {id}")]
+    UnknownAtom { id: u32 },
+
+    #[error(transparent)]
+    DecodeError(#[from] DecodeError),
+
+    #[error(transparent)]
+    BeamFileError(#[from] beam_file::Error),
+}
+
+/// A loaded `.beam` file.
+#[derive(Debug, Default)]
+pub struct BeamFile {
+    /// All chunks in the order of the file.
+    pub chunks: Vec<RawChunk>,
+
+    /// The atom table (`AtU8` or `Atom` chunk).
+    pub atoms: Vec<String>,
+
+    /// The instructions of the `Code` chunk.
+    pub code: Vec<Instruction>,
+
+    /// The literal table (`LitT` chunk), each in the external term format.
+    pub literals: Vec<Vec<u8>>,
+
+    /// The string table (`StrT` chunk).
+    pub strings: Vec<u8>,
+
+    /// The import table (`ImpT` chunk) with atoms resolved.
+    pub imports: Vec<Import>,
+
+    /// The export, local function and lambda tables.
+    pub tables: FunctionTables,
+}
+
+impl BeamFile {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_chunks(beam_file::RawBeamFile::from_file(path)?.chunks)
+    }
+
+    /// Decodes the standard chunks in `chunks`.
+    ///
+    /// Missing chunks result in empty tables.
+    pub fn from_chunks(chunks: Vec<RawChunk>) -> Result<Self, Error> {
+        let mut beam = Self::default();
+        let mut imports = Vec::new();
+        for chunk in &chunks {
+            match StandardChunk::decode_data(&chunk.id, &chunk.data[..])? {
+                StandardChunk::Atom(chunk) => {
+                    beam.atoms = chunk.atoms.into_iter().map(|x| x.name).collect();
+                }
+                StandardChunk::Code(chunk) => {
+                    beam.code = crate::decode_instructions(&chunk.bytecode)?;
+                }
+                StandardChunk::LitT(chunk) => beam.literals = chunk.literals,
+                StandardChunk::StrT(chunk) => beam.strings = chunk.strings,
+                StandardChunk::ImpT(chunk) => imports = chunk.imports,
+                StandardChunk::ExpT(chunk) => {
+                    beam.tables.exports = chunk
+                        .exports
+                        .iter()
+                        .map(|x| function_entry(x.function, x.arity, x.label))
+                        .collect();
+                }
+                StandardChunk::LocT(chunk) => {
+                    beam.tables.locals = chunk
+                        .locals
+                        .iter()
+                        .map(|x| function_entry(x.function, x.arity, x.label))
+                        .collect();
+                }
+                StandardChunk::FunT(chunk) => {
+                    beam.tables.lambdas = chunk
+                        .functions
+                        .iter()
+                        .map(|x| LambdaEntry {
+                            function: Atom {
+                                value: x.function as usize,
+                            },
+                            arity: x.arity as usize,
+                            label: Label {
+                                value: x.label as usize,
+                            },
+                            index: x.index as usize,
+                            num_free: x.num_free as usize,
+                            old_uniq: x.old_uniq,
+                        })
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+        beam.chunks = chunks;
+
+        let atom = |id: u32| {
+            beam.atom_name(Atom { value: id as usize })
+                .ok_or(Error::UnknownAtom { id })
+        };
+        beam.imports = imports
+            .iter()
+            .map(|x| {
+                Ok(Import::new(
+                    atom(x.module)?,
+                    atom(x.function)?,
+                    x.arity as usize,
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(beam)
+    }
+
+    /// Returns the first entry of the atom table (or `""` if there is none).
+    pub fn module_name(&self) -> &str {
+        self.atoms.first().map_or("", |x| x.as_str())
+    }
+
+    /// Returns the name of an atom (`atom(1)` is the first entry of the atom table).
+    pub fn atom_name(&self, atom: Atom) -> Option<&str> {
+        let index = atom.value.checked_sub(1)?;
+        self.atoms.get(index).map(|x| x.as_str())
+    }
+
+    pub fn chunk(&self, id: &[u8; 4]) -> Option<&RawChunk> {
+        self.chunks.iter().find(|x| x.id == *id)
+    }
+
+    /// Returns the tables used by [`diff::diff()`].
+    pub fn module(&self) -> diff::Module<'_> {
+        diff::Module {
+            code: &self.code,
+            atoms: &self.atoms,
+            literals: &self.literals,
+            imports: &self.imports,
+        }
+    }
+}
+
+fn function_entry(function: u32, arity: u32, label: u32) -> FunctionEntry {
+    FunctionEntry {
+        function: Atom {
+            value: function as usize,
+        },
+        arity: arity as usize,
+        label: Label {
+            value: label as usize,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+    use crate::module::CodeChunk;
+
+    #[test]
+    fn from_chunks_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info {atom,1} {atom,2} 0;
+            label 2;
+            call_ext_only 0 0;
+        };
+        let mut atoms = 3u32.to_be_bytes().to_vec();
+        for name in ["m", "f", "erlang"] {
+            atoms.push(name.len() as u8);
+            atoms.extend_from_slice(name.as_bytes());
+        }
+        let imports = [1u32, 3, 2, 0].map(u32::to_be_bytes).concat();
+        let exports = [1u32, 2, 0, 2].map(u32::to_be_bytes).concat();
+        let chunks = vec![
+            RawChunk {
+                id: *b"AtU8",
+                data: atoms,
+            },
+            RawChunk {
+                id: *b"Code",
+                data: CodeChunk::new(code.clone()).encode().unwrap(),
+            },
+            RawChunk {
+                id: *b"ImpT",
+                data: imports,
+            },
+            RawChunk {
+                id: *b"ExpT",
+                data: exports,
+            },
+            RawChunk {
+                id: *b"Dbgi",
+                data: vec![1, 2, 3],
+            },
+        ];
+
+        let beam = BeamFile::from_chunks(chunks).unwrap();
+        assert_eq!(beam.module_name(), "m");
+        assert_eq!(
+            crate::encode_instructions(&beam.code).unwrap(),
+            crate::encode_instructions(&code).unwrap()
+        );
+        assert_eq!(beam.imports, [Import::new("erlang", "f", 0)]);
+        assert_eq!(beam.tables.exports, [function_entry(2, 0, 2)]);
+        assert_eq!(beam.atom_name(Atom { value: 0 }), None);
+        assert_eq!(beam.chunk(b"Dbgi").map(|x| x.data.len()), Some(3));
+
+        let chunks = vec![RawChunk {
+            id: *b"ImpT",
+            data: [1u32, 1, 1, 0].map(u32::to_be_bytes).concat(),
+        }];
+        assert!(matches!(
+            BeamFile::from_chunks(chunks),
+            Err(Error::UnknownAtom { id: 1 })
+        ));
+    }
+}
@@ -0,0 +1,784 @@
+//! The `beamcode` command-line tool (requires the `cli` feature).
+//!
+//! Each subcommand accepts `.beam` files or directories (which are searched recursively) and
+//! prints one report per file, either as text or as a JSON array (`--format json`).
+use anyhow::Context as _;
+use beam_file::chunk::RawChunk;
+use beamcode::beam::BeamFile;
+use beamcode::diff::{self, DiffOptions};
+use beamcode::etf::{self, Term};
+use beamcode::instruction::Instruction;
+use beamcode::module::CodeHeader;
+use beamcode::stats::Stats;
+use beamcode::verify::{self, VerifyOptions};
+use clap::{ArgEnum, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Chunks kept by `strip` (the same as `beam_lib:strip/1` plus `Type`).
+const SIGNIFICANT_CHUNKS: [&[u8; 4]; 13] = [
+    b"Atom", b"AtU8", b"Code", b"StrT", b"ImpT", b"ExpT", b"FunT", b"LitT", b"Meta", b"Line",
+    b"Type", b"Attr", b"CInf",
+];
+
+/// Inspects and manipulates Erlang BEAM files.
+#[derive(Parser)]
+#[clap(version)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists the chunks and their sizes.
+    Chunks(Inputs),
+
+    /// Disassembles the `Code` chunk.
+    Disasm(Inputs),
+
+    /// Assembles the output of `disasm --format json` back into the `.beam` files it came from.
+    Asm {
+        /// JSON files written by `disasm --format json`.
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+
+        #[clap(long, arg_enum, default_value = "text")]
+        format: Format,
+
+        /// Writes the files to this directory instead of overwriting the original ones.
+        #[clap(short, long)]
+        output_dir: Option<PathBuf>,
+    },
+
+    /// Shows the module name, exports, imports and attributes.
+    Info(Inputs),
+
+    /// Removes the chunks that are not needed for loading (e.g., debug info and docs).
+    Strip {
+        #[clap(flatten)]
+        inputs: Inputs,
+
+        /// Writes the files to this directory instead of overwriting the original ones.
+        #[clap(short, long)]
+        output_dir: Option<PathBuf>,
+    },
+
+    /// Checks the code in the way of the BEAM loader (exits with 1 on problems).
+    Verify(Inputs),
+
+    /// Counts opcodes (including deprecated ones), operand kinds and encoded sizes over all the
+    /// files.
+    Stats {
+        /// `.beam` files or directories containing them.
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+
+        #[clap(long, arg_enum, default_value = "text")]
+        format: StatsFormat,
+
+        /// Prints the operand kinds instead of the opcodes.
+        #[clap(long)]
+        operand_kinds: bool,
+    },
+
+    /// Compares two files, or the files with the same names in two directories, instruction by
+    /// instruction (exits with 1 on differences).
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+
+        #[clap(long, arg_enum, default_value = "text")]
+        format: Format,
+
+        /// Number of context lines.
+        #[clap(short = 'U', long, default_value_t = 3)]
+        context: usize,
+
+        /// Compares `line` instructions too.
+        #[clap(long)]
+        lines: bool,
+    },
+
+    /// Checks that decoding and re-encoding reproduces the code (exits with 1 on mismatches).
+    Roundtrip(Inputs),
+}
+
+#[derive(clap::Args)]
+struct Inputs {
+    /// `.beam` files or directories containing them.
+    #[clap(required = true)]
+    paths: Vec<PathBuf>,
+
+    #[clap(long, arg_enum, default_value = "text")]
+    format: Format,
+}
+
+impl Inputs {
+    fn load(&self) -> anyhow::Result<Vec<Beam>> {
+        find_files(&self.paths, "beam")?
+            .iter()
+            .map(|x| Beam::load(x))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum StatsFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let ok = match args.command {
+        Command::Chunks(inputs) => print(inputs.format, &chunks(&inputs.load()?))?,
+        Command::Disasm(inputs) => print(inputs.format, &disasm(inputs.load()?))?,
+        Command::Asm {
+            paths,
+            format,
+            output_dir,
+        } => print(format, &asm(&paths, output_dir.as_deref())?)?,
+        Command::Info(inputs) => print(inputs.format, &info(&inputs.load()?)?)?,
+        Command::Strip { inputs, output_dir } => print(
+            inputs.format,
+            &strip(inputs.load()?, output_dir.as_deref())?,
+        )?,
+        Command::Verify(inputs) => print(inputs.format, &verify(&inputs.load()?))?,
+        Command::Stats {
+            paths,
+            format,
+            operand_kinds,
+        } => print_stats(format, &stats(&paths)?, operand_kinds)?,
+        Command::Diff {
+            old,
+            new,
+            format,
+            context,
+            lines,
+        } => print(format, &diff(&old, &new, context, &DiffOptions { lines })?)?,
+        Command::Roundtrip(inputs) => print(inputs.format, &roundtrip(&inputs.load()?)?)?,
+    };
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// A report of a subcommand for a file.
+trait Report: Serialize + fmt::Display {
+    /// Returns `false` if the subcommand found a problem.
+    fn is_ok(&self) -> bool {
+        true
+    }
+}
+
+fn print<T: Report>(format: Format, reports: &[T]) -> anyhow::Result<bool> {
+    match format {
+        Format::Text => {
+            for report in reports {
+                print!("{report}");
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(reports)?),
+    }
+    Ok(reports.iter().all(|x| x.is_ok()))
+}
+
+// Returns the files in `paths`, searching directories recursively for files with `extension`.
+fn find_files(paths: &[PathBuf], extension: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(path)
+                .with_context(|| format!("cannot read {}", path.display()))?
+                .map(|x| Ok(x?.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            entries.sort();
+            entries.retain(|x| x.is_dir() || x.extension().is_some_and(|x| x == extension));
+            files.extend(find_files(&entries, extension)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// A loaded `.beam` file.
+struct Beam {
+    path: PathBuf,
+    file: BeamFile,
+}
+
+impl Beam {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            file: BeamFile::from_file(path)
+                .with_context(|| format!("cannot load {}", path.display()))?,
+        })
+    }
+
+    // Returns the `Code` chunk header and the bytecode.
+    fn code_chunk(&self) -> anyhow::Result<(CodeHeader, &[u8])> {
+        let data = &self
+            .file
+            .chunk(b"Code")
+            .ok_or_else(|| anyhow::anyhow!("missing mandatory 'Code' chunk"))?
+            .data;
+        CodeHeader::decode(data).context("invalid 'Code' chunk")
+    }
+}
+
+fn write_beam(
+    chunks: Vec<RawChunk>,
+    path: &Path,
+    output_dir: Option<&Path>,
+) -> anyhow::Result<PathBuf> {
+    let output = match (output_dir, path.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => path.to_owned(),
+    };
+    beam_file::RawBeamFile { chunks }
+        .to_file(&output)
+        .with_context(|| format!("cannot write {}", output.display()))?;
+    Ok(output)
+}
+
+#[derive(Serialize)]
+struct ChunksReport {
+    path: PathBuf,
+    chunks: Vec<ChunkEntry>,
+}
+
+#[derive(Serialize)]
+struct ChunkEntry {
+    id: String,
+    size: usize,
+}
+
+impl Report for ChunksReport {}
+
+impl fmt::Display for ChunksReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.path.display())?;
+        for chunk in &self.chunks {
+            writeln!(f, "  {} {:>8}", chunk.id, chunk.size)?;
+        }
+        Ok(())
+    }
+}
+
+fn chunks(beams: &[Beam]) -> Vec<ChunksReport> {
+    beams
+        .iter()
+        .map(|beam| ChunksReport {
+            path: beam.path.clone(),
+            chunks: beam
+                .file
+                .chunks
+                .iter()
+                .map(|x| ChunkEntry {
+                    id: String::from_utf8_lossy(&x.id).into_owned(),
+                    size: x.data.len(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct DisasmReport {
+    path: PathBuf,
+    instructions: Vec<Instruction>,
+}
+
+impl Report for DisasmReport {}
+
+impl fmt::Display for DisasmReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.path.display())?;
+        for instruction in &self.instructions {
+            writeln!(f, "  {instruction}")?;
+        }
+        Ok(())
+    }
+}
+
+fn disasm(beams: Vec<Beam>) -> Vec<DisasmReport> {
+    beams
+        .into_iter()
+        .map(|beam| DisasmReport {
+            path: beam.path,
+            instructions: beam.file.code,
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct AsmReport {
+    path: PathBuf,
+    instructions: usize,
+    size: usize,
+}
+
+impl Report for AsmReport {}
+
+impl fmt::Display for AsmReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} instructions, {} bytes",
+            self.path.display(),
+            self.instructions,
+            self.size
+        )
+    }
+}
+
+fn asm(paths: &[PathBuf], output_dir: Option<&Path>) -> anyhow::Result<Vec<AsmReport>> {
+    let mut reports = Vec::new();
+    for path in find_files(paths, "json")? {
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let modules: Vec<DisasmReport> = serde_json::from_str(&json)
+            .with_context(|| format!("cannot parse {}", path.display()))?;
+        for module in modules {
+            let beam = Beam::load(&module.path)?;
+            let (header, _) = beam.code_chunk()?;
+            let bytecode = beamcode::encode_instructions(&module.instructions)?;
+            let computed = CodeHeader::new(&module.instructions);
+            let header = CodeHeader {
+                version: header.version,
+                opcode_max: computed.opcode_max.max(header.opcode_max),
+                ..computed
+            };
+            let chunks = beam
+                .file
+                .chunks
+                .into_iter()
+                .map(|chunk| match &chunk.id {
+                    b"Code" => RawChunk {
+                        id: chunk.id,
+                        data: header.encode(&bytecode),
+                    },
+                    _ => chunk,
+                })
+                .collect();
+            reports.push(AsmReport {
+                path: write_beam(chunks, &beam.path, output_dir)?,
+                instructions: module.instructions.len(),
+                size: bytecode.len(),
+            });
+        }
+    }
+    Ok(reports)
+}
+
+#[derive(Serialize)]
+struct InfoReport {
+    path: PathBuf,
+    module: String,
+    exports: Vec<String>,
+    imports: Vec<String>,
+    attributes: Vec<Attribute>,
+}
+
+#[derive(Serialize)]
+struct Attribute {
+    name: String,
+    value: String,
+}
+
+impl Report for InfoReport {}
+
+impl fmt::Display for InfoReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.path.display())?;
+        writeln!(f, "  module: {}", self.module)?;
+        writeln!(f, "  exports:")?;
+        for export in &self.exports {
+            writeln!(f, "    {export}")?;
+        }
+        writeln!(f, "  imports:")?;
+        for import in &self.imports {
+            writeln!(f, "    {import}")?;
+        }
+        writeln!(f, "  attributes:")?;
+        for attribute in &self.attributes {
+            writeln!(f, "    {}: {}", attribute.name, attribute.value)?;
+        }
+        Ok(())
+    }
+}
+
+fn info(beams: &[Beam]) -> anyhow::Result<Vec<InfoReport>> {
+    beams
+        .iter()
+        .map(|beam| {
+            let attributes = match beam.file.chunk(b"Attr") {
+                Some(chunk) => attributes(&chunk.data)
+                    .with_context(|| format!("invalid 'Attr' chunk in {}", beam.path.display()))?,
+                None => Vec::new(),
+            };
+            let exports = beam
+                .file
+                .tables
+                .exports
+                .iter()
+                .map(|x| {
+                    let name = beam.file.atom_name(x.function).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "unknown atom {} in {}",
+                            x.function.value,
+                            beam.path.display()
+                        )
+                    })?;
+                    Ok(format!("{name}/{}", x.arity))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(InfoReport {
+                path: beam.path.clone(),
+                module: beam.file.module_name().to_owned(),
+                exports,
+                imports: beam
+                    .file
+                    .imports
+                    .iter()
+                    .map(|x| format!("{}:{}/{}", x.module, x.function, x.arity))
+                    .collect(),
+                attributes,
+            })
+        })
+        .collect()
+}
+
+// Decodes the `Attr` chunk, which is a list of `{Name, Value}` tuples.
+fn attributes(bytes: &[u8]) -> anyhow::Result<Vec<Attribute>> {
+    let term = etf::decode(bytes)?;
+    let elements = term
+        .as_list()
+        .ok_or_else(|| anyhow::anyhow!("not a list: {term}"))?;
+    elements
+        .iter()
+        .map(|x| match x {
+            Term::Tuple(x) if x.len() == 2 => Ok(Attribute {
+                name: x[0].to_string(),
+                value: x[1].to_string(),
+            }),
+            x => anyhow::bail!("unexpected attribute: {x}"),
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct StripReport {
+    path: PathBuf,
+    removed: Vec<String>,
+}
+
+impl Report for StripReport {}
+
+impl fmt::Display for StripReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: removed [{}]",
+            self.path.display(),
+            self.removed.join(", ")
+        )
+    }
+}
+
+fn strip(beams: Vec<Beam>, output_dir: Option<&Path>) -> anyhow::Result<Vec<StripReport>> {
+    beams
+        .into_iter()
+        .map(|beam| {
+            let (kept, removed): (Vec<_>, Vec<_>) = beam
+                .file
+                .chunks
+                .into_iter()
+                .partition(|x| SIGNIFICANT_CHUNKS.contains(&&x.id));
+            Ok(StripReport {
+                path: write_beam(kept, &beam.path, output_dir)?,
+                removed: removed
+                    .iter()
+                    .map(|x| String::from_utf8_lossy(&x.id).into_owned())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct VerifyReport {
+    path: PathBuf,
+    diagnostics: Vec<String>,
+}
+
+impl Report for VerifyReport {
+    fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.diagnostics.is_empty() {
+            return writeln!(f, "{}: ok", self.path.display());
+        }
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}: {diagnostic}", self.path.display())?;
+        }
+        Ok(())
+    }
+}
+
+fn verify(beams: &[Beam]) -> Vec<VerifyReport> {
+    beams
+        .iter()
+        .map(|beam| {
+            let options = VerifyOptions {
+                opcode_max: beam
+                    .code_chunk()
+                    .ok()
+                    .and_then(|(header, _)| u8::try_from(header.opcode_max).ok()),
+            };
+            VerifyReport {
+                path: beam.path.clone(),
+                diagnostics: verify::verify(&beam.file.code, &options)
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn stats(paths: &[PathBuf]) -> anyhow::Result<Stats> {
+    let mut stats = Stats::default();
+    for path in find_files(paths, "beam")? {
+        stats.add_module(&Beam::load(&path)?.file.code)?;
+    }
+    Ok(stats)
+}
+
+fn print_stats(format: StatsFormat, stats: &Stats, operand_kinds: bool) -> anyhow::Result<bool> {
+    match format {
+        StatsFormat::Text => {
+            println!(
+                "{} modules, {} instructions, {} bytes",
+                stats.modules, stats.instructions, stats.bytes
+            );
+            if operand_kinds {
+                println!("operand kinds:");
+                for (kind, count) in &stats.operand_kinds {
+                    println!("  {kind:<24} {count:>10}");
+                }
+            } else {
+                let mut opcodes = stats.opcodes.iter().collect::<Vec<_>>();
+                opcodes.sort_by_key(|x| std::cmp::Reverse(x.1.count));
+                println!("opcodes:");
+                for (name, x) in opcodes {
+                    let deprecated = if x.deprecated { " (deprecated)" } else { "" };
+                    println!(
+                        "  {name:<24} {:>10} {:>10} bytes {:>6} modules{deprecated}",
+                        x.count, x.bytes, x.modules
+                    );
+                }
+            }
+        }
+        StatsFormat::Json if operand_kinds => {
+            println!("{}", serde_json::to_string_pretty(&stats.operand_kinds)?)
+        }
+        StatsFormat::Json => println!("{}", serde_json::to_string_pretty(stats)?),
+        StatsFormat::Csv if operand_kinds => print!("{}", stats.operand_kinds_csv()),
+        StatsFormat::Csv => print!("{}", stats.opcodes_csv()),
+    }
+    Ok(true)
+}
+
+#[derive(Serialize)]
+struct DiffReport {
+    old: Option<PathBuf>,
+    new: Option<PathBuf>,
+    functions: Vec<FunctionDiffSummary>,
+
+    #[serde(skip)]
+    unified: String,
+}
+
+#[derive(Serialize)]
+struct FunctionDiffSummary {
+    name: String,
+    arity: usize,
+    inserted: usize,
+    removed: usize,
+    changed: usize,
+}
+
+impl Report for DiffReport {
+    fn is_ok(&self) -> bool {
+        self.old.is_some() && self.new.is_some() && self.functions.is_empty()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(_), Some(_)) => write!(f, "{}", self.unified),
+            (Some(path), None) | (None, Some(path)) => writeln!(f, "Only in {}", path.display()),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+fn diff(
+    old: &Path,
+    new: &Path,
+    context: usize,
+    options: &DiffOptions,
+) -> anyhow::Result<Vec<DiffReport>> {
+    if !(old.is_dir() && new.is_dir()) {
+        return Ok(vec![diff_files(old, new, context, options)?]);
+    }
+
+    // Pairs the files by their paths relative to the directories.
+    let mut pairs = BTreeMap::<PathBuf, (Option<PathBuf>, Option<PathBuf>)>::new();
+    for path in find_files(&[old.to_owned()], "beam")? {
+        let key = path.strip_prefix(old)?.to_owned();
+        pairs.entry(key).or_default().0 = Some(path);
+    }
+    for path in find_files(&[new.to_owned()], "beam")? {
+        let key = path.strip_prefix(new)?.to_owned();
+        pairs.entry(key).or_default().1 = Some(path);
+    }
+    pairs
+        .into_values()
+        .map(|pair| match pair {
+            (Some(old), Some(new)) => diff_files(&old, &new, context, options),
+            (old, new) => Ok(DiffReport {
+                old,
+                new,
+                functions: Vec::new(),
+                unified: String::new(),
+            }),
+        })
+        .collect()
+}
+
+fn diff_files(
+    old: &Path,
+    new: &Path,
+    context: usize,
+    options: &DiffOptions,
+) -> anyhow::Result<DiffReport> {
+    let old_beam = Beam::load(old)?;
+    let new_beam = Beam::load(new)?;
+    let diff = diff::diff(&old_beam.file.module(), &new_beam.file.module(), options);
+    Ok(DiffReport {
+        old: Some(old.to_owned()),
+        new: Some(new.to_owned()),
+        functions: diff
+            .functions
+            .iter()
+            .filter(|x| !x.is_equal())
+            .map(|x| FunctionDiffSummary {
+                name: x.name.clone(),
+                arity: x.arity,
+                inserted: x.inserted(),
+                removed: x.removed(),
+                changed: x.changed(),
+            })
+            .collect(),
+        unified: diff.unified(
+            &old.display().to_string(),
+            &new.display().to_string(),
+            context,
+        ),
+    })
+}
+
+#[derive(Serialize)]
+struct RoundtripReport {
+    path: PathBuf,
+    instructions: usize,
+    mismatch: Option<Mismatch>,
+}
+
+#[derive(Serialize)]
+struct Mismatch {
+    position: usize,
+    instruction: String,
+    expected: Vec<u8>,
+    actual: Vec<u8>,
+}
+
+impl Report for RoundtripReport {
+    fn is_ok(&self) -> bool {
+        self.mismatch.is_none()
+    }
+}
+
+impl fmt::Display for RoundtripReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.mismatch {
+            None => writeln!(
+                f,
+                "{}: ok ({} instructions)",
+                self.path.display(),
+                self.instructions
+            ),
+            Some(x) => writeln!(
+                f,
+                "{}: mismatch at {}: {}: expected={:?}, actual={:?}",
+                self.path.display(),
+                x.position,
+                x.instruction,
+                x.expected,
+                x.actual
+            ),
+        }
+    }
+}
+
+fn roundtrip(beams: &[Beam]) -> anyhow::Result<Vec<RoundtripReport>> {
+    use beamcode::Decode as _;
+
+    let mut reports = Vec::new();
+    for beam in beams {
+        let (_, bytecode) = beam.code_chunk()?;
+        let mut reader = bytecode;
+        let mut mismatch = None;
+        for (position, instruction) in beam.file.code.iter().enumerate() {
+            let start = bytecode.len() - reader.len();
+            Instruction::decode(&mut reader)?;
+            let expected = &bytecode[start..bytecode.len() - reader.len()];
+            let actual = beamcode::encode_instructions(std::slice::from_ref(instruction))?;
+            if actual != expected {
+                mismatch = Some(Mismatch {
+                    position,
+                    instruction: instruction.to_string(),
+                    expected: expected.to_owned(),
+                    actual,
+                });
+                break;
+            }
+        }
+        reports.push(RoundtripReport {
+            path: beam.path.clone(),
+            instructions: beam.file.code.len(),
+            mismatch,
+        });
+    }
+    Ok(reports)
+}
@@ -0,0 +1,240 @@
+//! Construction of module code with symbolic labels.
+//!
+//! [`CodeBuilder`] has an emit method for each opcode except `label`, named after the opcode in
+//! `genop.tab` (`move_`, `return_` and `try_` are suffixed with `_` as they are Rust keywords).
+//! Labels are allocated by [`CodeBuilder::new_label()`] and defined by [`CodeBuilder::bind()`].
+//! Operands are taken as `impl Into<T>`, so registers, atoms and labels can be passed directly.
+//! List operands are taken as [`ListOperand`] (i.e., a `Vec` of the item type or [`EmptyList`]).
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::builder::{x, y, CodeBuilder};
+//! use beamcode::term::Atom;
+//!
+//! let mut b = CodeBuilder::new();
+//! let entry = b.function(Atom { value: 1 }, Atom { value: 2 }, 1);
+//! let fail = b.new_label();
+//! b.is_nil(fail, x(0)).return_();
+//! b.bind(fail);
+//! b.move_(x(0), y(0)).jump(entry);
+//! assert_eq!(b.label_count(), 4);
+//!
+//! let code = b.finish();
+//! let code = code.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+//! assert_eq!(
+//!     code,
+//!     [
+//!         "label 1",
+//!         "func_info atom(1), atom(2), 1",
+//!         "label 2",
+//!         "is_nil f(3), x(0)",
+//!         "return",
+//!         "label 3",
+//!         "move x(0), y(0)",
+//!         "jump f(2)",
+//!         "int_code_end",
+//!     ]
+//! );
+//! ```
+use crate::instruction::{self, Instruction};
+use crate::module::CodeChunk;
+use crate::term::{Atom, Label, Term, XRegister, YRegister};
+
+/// Returns the X register with the given number.
+pub fn x(value: usize) -> XRegister {
+    XRegister { value, ty: None }
+}
+
+/// Returns the Y register with the given number.
+pub fn y(value: usize) -> YRegister {
+    YRegister { value, ty: None }
+}
+
+/// The operand written as `[]` in [`beam_asm!`](crate::beam_asm).
+///
+/// It is `nil` (i.e., `Atom { value: 0 }`) for term operands and an empty list for list operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyList;
+
+impl From<EmptyList> for Atom {
+    fn from(_: EmptyList) -> Self {
+        Atom { value: 0 }
+    }
+}
+
+impl From<EmptyList> for Term {
+    fn from(x: EmptyList) -> Self {
+        Term::Atom(x.into())
+    }
+}
+
+/// A list operand of the emit methods: a `Vec` of the item type or [`EmptyList`].
+pub trait ListOperand<T> {
+    fn into_items(self) -> Vec<T>;
+}
+
+impl<T> ListOperand<T> for Vec<T> {
+    fn into_items(self) -> Vec<T> {
+        self
+    }
+}
+
+impl<T> ListOperand<T> for EmptyList {
+    fn into_items(self) -> Vec<T> {
+        Vec::new()
+    }
+}
+
+/// A builder of module code.
+#[derive(Debug, Clone, Default)]
+pub struct CodeBuilder {
+    instructions: Vec<Instruction>,
+    bound: Vec<bool>,
+}
+
+impl CodeBuilder {
+    /// Makes a new [`CodeBuilder`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a new label, which must be bound by [`CodeBuilder::bind()`] once.
+    pub fn new_label(&mut self) -> Label {
+        self.bound.push(false);
+        Label {
+            value: self.bound.len(),
+        }
+    }
+
+    /// Emits a `label` instruction that defines the label.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the label was not allocated by this builder or has already been bound.
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        let bound = label
+            .value
+            .checked_sub(1)
+            .and_then(|i| self.bound.get_mut(i))
+            .expect("unknown label");
+        assert!(!*bound, "label {} is already bound", label.value);
+        *bound = true;
+        self.push(Instruction::Label(instruction::Label {
+            literal: label.value,
+        }))
+    }
+
+    /// Starts a new function by emitting `label`, `func_info` and `label` instructions.
+    ///
+    /// Returns the entry label of the function (i.e., the target of local calls).
+    pub fn function(&mut self, module: Atom, name: Atom, arity: usize) -> Label {
+        let header = self.new_label();
+        let entry = self.new_label();
+        self.bind(header).func_info(module, name, arity).bind(entry);
+        entry
+    }
+
+    /// Emits an instruction.
+    pub fn push(&mut self, instruction: Instruction) -> &mut Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Returns the number of labels to be written to the header of the `Code` chunk.
+    pub fn label_count(&self) -> usize {
+        self.bound.len() + 1
+    }
+
+    /// Returns the emitted instructions as they are.
+    pub fn into_instructions(self) -> Vec<Instruction> {
+        self.instructions
+    }
+
+    /// Finishes the code by appending an `int_code_end` instruction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a label allocated by [`CodeBuilder::new_label()`] has not been bound.
+    pub fn finish(mut self) -> Vec<Instruction> {
+        if let Some(i) = self.bound.iter().position(|bound| !bound) {
+            panic!("label {} is not bound", i + 1);
+        }
+        self.int_code_end();
+        self.instructions
+    }
+
+    /// Finishes the code as [`CodeBuilder::finish()`] does and makes the `Code` chunk with
+    /// the label and function counts in its header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a label allocated by [`CodeBuilder::new_label()`] has not been bound.
+    pub fn finish_module(self) -> CodeChunk {
+        CodeChunk::new(self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::term::{Literal, Register, Term};
+
+    #[test]
+    fn builder_works() {
+        let mut b = CodeBuilder::new();
+        let entry = b.function(Atom { value: 1 }, Atom { value: 2 }, 0);
+        let handler = b.new_label();
+        b.allocate(1, 0)
+            .try_(Register::Y(y(0)), handler)
+            .move_(Literal { value: 0 }, x(0))
+            .call_only(0, entry)
+            .bind(handler)
+            .try_case(y(0))
+            .select_val(
+                x(0),
+                entry,
+                vec![Term::Atom(Atom { value: 3 }), Term::Label(handler)],
+            );
+        assert_eq!(b.label_count(), 4);
+
+        let code = b.finish();
+        assert_eq!(code.len(), 11);
+        assert_eq!(code[4].to_string(), "try y(0), f(3)");
+        assert_eq!(code[5].to_string(), "move literal(0), x(0)");
+        assert!(matches!(code[10], Instruction::IntCodeEnd(_)));
+    }
+
+    #[test]
+    fn finish_module_works() {
+        let mut b = CodeBuilder::new();
+        b.function(Atom { value: 1 }, Atom { value: 2 }, 0);
+        b.return_();
+        b.function(Atom { value: 1 }, Atom { value: 3 }, 0);
+        b.return_();
+        assert_eq!(b.label_count(), 5);
+
+        let code = b.finish_module();
+        assert_eq!(code.header.label_count, 5);
+        assert_eq!(code.header.function_count, 2);
+        assert_eq!(code.header.opcode_max, 19);
+        assert_eq!(code.instructions.len(), 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "label 1 is not bound")]
+    fn finish_with_unbound_label_fails() {
+        let mut b = CodeBuilder::new();
+        let label = b.new_label();
+        b.jump(label);
+        b.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "already bound")]
+    fn bind_twice_fails() {
+        let mut b = CodeBuilder::new();
+        let label = b.new_label();
+        b.bind(label).bind(label);
+    }
+}
@@ -0,0 +1,243 @@
+//! Call graphs of modules.
+use crate::cfg;
+use crate::instruction::Instruction;
+use crate::module::LambdaEntry;
+use crate::term::{Atom, Label, Term};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A function in a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The range of the function in the module code (see [`cfg::functions()`]).
+    pub range: Range<usize>,
+
+    pub module: Atom,
+
+    pub name: Atom,
+
+    pub arity: usize,
+
+    /// The label following `func_info`, which is the target of local calls.
+    pub entry: Option<Label>,
+}
+
+/// The target of a [`Call`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Callee {
+    /// A function in the same module (index of [`CallGraph::functions()`]).
+    Local(usize),
+
+    /// An entry of the import table (`ImpT` chunk).
+    Import(usize),
+
+    /// An entry of the lambda table (`FunT` chunk) that could not be resolved to a function.
+    Lambda(usize),
+}
+
+/// The kind of a [`Call`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallKind {
+    /// `call` or `call_ext`.
+    Call,
+
+    /// `call_only`, `call_last`, `call_ext_only` or `call_ext_last`.
+    TailCall,
+
+    /// `make_fun`, `make_fun2` or `make_fun3`.
+    MakeFun,
+}
+
+/// A statically known call (or fun creation) in a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Call {
+    /// The calling function (index of [`CallGraph::functions()`]).
+    pub caller: usize,
+
+    pub callee: Callee,
+
+    pub kind: CallKind,
+
+    /// The index of the calling instruction in the module code.
+    pub position: usize,
+}
+
+/// The call graph of a module.
+///
+/// Dynamic calls such as `apply` and `call_fun` are not included.
+#[derive(Debug, Clone)]
+pub struct CallGraph {
+    functions: Vec<Function>,
+    calls: Vec<Call>,
+    labels: HashMap<usize, usize>,
+}
+
+impl CallGraph {
+    /// Builds the call graph of module code.
+    ///
+    /// `make_fun2` and `make_fun3` refer to entries of `lambdas` (the `FunT` chunk), which are
+    /// resolved to the functions defining their labels. Entries that are missing from `lambdas`
+    /// are kept as [`Callee::Lambda`].
+    /// Local calls to labels that are not defined in any function are omitted.
+    pub fn new(instructions: &[Instruction], lambdas: &[LambdaEntry]) -> Self {
+        let mut functions = Vec::new();
+        let mut labels = HashMap::new();
+        for range in cfg::functions(instructions) {
+            let index = functions.len();
+            let mut function = Function {
+                range: range.clone(),
+                module: Atom { value: 0 },
+                name: Atom { value: 0 },
+                arity: 0,
+                entry: None,
+            };
+            for i in range {
+                match &instructions[i] {
+                    Instruction::Label(x) => {
+                        labels.insert(x.literal, index);
+                    }
+                    Instruction::FuncInfo(x) => {
+                        function.module = x.module;
+                        function.name = x.function;
+                        function.arity = x.arity;
+                        if let Some(Instruction::Label(x)) = instructions.get(i + 1) {
+                            function.entry = Some(Label { value: x.literal });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            functions.push(function);
+        }
+
+        let mut calls = Vec::new();
+        for (caller, function) in functions.iter().enumerate() {
+            for position in function.range.clone() {
+                let target = match &instructions[position] {
+                    Instruction::Call(x) => local(&labels, x.label.value, CallKind::Call),
+                    Instruction::CallOnly(x) => local(&labels, x.label.value, CallKind::TailCall),
+                    Instruction::CallLast(x) => match x.arg2 {
+                        Term::Label(label) => local(&labels, label.value, CallKind::TailCall),
+                        _ => None,
+                    },
+                    Instruction::CallExt(x) => {
+                        Some((Callee::Import(x.destination), CallKind::Call))
+                    }
+                    Instruction::CallExtOnly(x) => {
+                        Some((Callee::Import(x.destination), CallKind::TailCall))
+                    }
+                    Instruction::CallExtLast(x) => {
+                        Some((Callee::Import(x.destination), CallKind::TailCall))
+                    }
+                    Instruction::MakeFun(x) => match x.arg1 {
+                        Term::Label(label) => local(&labels, label.value, CallKind::MakeFun),
+                        _ => None,
+                    },
+                    Instruction::MakeFun2(x) => lambda(&labels, lambdas, &x.arg1),
+                    Instruction::MakeFun3(x) => lambda(&labels, lambdas, &x.arg1),
+                    _ => None,
+                };
+                if let Some((callee, kind)) = target {
+                    calls.push(Call {
+                        caller,
+                        callee,
+                        kind,
+                        position,
+                    });
+                }
+            }
+        }
+        Self {
+            functions,
+            calls,
+            labels,
+        }
+    }
+
+    /// Returns the functions in code order.
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
+    }
+
+    /// Returns the calls in code order.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// Returns the index of the function that defines the label.
+    pub fn function_of_label(&self, label: Label) -> Option<usize> {
+        self.labels.get(&label.value).copied()
+    }
+}
+
+fn local(
+    labels: &HashMap<usize, usize>,
+    label: usize,
+    kind: CallKind,
+) -> Option<(Callee, CallKind)> {
+    labels
+        .get(&label)
+        .map(|&index| (Callee::Local(index), kind))
+}
+
+fn lambda(
+    labels: &HashMap<usize, usize>,
+    lambdas: &[LambdaEntry],
+    index: &Term,
+) -> Option<(Callee, CallKind)> {
+    let Term::Usize(index) = index else {
+        return None;
+    };
+    match lambdas.iter().find(|x| x.index == *index) {
+        Some(entry) => local(labels, entry.label.value, CallKind::MakeFun),
+        None => Some((Callee::Lambda(*index), CallKind::MakeFun)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    #[test]
+    fn call_graph_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 0;
+            label 2;
+            make_fun3 0 {x,0} [];
+            make_fun2 1;
+            call_only 0 {f,4};
+            label 3;
+            func_info foo bar_fun 0;
+            label 4;
+            call_ext_only 0 0;
+        };
+        let lambdas = [LambdaEntry {
+            function: Atom { value: 3 },
+            arity: 0,
+            label: Label { value: 4 },
+            index: 0,
+            num_free: 0,
+            old_uniq: 0,
+        }];
+        let graph = CallGraph::new(&code, &lambdas);
+        assert_eq!(graph.functions().len(), 2);
+        assert_eq!(graph.functions()[1].entry, Some(Label { value: 4 }));
+        assert_eq!(
+            graph
+                .calls()
+                .iter()
+                .map(|x| (x.caller, x.callee, x.kind, x.position))
+                .collect::<Vec<_>>(),
+            [
+                (0, Callee::Local(1), CallKind::MakeFun, 3),
+                (0, Callee::Lambda(1), CallKind::MakeFun, 4),
+                (0, Callee::Local(1), CallKind::TailCall, 5),
+                (1, Callee::Import(0), CallKind::TailCall, 9),
+            ]
+        );
+        assert_eq!(graph.function_of_label(Label { value: 3 }), Some(1));
+        assert_eq!(graph.function_of_label(Label { value: 5 }), None);
+    }
+}
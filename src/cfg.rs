@@ -0,0 +1,455 @@
+//! Control-flow graphs of functions.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::cfg::{self, Cfg};
+//! use beamcode::instruction::{self, Instruction};
+//! use beamcode::term::{Atom, Label};
+//!
+//! let code = vec![
+//!     Instruction::Label(instruction::Label { literal: 1 }),
+//!     Instruction::FuncInfo(instruction::FuncInfo {
+//!         module: Atom { value: 1 },
+//!         function: Atom { value: 2 },
+//!         arity: 0,
+//!     }),
+//!     Instruction::Label(instruction::Label { literal: 2 }),
+//!     Instruction::Jump(instruction::Jump { label: Label { value: 3 } }),
+//!     Instruction::Label(instruction::Label { literal: 3 }),
+//!     Instruction::Return(instruction::Return {}),
+//! ];
+//! let functions = cfg::functions(&code);
+//! assert_eq!(functions.len(), 1);
+//! assert_eq!(functions[0], 0..6);
+//!
+//! let cfg = Cfg::new(&code[functions[0].clone()]);
+//! assert_eq!(cfg.blocks().len(), 3);
+//! assert_eq!(cfg.entry(), cfg.block_of_label(Label { value: 2 }).unwrap());
+//! ```
+use crate::flow::Successors;
+use crate::instruction::Instruction;
+use crate::term::{Label, Term};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Splits module code into functions.
+///
+/// A function starts at the `label`s (and `line`s) preceding its `func_info` instruction and
+/// ends just before the next function. A trailing `int_code_end` is not included.
+pub fn functions(instructions: &[Instruction]) -> Vec<Range<usize>> {
+    let mut starts = Vec::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        if !matches!(instruction, Instruction::FuncInfo(_)) {
+            continue;
+        }
+        let mut start = i;
+        while start > 0
+            && matches!(
+                instructions[start - 1],
+                Instruction::Label(_) | Instruction::Line(_)
+            )
+        {
+            start -= 1;
+        }
+        starts.push(start);
+    }
+
+    let mut end = instructions.len();
+    if matches!(instructions.last(), Some(Instruction::IntCodeEnd(_))) {
+        end -= 1;
+    }
+    let mut functions = Vec::with_capacity(starts.len());
+    for (i, &start) in starts.iter().enumerate() {
+        let next = starts.get(i + 1).copied().unwrap_or(end);
+        functions.push(start..next);
+    }
+    functions
+}
+
+/// The index of a basic block in a [`Cfg`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub usize);
+
+/// The kind of an [`Edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// Control continues with the next block.
+    FallThrough,
+
+    /// Unconditional jump (e.g., `jump`, `loop_rec_end`).
+    Jump,
+
+    /// Taken when a test or an operation fails (e.g., `is_nil`, `bs_get_integer2`).
+    Branch,
+
+    /// One of the destinations (including the fail label) of `select_val` or `select_tuple_arity`.
+    Select,
+
+    /// From `try` or `catch` to its handler.
+    Exception,
+}
+
+/// A directed edge between basic blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub target: BlockId,
+    pub kind: EdgeKind,
+}
+
+/// A maximal sequence of instructions that is entered only at the top and left only at the
+/// bottom (ignoring implicit exceptions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The range of the block in the instructions passed to [`Cfg::new()`].
+    pub range: Range<usize>,
+
+    /// The labels defined at the top of the block.
+    pub labels: Vec<Label>,
+
+    pub successors: Vec<Edge>,
+
+    pub predecessors: Vec<BlockId>,
+}
+
+/// The control-flow graph of a function.
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    blocks: Vec<BasicBlock>,
+    labels: HashMap<Label, BlockId>,
+    entry: BlockId,
+}
+
+impl Cfg {
+    /// Builds the control-flow graph of a function.
+    ///
+    /// The entry block is the one following `func_info`, or the first block if there is none.
+    /// Edges to labels that are not defined in `instructions` are omitted.
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let mut blocks = Vec::<BasicBlock>::new();
+        let mut labels = HashMap::new();
+        let mut entry = None;
+        let mut start = 0;
+        let mut after_func_info = false;
+        for (i, instruction) in instructions.iter().enumerate() {
+            if let Instruction::Label(label) = instruction {
+                let only_labels = instructions[start..i]
+                    .iter()
+                    .all(|x| matches!(x, Instruction::Label(_)));
+                if !only_labels {
+                    blocks.push(BasicBlock::new(start..i));
+                    start = i;
+                }
+                let label = Label {
+                    value: label.literal,
+                };
+                labels.insert(label, BlockId(blocks.len()));
+            }
+            if after_func_info && entry.is_none() {
+                entry = Some(BlockId(blocks.len()));
+            }
+            after_func_info = matches!(instruction, Instruction::FuncInfo(_));
+            if instruction.successors().is_terminator() {
+                blocks.push(BasicBlock::new(start..i + 1));
+                start = i + 1;
+            }
+        }
+        if start < instructions.len() {
+            blocks.push(BasicBlock::new(start..instructions.len()));
+        }
+        for (label, id) in &labels {
+            blocks[id.0].labels.push(*label);
+        }
+
+        let mut cfg = Self {
+            labels,
+            entry: entry.unwrap_or(BlockId(0)),
+            blocks,
+        };
+        for i in 0..cfg.blocks.len() {
+            let successors = cfg.compute_successors(instructions, BlockId(i));
+            for edge in &successors {
+                let predecessors = &mut cfg.blocks[edge.target.0].predecessors;
+                if !predecessors.contains(&BlockId(i)) {
+                    predecessors.push(BlockId(i));
+                }
+            }
+            let block = &mut cfg.blocks[i];
+            block.labels.sort();
+            block.successors = successors;
+        }
+        cfg
+    }
+
+    fn compute_successors(&self, instructions: &[Instruction], id: BlockId) -> Vec<Edge> {
+        let block = &self.blocks[id.0];
+        let mut edges = Vec::new();
+        let mut add = |label: Label, kind| {
+            if let Some(&target) = self.labels.get(&label) {
+                edges.push(Edge { target, kind });
+            }
+        };
+        for instruction in &instructions[block.range.clone()] {
+            match instruction {
+                Instruction::Try(x) => add(x.label, EdgeKind::Exception),
+                Instruction::Catch(x) => {
+                    if let Term::Label(label) = x.arg2 {
+                        add(label, EdgeKind::Exception);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let successors = block
+            .range
+            .clone()
+            .last()
+            .map_or(Successors::FallThrough, |i| instructions[i].successors());
+        match &successors {
+            Successors::Jump(label) => add(*label, EdgeKind::Jump),
+            Successors::Branch(label) => add(*label, EdgeKind::Branch),
+            Successors::Select { .. } => {
+                for label in successors.labels() {
+                    add(label, EdgeKind::Select);
+                }
+            }
+            _ => {}
+        }
+        if successors.falls_through() && id.0 + 1 < self.blocks.len() {
+            edges.push(Edge {
+                target: BlockId(id.0 + 1),
+                kind: EdgeKind::FallThrough,
+            });
+        }
+        edges
+    }
+
+    /// Returns the entry block.
+    pub fn entry(&self) -> BlockId {
+        self.entry
+    }
+
+    /// Returns the basic blocks in code order.
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Returns the basic block with the given ID.
+    pub fn block(&self, id: BlockId) -> &BasicBlock {
+        &self.blocks[id.0]
+    }
+
+    /// Returns the basic block that starts with the label, if any.
+    pub fn block_of_label(&self, label: Label) -> Option<BlockId> {
+        self.labels.get(&label).copied()
+    }
+
+    /// Returns the blocks reachable from the entry in reverse postorder.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        if self.blocks.is_empty() {
+            return Vec::new();
+        }
+        let mut visited = vec![false; self.blocks.len()];
+        let mut order = Vec::with_capacity(self.blocks.len());
+        let mut stack = vec![(self.entry, 0)];
+        visited[self.entry.0] = true;
+        while let Some((id, next)) = stack.pop() {
+            if let Some(edge) = self.blocks[id.0].successors.get(next) {
+                stack.push((id, next + 1));
+                if !visited[edge.target.0] {
+                    visited[edge.target.0] = true;
+                    stack.push((edge.target, 0));
+                }
+            } else {
+                order.push(id);
+            }
+        }
+        order.reverse();
+        order
+    }
+
+    /// Computes the dominator tree.
+    ///
+    /// This uses the algorithm described in "A Simple, Fast Dominance Algorithm" by Cooper, Harvey
+    /// and Kennedy.
+    pub fn dominators(&self) -> DominatorTree {
+        let order = self.reverse_postorder();
+        let mut rank = vec![usize::MAX; self.blocks.len()];
+        for (i, id) in order.iter().enumerate() {
+            rank[id.0] = i;
+        }
+
+        let mut idom = vec![None; self.blocks.len()];
+        if let Some(&entry) = order.first() {
+            idom[entry.0] = Some(entry);
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &id in order.iter().skip(1) {
+                let mut new_idom = None;
+                for &pred in &self.blocks[id.0].predecessors {
+                    if idom[pred.0].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(other) => intersect(&idom, &rank, pred, other),
+                    });
+                }
+                if new_idom.is_some() && idom[id.0] != new_idom {
+                    idom[id.0] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        DominatorTree {
+            entry: self.entry,
+            idom,
+        }
+    }
+}
+
+impl BasicBlock {
+    fn new(range: Range<usize>) -> Self {
+        Self {
+            range,
+            labels: Vec::new(),
+            successors: Vec::new(),
+            predecessors: Vec::new(),
+        }
+    }
+}
+
+fn intersect(idom: &[Option<BlockId>], rank: &[usize], mut a: BlockId, mut b: BlockId) -> BlockId {
+    while a != b {
+        while rank[a.0] > rank[b.0] {
+            a = idom[a.0].expect("unreachable");
+        }
+        while rank[b.0] > rank[a.0] {
+            b = idom[b.0].expect("unreachable");
+        }
+    }
+    a
+}
+
+/// The dominator tree of a [`Cfg`].
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    entry: BlockId,
+    idom: Vec<Option<BlockId>>,
+}
+
+impl DominatorTree {
+    /// Returns the immediate dominator of the block.
+    ///
+    /// `None` is returned for the entry block and for unreachable blocks.
+    pub fn immediate_dominator(&self, id: BlockId) -> Option<BlockId> {
+        if id == self.entry {
+            None
+        } else {
+            self.idom[id.0]
+        }
+    }
+
+    /// Returns `true` if every path from the entry to `b` goes through `a`.
+    pub fn dominates(&self, a: BlockId, mut b: BlockId) -> bool {
+        if self.idom[b.0].is_none() {
+            return false;
+        }
+        loop {
+            if a == b {
+                return true;
+            }
+            match self.immediate_dominator(b) {
+                Some(parent) => b = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Returns `true` if the block is reachable from the entry.
+    pub fn is_reachable(&self, id: BlockId) -> bool {
+        self.idom[id.0].is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction;
+    use crate::term::{Atom, Register, XRegister, YRegister};
+
+    fn label(value: usize) -> Instruction {
+        Instruction::Label(instruction::Label { literal: value })
+    }
+
+    #[test]
+    fn cfg_works() {
+        let x0 = Term::XRegister(XRegister { value: 0, ty: None });
+        let code = vec![
+            label(1),
+            Instruction::FuncInfo(instruction::FuncInfo {
+                module: Atom { value: 1 },
+                function: Atom { value: 2 },
+                arity: 1,
+            }),
+            label(2),
+            Instruction::Try(instruction::Try {
+                register: Register::Y(YRegister { value: 0, ty: None }),
+                label: Label { value: 5 },
+            }),
+            Instruction::IsNil(instruction::IsNil {
+                label: Label { value: 3 },
+                arg1: x0.clone(),
+            }),
+            Instruction::Jump(instruction::Jump {
+                label: Label { value: 4 },
+            }),
+            label(3),
+            Instruction::Badmatch(instruction::Badmatch { arg1: x0 }),
+            label(4),
+            Instruction::Return(instruction::Return {}),
+            label(5),
+            Instruction::Return(instruction::Return {}),
+            label(6),
+            Instruction::Return(instruction::Return {}),
+            Instruction::IntCodeEnd(instruction::IntCodeEnd {}),
+        ];
+        let functions = functions(&code);
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0], 0..14);
+
+        let cfg = Cfg::new(&code[functions[0].clone()]);
+        let b = |value| cfg.block_of_label(Label { value }).unwrap();
+        assert_eq!(cfg.blocks().len(), 7);
+        assert_eq!(cfg.entry(), b(2));
+        assert_eq!(
+            cfg.block(b(2)).successors,
+            [
+                Edge {
+                    target: b(5),
+                    kind: EdgeKind::Exception
+                },
+                Edge {
+                    target: b(3),
+                    kind: EdgeKind::Branch
+                },
+                Edge {
+                    target: BlockId(2),
+                    kind: EdgeKind::FallThrough
+                },
+            ]
+        );
+        assert_eq!(cfg.block(b(4)).predecessors, [BlockId(2)]);
+
+        let dominators = cfg.dominators();
+        assert_eq!(dominators.immediate_dominator(b(2)), None);
+        assert_eq!(dominators.immediate_dominator(b(4)), Some(BlockId(2)));
+        assert!(dominators.dominates(b(2), b(4)));
+        assert!(!dominators.dominates(b(3), b(4)));
+        assert!(!dominators.is_reachable(b(1)));
+        assert!(!dominators.is_reachable(b(6)));
+    }
+}
@@ -0,0 +1,872 @@
+//! Registers read (used) and written (defined) by instructions.
+//!
+//! Besides explicit register operands, the implicit operands of the BEAM calling conventions are
+//! taken into account, e.g., `x0..x(Arity-1)` for calls and `x0..x(Live-1)` for instructions
+//! that may trigger a garbage collection.
+//!
+//! Note that calls also invalidate every X register except `x0`, and `trim` renumbers Y registers.
+//! Those effects are not reported as definitions.
+//!
+//! # References
+//!
+//! - [erlang/otp/lib/compiler/src/genop.tab](https://github.com/erlang/otp/blob/master/lib/compiler/src/genop.tab)
+//! - [erlang/otp/lib/compiler/src/beam_validator.erl](https://github.com/erlang/otp/blob/master/lib/compiler/src/beam_validator.erl)
+use crate::instruction::Instruction;
+use crate::term::{Allocation, List, Register, Term, TypedRegister};
+use std::fmt;
+
+/// A register identifier without type information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RegisterId {
+    X(usize),
+    Y(usize),
+    /// Floating point register.
+    Fr(usize),
+}
+
+impl RegisterId {
+    /// Returns the identifier of a register term.
+    ///
+    /// If `term` is not a register, this method returns `None`.
+    pub fn from_term(term: &Term) -> Option<Self> {
+        match term {
+            Term::XRegister(x) => Some(Self::X(x.value)),
+            Term::YRegister(x) => Some(Self::Y(x.value)),
+            Term::FloatingPointRegister(x) => Some(Self::Fr(x.value)),
+            Term::TypedRegister(x) => Some(Self::from_typed_register(x)),
+            _ => None,
+        }
+    }
+
+    pub fn from_register(register: &Register) -> Self {
+        match register {
+            Register::X(x) => Self::X(x.value),
+            Register::Y(x) => Self::Y(x.value),
+            Register::Typed(x) => Self::from_typed_register(x),
+        }
+    }
+
+    fn from_typed_register(register: &TypedRegister) -> Self {
+        match register {
+            TypedRegister::X { register, .. } => Self::X(register.value),
+            TypedRegister::Y { register, .. } => Self::Y(register.value),
+        }
+    }
+}
+
+impl fmt::Display for RegisterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::X(i) => write!(f, "x({i})"),
+            Self::Y(i) => write!(f, "y({i})"),
+            Self::Fr(i) => write!(f, "fr({i})"),
+        }
+    }
+}
+
+impl Instruction {
+    /// Returns the registers written by this instruction in ascending order.
+    pub fn defs(&self) -> Vec<RegisterId> {
+        DefUse::new(self).defs
+    }
+
+    /// Returns the registers read by this instruction in ascending order.
+    pub fn uses(&self) -> Vec<RegisterId> {
+        DefUse::new(self).uses
+    }
+
+    /// Returns the number of X registers preserved by this instruction, if it clobbers the others.
+    ///
+    /// This is `live` for instructions that may garbage collect (e.g., `test_heap`, `gc_bif2`)
+    /// and 0 for calls. Note that registers in [`Instruction::defs()`] are written afterwards
+    /// (e.g., `x0` holds the return value of a call).
+    pub fn preserved_x_registers(&self) -> Option<usize> {
+        DefUse::new(self).preserved
+    }
+}
+
+#[derive(Debug, Default)]
+struct DefUse {
+    defs: Vec<RegisterId>,
+    uses: Vec<RegisterId>,
+    preserved: Option<usize>,
+}
+
+impl DefUse {
+    fn new(instruction: &Instruction) -> Self {
+        let mut x = Self::default();
+        x.analyze(instruction);
+        x.defs.sort();
+        x.defs.dedup();
+        x.uses.sort();
+        x.uses.dedup();
+        x
+    }
+
+    fn analyze(&mut self, instruction: &Instruction) {
+        use Instruction as I;
+
+        match instruction {
+            I::Label(_)
+            | I::Line(_)
+            | I::IntCodeEnd(_)
+            | I::Jump(_)
+            | I::Deallocate(_)
+            | I::Trim(_)
+            | I::RemoveMessage(_)
+            | I::Timeout(_)
+            | I::LoopRecEnd(_)
+            | I::Wait(_)
+            | I::RecvMark(_)
+            | I::RecvSet(_)
+            | I::IfEnd(_)
+            | I::Fclearerror(_)
+            | I::Fcheckerror(_)
+            | I::OnLoad(_)
+            | I::NifStart(_)
+            | I::BsPutString(_) => {}
+            I::FuncInfo(x) => self.use_x_range(x.arity),
+
+            // Calls.
+            I::Call(x) => {
+                self.use_x_range(x.arity);
+                self.call();
+            }
+            I::CallOnly(x) => self.use_x_range(x.arity),
+            I::CallLast(x) => self.use_x_range_term(&x.arg1),
+            I::CallExt(x) => {
+                self.use_x_range(x.arity);
+                self.call();
+            }
+            I::CallExtOnly(x) => self.use_x_range(x.arity),
+            I::CallExtLast(x) => self.use_x_range(x.arity),
+            I::CallFun(x) => {
+                self.use_x_range_term_plus(&x.arg1, 1);
+                self.call();
+            }
+            I::CallFun2(x) => {
+                self.use_x_range_term(&x.arg2);
+                self.use_term(&x.arg3);
+                self.call();
+            }
+            I::Apply(x) => {
+                self.use_x_range_term_plus(&x.arg1, 2);
+                self.call();
+            }
+            I::ApplyLast(x) => self.use_x_range_term_plus(&x.arg1, 2),
+            I::Return(_) => self.use_(RegisterId::X(0)),
+
+            // BIFs.
+            I::Bif0(x) => self.def_term(&x.arg2),
+            I::Bif1(x) => {
+                self.use_term(&x.arg3);
+                self.def_term(&x.arg4);
+            }
+            I::Bif2(x) => {
+                self.use_term(&x.arg3);
+                self.use_term(&x.arg4);
+                self.def_term(&x.arg5);
+            }
+            I::GcBif1(x) => {
+                self.live_term(&x.arg2);
+                self.use_term(&x.arg4);
+                self.def_term(&x.arg5);
+            }
+            I::GcBif2(x) => {
+                self.live_term(&x.arg2);
+                self.use_term(&x.arg4);
+                self.use_term(&x.arg5);
+                self.def_term(&x.arg6);
+            }
+            I::GcBif3(x) => {
+                self.live_term(&x.arg2);
+                self.use_term(&x.arg4);
+                self.use_term(&x.arg5);
+                self.use_term(&x.arg6);
+                self.def_term(&x.arg7);
+            }
+
+            // Stack and heap.
+            I::Allocate(x) => self.live(x.live),
+            I::AllocateHeap(x) => self.live(x.live),
+            I::AllocateZero(x) => {
+                self.live_term(&x.arg2);
+                if let Term::Usize(n) = x.arg1 {
+                    self.def_y_range(n);
+                }
+            }
+            I::AllocateHeapZero(x) => {
+                self.live(x.live);
+                if let Allocation::Words(n) = x.stack_need {
+                    self.def_y_range(n);
+                }
+            }
+            I::TestHeap(x) => self.live(x.live),
+            I::Init(x) => self.def_term(&x.arg1),
+            I::InitYregs(x) => {
+                for y in &x.registers {
+                    self.def(RegisterId::Y(y.value));
+                }
+            }
+
+            // Messages.
+            I::Send(_) => {
+                self.use_(RegisterId::X(0));
+                self.use_(RegisterId::X(1));
+                self.def(RegisterId::X(0));
+            }
+            I::LoopRec(x) => self.def_term(&x.arg2),
+            I::WaitTimeout(x) => self.use_term(&x.arg2),
+            I::RecvMarkerBind(x) => {
+                self.use_term(&x.arg1);
+                self.use_term(&x.arg2);
+            }
+            I::RecvMarkerClear(x) => self.use_term(&x.arg1),
+            I::RecvMarkerReserve(x) => self.def_term(&x.arg1),
+            I::RecvMarkerUse(x) => self.use_term(&x.arg1),
+
+            // Arithmetic.
+            I::MPlus(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::MMinus(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::MTimes(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::MDiv(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntDiv(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntRem(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntBand(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntBor(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntBxor(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntBsl(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntBsr(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::IntBnot(x) => self.arith(&[&x.arg2], &x.arg3),
+
+            // Tests.
+            I::IsLt(x) => self.use_terms(&[&x.arg2, &x.arg3]),
+            I::IsGe(x) => self.use_terms(&[&x.arg2, &x.arg3]),
+            I::IsEq(x) => self.use_terms(&[&x.arg2, &x.arg3]),
+            I::IsNe(x) => self.use_terms(&[&x.arg2, &x.arg3]),
+            I::IsEqExact(x) => self.use_terms(&[&x.arg1, &x.arg2]),
+            I::IsNeExact(x) => self.use_terms(&[&x.arg1, &x.arg2]),
+            I::IsInteger(x) => self.use_term(&x.arg1),
+            I::IsFloat(x) => self.use_term(&x.arg1),
+            I::IsNumber(x) => self.use_term(&x.arg1),
+            I::IsAtom(x) => self.use_term(&x.arg1),
+            I::IsPid(x) => self.use_term(&x.arg1),
+            I::IsReference(x) => self.use_term(&x.arg1),
+            I::IsPort(x) => self.use_term(&x.arg1),
+            I::IsNil(x) => self.use_term(&x.arg1),
+            I::IsBinary(x) => self.use_term(&x.arg1),
+            I::IsConstant(x) => self.use_term(&x.arg1),
+            I::IsList(x) => self.use_term(&x.arg1),
+            I::IsNonemptyList(x) => self.use_term(&x.arg1),
+            I::IsTuple(x) => self.use_term(&x.arg1),
+            I::TestArity(x) => self.use_term(&x.arg1),
+            I::IsFunction(x) => self.use_term(&x.arg2),
+            I::IsFunction2(x) => self.use_terms(&[&x.arg2, &x.arg3]),
+            I::IsBoolean(x) => self.use_term(&x.arg2),
+            I::IsBitstr(x) => self.use_term(&x.arg2),
+            I::IsMap(x) => self.use_term(&x.arg2),
+            I::IsTaggedTuple(x) => self.use_register(&x.register),
+            I::HasMapFields(x) => self.use_term(&x.arg2),
+            I::SelectVal(x) => self.use_term(&x.arg),
+            I::SelectTupleArity(x) => self.use_term(&x.arg1),
+
+            // Data construction and deconstruction.
+            I::Move(x) => {
+                self.use_term(&x.src);
+                self.def_register(&x.dst);
+            }
+            I::Swap(x) => {
+                self.use_terms(&[&x.arg1, &x.arg2]);
+                self.def_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::GetList(x) => {
+                self.use_term(&x.source);
+                self.def_register(&x.head);
+                self.def_register(&x.tail);
+            }
+            I::GetHd(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::GetTl(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::GetTupleElement(x) => {
+                self.use_register(&x.source);
+                self.def_register(&x.destination);
+            }
+            I::SetTupleElement(x) => self.use_terms(&[&x.arg1, &x.arg2]),
+            I::GetMapElement(x) => {
+                self.use_term(&x.arg2);
+                if let Term::List(list) = &x.arg3 {
+                    for (i, item) in list.items.iter().enumerate() {
+                        if i % 2 == 0 {
+                            self.use_term(item);
+                        } else {
+                            self.def_term(item);
+                        }
+                    }
+                }
+            }
+            I::PutList(x) => {
+                self.use_terms(&[&x.head, &x.tail]);
+                self.def_register(&x.destination);
+            }
+            I::PutString(x) => self.def_term(&x.arg3),
+            I::PutTuple(x) => self.def_term(&x.arg2),
+            I::Put(x) => self.use_term(&x.arg1),
+            I::PutTuple2(x) => {
+                self.use_list(&x.elements);
+                self.def_register(&x.destination);
+            }
+            I::PutLiteral(x) => self.def_term(&x.arg2),
+            I::PutMapAssoc(x) => {
+                self.live_term(&x.arg4);
+                self.use_terms(&[&x.arg2, &x.arg5]);
+                self.def_term(&x.arg3);
+            }
+            I::PutMapExact(x) => {
+                self.live_term(&x.arg4);
+                self.use_terms(&[&x.arg2, &x.arg5]);
+                self.def_term(&x.arg3);
+            }
+            I::MakeFun(_) | I::MakeFun2(_) => {
+                // The number of free variables is only known from the lambda table.
+                self.def(RegisterId::X(0));
+            }
+            I::MakeFun3(x) => {
+                self.use_term(&x.arg3);
+                self.def_term(&x.arg2);
+            }
+
+            // Exceptions.
+            I::Badmatch(x) => self.use_term(&x.arg1),
+            I::CaseEnd(x) => self.use_term(&x.arg1),
+            I::TryCaseEnd(x) => self.use_term(&x.arg1),
+            I::Badrecord(x) => self.use_term(&x.arg1),
+            I::Catch(x) => self.def_term(&x.arg1),
+            I::CatchEnd(x) => {
+                self.use_term(&x.arg1);
+                self.use_(RegisterId::X(0));
+                self.def_term(&x.arg1);
+                self.def(RegisterId::X(0));
+            }
+            I::Try(x) => self.def_register(&x.register),
+            I::TryEnd(x) => {
+                self.use_register(&x.register);
+                self.def_register(&x.register);
+            }
+            I::TryCase(x) => {
+                self.use_register(&x.register);
+                self.def_register(&x.register);
+                self.def_x_range(3);
+            }
+            I::Raise(x) => self.use_terms(&[&x.stacktrace, &x.exc_value]),
+            I::RawRaise(_) => self.use_x_range(3),
+            I::BuildStacktrace(_) => {
+                self.use_(RegisterId::X(0));
+                self.def(RegisterId::X(0));
+            }
+
+            // Floating point arithmetic.
+            I::Fmove(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::Fconv(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::Fadd(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::Fsub(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::Fmul(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::Fdiv(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg4),
+            I::Fnegate(x) => self.arith(&[&x.arg2], &x.arg3),
+
+            // Binary matching.
+            I::BsStartMatch(x) => self.use_term(&x.arg2),
+            I::BsStartMatch2(x) => {
+                self.live_term(&x.arg3);
+                self.use_term(&x.arg2);
+                self.def_term(&x.arg5);
+            }
+            I::BsStartMatch3(x) => {
+                self.live(x.live);
+                self.use_term(&x.bin);
+                self.def_register(&x.destination);
+            }
+            I::BsStartMatch4(x) => {
+                self.live_term(&x.arg2);
+                self.use_term(&x.arg3);
+                self.def_term(&x.arg4);
+            }
+            I::BsGetInteger(x) => {
+                self.use_term(&x.arg2);
+                self.def_term(&x.arg5);
+            }
+            I::BsGetFloat(x) => {
+                self.use_term(&x.arg2);
+                self.def_term(&x.arg5);
+            }
+            I::BsGetBinary(x) => {
+                self.use_term(&x.arg2);
+                self.def_term(&x.arg5);
+            }
+            I::BsGetInteger2(x) => self.bs_get2(&x.arg2, &x.arg3, &x.arg4, &x.arg7),
+            I::BsGetFloat2(x) => self.bs_get2(&x.arg2, &x.arg3, &x.arg4, &x.arg7),
+            I::BsGetBinary2(x) => self.bs_get2(&x.arg2, &x.arg3, &x.arg4, &x.arg7),
+            I::BsSkipBits(x) => self.use_term(&x.arg2),
+            I::BsSkipBits2(x) => self.use_terms(&[&x.arg2, &x.arg3]),
+            I::BsTestTail(_) | I::BsSave(_) | I::BsRestore(_) => {}
+            I::BsTestTail2(x) => self.use_term(&x.arg2),
+            I::BsSave2(x) => self.use_term(&x.arg1),
+            I::BsRestore2(x) => self.use_term(&x.arg1),
+            I::BsTestUnit(x) => self.use_term(&x.arg2),
+            I::BsMatchString(x) => self.use_term(&x.arg2),
+            I::BsGetUtf8(x) => self.bs_get_utf(&x.arg2, &x.arg3, &x.arg5),
+            I::BsGetUtf16(x) => self.bs_get_utf(&x.arg2, &x.arg3, &x.arg5),
+            I::BsGetUtf32(x) => self.bs_get_utf(&x.arg2, &x.arg3, &x.arg5),
+            I::BsSkipUtf8(x) => {
+                self.live_term(&x.arg3);
+                self.use_term(&x.arg2);
+            }
+            I::BsSkipUtf16(x) => {
+                self.live_term(&x.arg3);
+                self.use_term(&x.arg2);
+            }
+            I::BsSkipUtf32(x) => {
+                self.live_term(&x.arg3);
+                self.use_term(&x.arg2);
+            }
+            I::BsGetTail(x) => {
+                self.live(x.live);
+                self.use_term(&x.context);
+                self.def_register(&x.destination);
+            }
+            I::BsGetPosition(x) => {
+                self.live(x.live);
+                self.use_term(&x.context);
+                self.def_register(&x.destination);
+            }
+            I::BsSetPosition(x) => self.use_terms(&[&x.context, &x.position]),
+            I::BsContextToBinary(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg1);
+            }
+
+            // Binary construction.
+            I::BsInit(_) | I::BsNeedBuf(_) => {}
+            I::BsFinal(x) => self.def_term(&x.arg2),
+            I::BsFinal2(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::BsInit2(x) => {
+                self.live_term(&x.arg4);
+                self.use_term(&x.arg2);
+                self.def_term(&x.arg6);
+            }
+            I::BsInitBits(x) => {
+                self.live_term(&x.arg4);
+                self.use_term(&x.arg2);
+                self.def_term(&x.arg6);
+            }
+            I::BsInitWritable(_) => {
+                self.use_(RegisterId::X(0));
+                self.def(RegisterId::X(0));
+            }
+            I::BsAdd(x) => self.arith(&[&x.arg2, &x.arg3], &x.arg5),
+            I::BsBitsToBytes(x) => self.arith(&[&x.arg2], &x.arg3),
+            I::BsBitsToBytes2(x) => {
+                self.use_term(&x.arg1);
+                self.def_term(&x.arg2);
+            }
+            I::BsUtf8Size(x) => self.arith(&[&x.arg2], &x.arg3),
+            I::BsUtf16Size(x) => self.arith(&[&x.arg2], &x.arg3),
+            I::BsAppend(x) => {
+                self.live_term(&x.arg4);
+                self.use_terms(&[&x.arg2, &x.arg6]);
+                self.def_term(&x.arg8);
+            }
+            I::BsPrivateAppend(x) => {
+                self.use_terms(&[&x.arg2, &x.arg4]);
+                self.def_term(&x.arg6);
+            }
+            I::BsPutInteger(x) => self.use_terms(&[&x.arg2, &x.arg5]),
+            I::BsPutBinary(x) => self.use_terms(&[&x.arg2, &x.arg5]),
+            I::BsPutFloat(x) => self.use_terms(&[&x.arg2, &x.arg5]),
+            I::BsPutUtf8(x) => self.use_term(&x.arg3),
+            I::BsPutUtf16(x) => self.use_term(&x.arg3),
+            I::BsPutUtf32(x) => self.use_term(&x.arg3),
+            I::BsCreateBin(x) => {
+                self.live_term(&x.arg3);
+                self.use_term(&x.arg6);
+                self.def_term(&x.arg5);
+            }
+        }
+    }
+
+    // The callee clobbers all X registers and returns its result in `x0`.
+    fn call(&mut self) {
+        self.def(RegisterId::X(0));
+        self.preserved = Some(0);
+    }
+
+    fn use_(&mut self, register: RegisterId) {
+        self.uses.push(register);
+    }
+
+    fn def(&mut self, register: RegisterId) {
+        self.defs.push(register);
+    }
+
+    fn use_term(&mut self, term: &Term) {
+        if let Term::List(list) = term {
+            self.use_list(list);
+        } else if let Some(register) = RegisterId::from_term(term) {
+            self.use_(register);
+        }
+    }
+
+    fn use_terms(&mut self, terms: &[&Term]) {
+        for term in terms {
+            self.use_term(term);
+        }
+    }
+
+    fn use_list(&mut self, list: &List) {
+        for item in &list.items {
+            self.use_term(item);
+        }
+    }
+
+    fn use_register(&mut self, register: &Register) {
+        self.use_(RegisterId::from_register(register));
+    }
+
+    fn def_term(&mut self, term: &Term) {
+        if let Some(register) = RegisterId::from_term(term) {
+            self.def(register);
+        }
+    }
+
+    fn def_register(&mut self, register: &Register) {
+        self.def(RegisterId::from_register(register));
+    }
+
+    fn use_x_range(&mut self, n: usize) {
+        self.uses.extend((0..n).map(RegisterId::X));
+    }
+
+    fn use_x_range_term(&mut self, n: &Term) {
+        self.use_x_range_term_plus(n, 0);
+    }
+
+    fn use_x_range_term_plus(&mut self, n: &Term, extra: usize) {
+        if let Term::Usize(n) = n {
+            self.use_x_range(n + extra);
+        }
+    }
+
+    // The instruction may trigger a garbage collection that keeps only `x0..n-1`.
+    fn live(&mut self, n: usize) {
+        self.use_x_range(n);
+        self.preserved = Some(n);
+    }
+
+    fn live_term(&mut self, n: &Term) {
+        if let Term::Usize(n) = n {
+            self.live(*n);
+        }
+    }
+
+    fn def_x_range(&mut self, n: usize) {
+        self.defs.extend((0..n).map(RegisterId::X));
+    }
+
+    fn def_y_range(&mut self, n: usize) {
+        self.defs.extend((0..n).map(RegisterId::Y));
+    }
+
+    fn arith(&mut self, args: &[&Term], dst: &Term) {
+        self.use_terms(args);
+        self.def_term(dst);
+    }
+
+    fn bs_get2(&mut self, context: &Term, live: &Term, size: &Term, dst: &Term) {
+        self.live_term(live);
+        self.use_terms(&[context, size]);
+        self.def_term(dst);
+    }
+
+    fn bs_get_utf(&mut self, context: &Term, live: &Term, dst: &Term) {
+        self.live_term(live);
+        self.use_term(context);
+        self.def_term(dst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegisterId::{Fr, X, Y};
+    use super::*;
+    use crate::instruction::*;
+    use crate::term::{self, AllocationList, Atom, XRegister, YRegister};
+
+    fn x(value: usize) -> Term {
+        Term::XRegister(XRegister { value, ty: None })
+    }
+
+    fn y(value: usize) -> Term {
+        Term::YRegister(YRegister { value, ty: None })
+    }
+
+    fn fr(value: usize) -> Term {
+        Term::FloatingPointRegister(term::FloatingPointRegister { value })
+    }
+
+    fn rx(value: usize) -> Register {
+        Register::X(XRegister { value, ty: None })
+    }
+
+    fn ry(value: usize) -> Register {
+        Register::Y(YRegister { value, ty: None })
+    }
+
+    fn u(value: usize) -> Term {
+        Term::Usize(value)
+    }
+
+    fn a(value: usize) -> Term {
+        Term::Atom(Atom { value })
+    }
+
+    fn l(value: usize) -> Term {
+        Term::Label(label(value))
+    }
+
+    fn label(value: usize) -> term::Label {
+        term::Label { value }
+    }
+
+    fn list(items: Vec<Term>) -> Term {
+        Term::List(List { items })
+    }
+
+    fn words(n: usize) -> Allocation {
+        Allocation::Words(n)
+    }
+
+    macro_rules! op {
+        ($name:ident { $($field:ident : $value:expr),* $(,)? }, [$($use:expr),*], [$($def:expr),*]) => {
+            (
+                Instruction::$name($name { $($field: $value),* }),
+                vec![$($use),*],
+                vec![$($def),*],
+            )
+        };
+    }
+
+    #[test]
+    fn defs_and_uses_work() {
+        #[rustfmt::skip]
+        let table: Vec<(Instruction, Vec<RegisterId>, Vec<RegisterId>)> = vec![
+            op!(Label { literal: 1 }, [], []),
+            op!(FuncInfo { module: Atom { value: 1 }, function: Atom { value: 2 }, arity: 2 }, [X(0), X(1)], []),
+            op!(IntCodeEnd {}, [], []),
+            op!(Call { arity: 2, label: label(3) }, [X(0), X(1)], [X(0)]),
+            op!(CallLast { arg1: u(1), arg2: l(3), arg3: u(2) }, [X(0)], []),
+            op!(CallOnly { arity: 1, label: label(3) }, [X(0)], []),
+            op!(CallExt { arity: 1, destination: 0 }, [X(0)], [X(0)]),
+            op!(CallExtLast { arity: 2, destination: 0, deallocate: 1 }, [X(0), X(1)], []),
+            op!(Bif0 { arg1: u(0), arg2: x(3) }, [], [X(3)]),
+            op!(Bif1 { arg1: l(0), arg2: u(0), arg3: y(1), arg4: x(3) }, [Y(1)], [X(3)]),
+            op!(Bif2 { arg1: l(9), arg2: u(0), arg3: x(1), arg4: y(1), arg5: x(3) }, [X(1), Y(1)], [X(3)]),
+            op!(Allocate { stack_need: words(2), live: 2 }, [X(0), X(1)], []),
+            op!(AllocateHeap { stack_need: words(2), heap_need: words(3), live: 1 }, [X(0)], []),
+            op!(AllocateZero { arg1: u(2), arg2: u(1) }, [X(0)], [Y(0), Y(1)]),
+            op!(AllocateHeapZero { stack_need: words(1), heap_need: words(3), live: 1 }, [X(0)], [Y(0)]),
+            op!(TestHeap { heap_need: Allocation::List(AllocationList { items: vec![] }), live: 3 }, [X(0), X(1), X(2)], []),
+            op!(Init { arg1: y(2) }, [], [Y(2)]),
+            op!(Deallocate { n: 1 }, [], []),
+            op!(Return {}, [X(0)], []),
+            op!(Send {}, [X(0), X(1)], [X(0)]),
+            op!(RemoveMessage {}, [], []),
+            op!(Timeout {}, [], []),
+            op!(LoopRec { arg1: l(5), arg2: x(0) }, [], [X(0)]),
+            op!(LoopRecEnd { arg1: l(4) }, [], []),
+            op!(Wait { arg1: l(4) }, [], []),
+            op!(WaitTimeout { arg1: l(4), arg2: y(0) }, [Y(0)], []),
+            op!(MPlus { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(MMinus { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(MTimes { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(MDiv { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntDiv { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntRem { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntBand { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntBor { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntBxor { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntBsl { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntBsr { arg1: l(0), arg2: x(0), arg3: x(1), arg4: x(2) }, [X(0), X(1)], [X(2)]),
+            op!(IntBnot { arg1: l(0), arg2: y(0), arg3: x(2) }, [Y(0)], [X(2)]),
+            op!(IsLt { arg1: l(3), arg2: x(0), arg3: y(1) }, [X(0), Y(1)], []),
+            op!(IsGe { arg1: l(3), arg2: x(0), arg3: u(1) }, [X(0)], []),
+            op!(IsEq { arg1: l(3), arg2: x(0), arg3: x(1) }, [X(0), X(1)], []),
+            op!(IsNe { arg1: l(3), arg2: x(0), arg3: x(1) }, [X(0), X(1)], []),
+            op!(IsEqExact { label: label(3), arg1: x(0), arg2: a(1) }, [X(0)], []),
+            op!(IsNeExact { label: label(3), arg1: y(0), arg2: a(1) }, [Y(0)], []),
+            op!(IsInteger { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsFloat { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsNumber { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsAtom { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsPid { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsReference { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsPort { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsNil { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsBinary { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsConstant { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsList { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsNonemptyList { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(IsTuple { label: label(3), arg1: x(0) }, [X(0)], []),
+            op!(TestArity { label: label(3), arg1: x(0), arity: 2 }, [X(0)], []),
+            op!(SelectVal { arg: x(0), fail_label: label(3), destinations: List { items: vec![a(1), l(4)] } }, [X(0)], []),
+            op!(SelectTupleArity { arg1: y(0), arg2: l(3), arg3: list(vec![u(1), l(4)]) }, [Y(0)], []),
+            op!(Jump { label: label(3) }, [], []),
+            op!(Catch { arg1: y(0), arg2: l(3) }, [], [Y(0)]),
+            op!(CatchEnd { arg1: y(0) }, [X(0), Y(0)], [X(0), Y(0)]),
+            op!(Move { src: x(1), dst: ry(0) }, [X(1)], [Y(0)]),
+            op!(GetList { source: x(0), head: rx(1), tail: rx(0) }, [X(0)], [X(0), X(1)]),
+            op!(GetTupleElement { source: ry(0), element: 1, destination: rx(2) }, [Y(0)], [X(2)]),
+            op!(SetTupleElement { arg1: x(1), arg2: x(0), arg3: u(2) }, [X(0), X(1)], []),
+            op!(PutString { arg1: u(3), arg2: u(0), arg3: x(0) }, [], [X(0)]),
+            op!(PutList { head: x(1), tail: y(0), destination: rx(0) }, [X(1), Y(0)], [X(0)]),
+            op!(PutTuple { arg1: u(2), arg2: x(3) }, [], [X(3)]),
+            op!(Put { arg1: y(1) }, [Y(1)], []),
+            op!(Badmatch { arg1: x(0) }, [X(0)], []),
+            op!(IfEnd {}, [], []),
+            op!(CaseEnd { arg1: x(1) }, [X(1)], []),
+            op!(CallFun { arg1: u(1) }, [X(0), X(1)], [X(0)]),
+            op!(MakeFun { arg1: u(0), arg2: u(0), arg3: u(0) }, [], [X(0)]),
+            op!(IsFunction { arg1: l(3), arg2: x(0) }, [X(0)], []),
+            op!(CallExtOnly { arity: 3, destination: 1 }, [X(0), X(1), X(2)], []),
+            op!(BsStartMatch { arg1: l(3), arg2: x(0) }, [X(0)], []),
+            op!(BsGetInteger { arg1: l(3), arg2: x(1), arg3: u(8), arg4: u(0), arg5: x(2) }, [X(1)], [X(2)]),
+            op!(BsGetFloat { arg1: l(3), arg2: x(1), arg3: u(8), arg4: u(0), arg5: x(2) }, [X(1)], [X(2)]),
+            op!(BsGetBinary { arg1: l(3), arg2: x(1), arg3: u(8), arg4: u(0), arg5: x(2) }, [X(1)], [X(2)]),
+            op!(BsSkipBits { arg1: l(3), arg2: x(1), arg3: u(8), arg4: u(0) }, [X(1)], []),
+            op!(BsTestTail { arg1: l(3), arg2: u(0) }, [], []),
+            op!(BsSave { arg1: u(0) }, [], []),
+            op!(BsRestore { arg1: u(0) }, [], []),
+            op!(BsInit { arg1: u(0), arg2: u(0) }, [], []),
+            op!(BsFinal { arg1: l(0), arg2: x(0) }, [], [X(0)]),
+            op!(BsPutInteger { arg1: l(0), arg2: x(1), arg3: u(1), arg4: u(0), arg5: y(0) }, [X(1), Y(0)], []),
+            op!(BsPutBinary { arg1: l(0), arg2: a(1), arg3: u(8), arg4: u(0), arg5: x(2) }, [X(2)], []),
+            op!(BsPutFloat { arg1: l(0), arg2: u(64), arg3: u(1), arg4: u(0), arg5: x(2) }, [X(2)], []),
+            op!(BsPutString { arg1: u(3), arg2: u(0) }, [], []),
+            op!(BsNeedBuf { arg1: u(8) }, [], []),
+            op!(Fclearerror {}, [], []),
+            op!(Fcheckerror { arg1: l(0) }, [], []),
+            op!(Fmove { arg1: x(0), arg2: fr(0) }, [X(0)], [Fr(0)]),
+            op!(Fconv { arg1: y(0), arg2: fr(1) }, [Y(0)], [Fr(1)]),
+            op!(Fadd { arg1: l(0), arg2: fr(0), arg3: fr(1), arg4: fr(2) }, [Fr(0), Fr(1)], [Fr(2)]),
+            op!(Fsub { arg1: l(0), arg2: fr(0), arg3: fr(1), arg4: fr(2) }, [Fr(0), Fr(1)], [Fr(2)]),
+            op!(Fmul { arg1: l(0), arg2: fr(0), arg3: fr(1), arg4: fr(2) }, [Fr(0), Fr(1)], [Fr(2)]),
+            op!(Fdiv { arg1: l(0), arg2: fr(0), arg3: fr(1), arg4: fr(2) }, [Fr(0), Fr(1)], [Fr(2)]),
+            op!(Fnegate { arg1: l(0), arg2: fr(0), arg3: fr(1) }, [Fr(0)], [Fr(1)]),
+            op!(MakeFun2 { arg1: u(0) }, [], [X(0)]),
+            op!(Try { register: ry(0), label: label(3) }, [], [Y(0)]),
+            op!(TryEnd { register: ry(0) }, [Y(0)], [Y(0)]),
+            op!(TryCase { register: ry(0) }, [Y(0)], [X(0), X(1), X(2), Y(0)]),
+            op!(TryCaseEnd { arg1: x(0) }, [X(0)], []),
+            op!(Raise { stacktrace: x(2), exc_value: x(1) }, [X(1), X(2)], []),
+            op!(BsInit2 { arg1: l(0), arg2: u(8), arg3: u(0), arg4: u(1), arg5: u(0), arg6: x(1) }, [X(0)], [X(1)]),
+            op!(BsBitsToBytes { arg1: l(0), arg2: x(0), arg3: x(1) }, [X(0)], [X(1)]),
+            op!(BsAdd { arg1: l(0), arg2: x(0), arg3: y(0), arg4: u(1), arg5: x(1) }, [X(0), Y(0)], [X(1)]),
+            op!(Apply { arg1: u(1) }, [X(0), X(1), X(2)], [X(0)]),
+            op!(ApplyLast { arg1: u(0), arg2: u(1) }, [X(0), X(1)], []),
+            op!(IsBoolean { arg1: l(3), arg2: x(0) }, [X(0)], []),
+            op!(IsFunction2 { arg1: l(3), arg2: x(0), arg3: u(2) }, [X(0)], []),
+            op!(BsStartMatch2 { arg1: l(3), arg2: x(0), arg3: u(2), arg4: u(0), arg5: x(0) }, [X(0), X(1)], [X(0)]),
+            op!(BsGetInteger2 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: y(0), arg5: u(1), arg6: u(0), arg7: x(1) }, [X(0), Y(0)], [X(1)]),
+            op!(BsGetFloat2 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: u(64), arg5: u(1), arg6: u(0), arg7: x(1) }, [X(0)], [X(1)]),
+            op!(BsGetBinary2 { arg1: l(3), arg2: x(2), arg3: u(2), arg4: a(1), arg5: u(8), arg6: u(0), arg7: x(3) }, [X(0), X(1), X(2)], [X(3)]),
+            op!(BsSkipBits2 { arg1: l(3), arg2: x(0), arg3: x(1), arg4: u(8), arg5: u(0) }, [X(0), X(1)], []),
+            op!(BsTestTail2 { arg1: l(3), arg2: x(0), arg3: u(0) }, [X(0)], []),
+            op!(BsSave2 { arg1: x(0), arg2: u(0) }, [X(0)], []),
+            op!(BsRestore2 { arg1: x(0), arg2: u(0) }, [X(0)], []),
+            op!(GcBif1 { arg1: l(0), arg2: u(2), arg3: u(0), arg4: y(0), arg5: x(2) }, [X(0), X(1), Y(0)], [X(2)]),
+            op!(GcBif2 { arg1: l(0), arg2: u(1), arg3: u(0), arg4: x(3), arg5: y(0), arg6: x(0) }, [X(0), X(3), Y(0)], [X(0)]),
+            op!(BsFinal2 { arg1: x(0), arg2: x(1) }, [X(0)], [X(1)]),
+            op!(BsBitsToBytes2 { arg1: x(0), arg2: x(1) }, [X(0)], [X(1)]),
+            op!(PutLiteral { arg1: u(0), arg2: x(1) }, [], [X(1)]),
+            op!(IsBitstr { arg1: l(3), arg2: x(0) }, [X(0)], []),
+            op!(BsContextToBinary { arg1: x(0) }, [X(0)], [X(0)]),
+            op!(BsTestUnit { arg1: l(3), arg2: x(0), arg3: u(8) }, [X(0)], []),
+            op!(BsMatchString { arg1: l(3), arg2: x(0), arg3: u(8), arg4: u(0) }, [X(0)], []),
+            op!(BsInitWritable {}, [X(0)], [X(0)]),
+            op!(BsAppend { arg1: l(0), arg2: x(1), arg3: u(0), arg4: u(3), arg5: u(8), arg6: y(0), arg7: u(0), arg8: x(0) }, [X(0), X(1), X(2), Y(0)], [X(0)]),
+            op!(BsPrivateAppend { arg1: l(0), arg2: x(1), arg3: u(8), arg4: x(0), arg5: u(0), arg6: x(0) }, [X(0), X(1)], [X(0)]),
+            op!(Trim { arg1: u(1), arg2: u(1) }, [], []),
+            op!(BsInitBits { arg1: l(0), arg2: x(3), arg3: u(0), arg4: u(1), arg5: u(0), arg6: x(1) }, [X(0), X(3)], [X(1)]),
+            op!(BsGetUtf8 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: u(0), arg5: x(1) }, [X(0)], [X(1)]),
+            op!(BsSkipUtf8 { arg1: l(3), arg2: x(1), arg3: u(1), arg4: u(0) }, [X(0), X(1)], []),
+            op!(BsGetUtf16 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: u(0), arg5: y(1) }, [X(0)], [Y(1)]),
+            op!(BsSkipUtf16 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: u(0) }, [X(0)], []),
+            op!(BsGetUtf32 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: u(0), arg5: x(1) }, [X(0)], [X(1)]),
+            op!(BsSkipUtf32 { arg1: l(3), arg2: x(0), arg3: u(1), arg4: u(0) }, [X(0)], []),
+            op!(BsUtf8Size { arg1: l(0), arg2: x(0), arg3: x(1) }, [X(0)], [X(1)]),
+            op!(BsPutUtf8 { arg1: l(0), arg2: u(0), arg3: x(1) }, [X(1)], []),
+            op!(BsUtf16Size { arg1: l(0), arg2: y(0), arg3: x(1) }, [Y(0)], [X(1)]),
+            op!(BsPutUtf16 { arg1: l(0), arg2: u(0), arg3: x(1) }, [X(1)], []),
+            op!(BsPutUtf32 { arg1: l(0), arg2: u(0), arg3: x(1) }, [X(1)], []),
+            op!(OnLoad {}, [], []),
+            op!(RecvMark { arg1: l(3) }, [], []),
+            op!(RecvSet { arg1: l(3) }, [], []),
+            op!(GcBif3 { arg1: l(0), arg2: u(1), arg3: u(0), arg4: x(1), arg5: x(2), arg6: y(0), arg7: x(0) }, [X(0), X(1), X(2), Y(0)], [X(0)]),
+            op!(Line { literal: 1 }, [], []),
+            op!(PutMapAssoc { arg1: l(0), arg2: x(0), arg3: x(0), arg4: u(2), arg5: list(vec![a(1), y(0)]) }, [X(0), X(1), Y(0)], [X(0)]),
+            op!(PutMapExact { arg1: l(0), arg2: y(1), arg3: x(0), arg4: u(0), arg5: list(vec![a(1), x(2)]) }, [X(2), Y(1)], [X(0)]),
+            op!(IsMap { arg1: l(3), arg2: x(0) }, [X(0)], []),
+            op!(HasMapFields { arg1: l(3), arg2: x(0), arg3: list(vec![a(1)]) }, [X(0)], []),
+            op!(GetMapElement { arg1: l(3), arg2: x(0), arg3: list(vec![a(1), x(1), x(2), y(0)]) }, [X(0), X(2)], [X(1), Y(0)]),
+            op!(IsTaggedTuple { label: label(3), register: rx(0), arity: 2, atom: Atom { value: 1 } }, [X(0)], []),
+            op!(BuildStacktrace {}, [X(0)], [X(0)]),
+            op!(RawRaise {}, [X(0), X(1), X(2)], []),
+            op!(GetHd { arg1: x(0), arg2: x(1) }, [X(0)], [X(1)]),
+            op!(GetTl { arg1: x(0), arg2: x(0) }, [X(0)], [X(0)]),
+            op!(PutTuple2 { destination: rx(0), elements: List { items: vec![x(0), a(1), y(2)] } }, [X(0), Y(2)], [X(0)]),
+            op!(BsGetTail { context: x(0), destination: rx(1), live: 3 }, [X(0), X(1), X(2)], [X(1)]),
+            op!(BsStartMatch3 { fail: label(3), bin: x(2), live: 1, destination: rx(2) }, [X(0), X(2)], [X(2)]),
+            op!(BsGetPosition { context: x(1), destination: rx(2), live: 0 }, [X(1)], [X(2)]),
+            op!(BsSetPosition { context: x(1), position: x(2) }, [X(1), X(2)], []),
+            op!(Swap { arg1: x(0), arg2: y(1) }, [X(0), Y(1)], [X(0), Y(1)]),
+            op!(BsStartMatch4 { arg1: a(1), arg2: u(1), arg3: x(0), arg4: x(0) }, [X(0)], [X(0)]),
+            op!(MakeFun3 { arg1: u(0), arg2: x(0), arg3: list(vec![x(0), y(1)]) }, [X(0), Y(1)], [X(0)]),
+            op!(InitYregs { registers: vec![YRegister { value: 0, ty: None }, YRegister { value: 2, ty: None }] }, [], [Y(0), Y(2)]),
+            op!(RecvMarkerBind { arg1: x(0), arg2: y(0) }, [X(0), Y(0)], []),
+            op!(RecvMarkerClear { arg1: y(0) }, [Y(0)], []),
+            op!(RecvMarkerReserve { arg1: x(0) }, [], [X(0)]),
+            op!(RecvMarkerUse { arg1: y(0) }, [Y(0)], []),
+            op!(BsCreateBin { arg1: l(0), arg2: u(0), arg3: u(1), arg4: u(1), arg5: x(0), arg6: list(vec![a(1), u(1), u(0), x(2), u(8)]) }, [X(0), X(2)], [X(0)]),
+            op!(CallFun2 { arg1: a(1), arg2: u(1), arg3: x(1) }, [X(0), X(1)], [X(0)]),
+            op!(NifStart {}, [], []),
+            op!(Badrecord { arg1: y(0) }, [Y(0)], []),
+        ];
+
+        let mut opcodes = Vec::new();
+        for (instruction, uses, defs) in &table {
+            assert_eq!(instruction.uses(), *uses, "{:?}", instruction);
+            assert_eq!(instruction.defs(), *defs, "{:?}", instruction);
+            opcodes.push(instruction.opcode());
+        }
+        assert_eq!(opcodes, (1..=180).collect::<Vec<_>>());
+
+        let preserved = |i: usize| table[i].0.preserved_x_registers();
+        let position = |opcode: u8| opcodes.iter().position(|&x| x == opcode).unwrap();
+        assert_eq!(preserved(position(TestHeap::CODE)), Some(3));
+        assert_eq!(preserved(position(Call::CODE)), Some(0));
+        assert_eq!(preserved(position(Move::CODE)), None);
+    }
+}
@@ -0,0 +1,690 @@
+//! Instruction-level comparison of modules.
+//!
+//! Functions are aligned by name and arity, and their instructions are compared after the
+//! following normalizations, so that unrelated changes elsewhere in a module (or a different
+//! compiler version) do not show up as differences:
+//!
+//! - labels are renumbered from 1 in each function, and calls to other functions are shown as
+//!   `name/arity`;
+//! - atoms are shown by name;
+//! - literals with the same contents are given the same number in both modules;
+//! - imports (e.g., of `call_ext` and `gc_bif2`) are shown as `module:function/arity`;
+//! - type annotations of registers are dropped;
+//! - `line` instructions are dropped unless [`DiffOptions::lines`] is set.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::diff::{self, DiffOptions, Module};
+//!
+//! let old = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 1;
+//!     label 2;
+//!     move {x,0} {x,1};
+//!     return;
+//! };
+//! let new = beam_asm! {
+//!     label 5;
+//!     func_info foo bar 1;
+//!     label 6;
+//!     move {x,0} {x,2};
+//!     return;
+//! };
+//! let atoms = ["foo".to_owned(), "bar".to_owned()];
+//! let old = Module { code: &old, atoms: &atoms, ..Default::default() };
+//! let new = Module { code: &new, atoms: &atoms, ..Default::default() };
+//! let diff = diff::diff(&old, &new, &DiffOptions::default());
+//! assert_eq!(diff.functions[0].changed(), 1);
+//! assert_eq!(
+//!     diff.unified("old", "new", 0),
+//!     "--- old\n+++ new\n@@ -4 +4 @@ bar/1\n-move x(0), x(1)\n+move x(0), x(2)\n"
+//! );
+//! ```
+use crate::cfg;
+use crate::etf::quote_atom;
+use crate::instruction::Instruction;
+use crate::module::Import;
+use crate::operand::Operand;
+use crate::term::{Atom, Label, Register, Term, TypedRegister};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+// Functions whose changed parts are larger than this (in the product of the numbers of
+// instructions) are not aligned instruction by instruction.
+const MAX_TABLE_SIZE: usize = 1 << 24;
+
+/// The code and tables of a module to be compared by [`diff()`].
+///
+/// Missing tables are allowed, in which case atoms, literals and imports are shown by index.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Module<'a> {
+    pub code: &'a [Instruction],
+
+    /// The atom table (`atoms[0]` is the atom with the index 1).
+    pub atoms: &'a [String],
+
+    /// The literal table, each in the external term format.
+    pub literals: &'a [Vec<u8>],
+
+    pub imports: &'a [Import],
+}
+
+/// Options for [`diff()`].
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// If `true`, `line` instructions are compared.
+    ///
+    /// Their operands are indices of the line table, which is not normalized.
+    pub lines: bool,
+}
+
+/// A step of an edit script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Equal(String),
+    Insert(String),
+    Remove(String),
+
+    /// An instruction replaced by another one.
+    Change {
+        old: String,
+        new: String,
+    },
+}
+
+/// The differences of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDiff {
+    pub name: String,
+    pub arity: usize,
+
+    /// The edit script from the old function to the new one.
+    ///
+    /// A function that exists only in the new (old) module consists of [`Edit::Insert`]s
+    /// ([`Edit::Remove`]s).
+    pub edits: Vec<Edit>,
+}
+
+impl FunctionDiff {
+    pub fn is_equal(&self) -> bool {
+        self.edits.iter().all(|x| matches!(x, Edit::Equal(_)))
+    }
+
+    /// Returns the number of inserted instructions.
+    pub fn inserted(&self) -> usize {
+        self.count(|x| matches!(x, Edit::Insert(_)))
+    }
+
+    /// Returns the number of removed instructions.
+    pub fn removed(&self) -> usize {
+        self.count(|x| matches!(x, Edit::Remove(_)))
+    }
+
+    /// Returns the number of changed instructions.
+    pub fn changed(&self) -> usize {
+        self.count(|x| matches!(x, Edit::Change { .. }))
+    }
+
+    fn count(&self, f: impl Fn(&Edit) -> bool) -> usize {
+        self.edits.iter().filter(|x| f(x)).count()
+    }
+
+    // Appends the hunks in the unified format.
+    fn write_unified(&self, out: &mut String, context: usize) {
+        // (prefix, text, the numbers of the old and new lines before it)
+        let mut lines = Vec::new();
+        let (mut old, mut new) = (0, 0);
+        let mut i = 0;
+        while i < self.edits.len() {
+            if let Edit::Equal(text) = &self.edits[i] {
+                lines.push((' ', text, old, new));
+                old += 1;
+                new += 1;
+                i += 1;
+                continue;
+            }
+            let end = self.edits[i..]
+                .iter()
+                .position(|x| matches!(x, Edit::Equal(_)))
+                .map_or(self.edits.len(), |n| i + n);
+            let mut inserted = Vec::new();
+            for edit in &self.edits[i..end] {
+                match edit {
+                    Edit::Remove(text) | Edit::Change { old: text, .. } => {
+                        lines.push(('-', text, old, new));
+                        old += 1;
+                    }
+                    _ => {}
+                }
+                if let Edit::Insert(text) | Edit::Change { new: text, .. } = edit {
+                    inserted.push(text);
+                }
+            }
+            for text in inserted {
+                lines.push(('+', text, old, new));
+                new += 1;
+            }
+            i = end;
+        }
+
+        let changes = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.0 != ' ')
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let mut k = 0;
+        while k < changes.len() {
+            let start = changes[k].saturating_sub(context);
+            let mut last = changes[k];
+            while k + 1 < changes.len() && changes[k + 1] - last <= 2 * context + 1 {
+                k += 1;
+                last = changes[k];
+            }
+            k += 1;
+            let end = (last + context + 1).min(lines.len());
+
+            let hunk = &lines[start..end];
+            let old_len = hunk.iter().filter(|x| x.0 != '+').count();
+            let new_len = hunk.iter().filter(|x| x.0 != '-').count();
+            let _ = writeln!(
+                out,
+                "@@ -{} +{} @@ {}/{}",
+                range(hunk[0].2, old_len),
+                range(hunk[0].3, new_len),
+                self.name,
+                self.arity
+            );
+            for (prefix, text, _, _) in hunk {
+                let _ = writeln!(out, "{prefix}{text}");
+            }
+        }
+    }
+}
+
+// Formats a range of a hunk header (the start is 1-based, or the line before an empty range).
+fn range(before: usize, len: usize) -> String {
+    match len {
+        0 => format!("{before},0"),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{len}", before + 1),
+    }
+}
+
+/// The result of [`diff()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDiff {
+    /// The functions of the old module in order, followed by those only in the new module.
+    pub functions: Vec<FunctionDiff>,
+}
+
+impl ModuleDiff {
+    pub fn is_equal(&self) -> bool {
+        self.functions.iter().all(|x| x.is_equal())
+    }
+
+    /// Formats the differences like `diff -u` with `context` lines around changes.
+    ///
+    /// Line numbers in hunk headers are instruction positions in the (normalized) function, and
+    /// each header ends with the name and arity of the function. An empty string is returned if
+    /// there are no differences.
+    pub fn unified(&self, old_name: &str, new_name: &str, context: usize) -> String {
+        let mut out = String::new();
+        if self.is_equal() {
+            return out;
+        }
+        let _ = writeln!(out, "--- {old_name}");
+        let _ = writeln!(out, "+++ {new_name}");
+        for function in &self.functions {
+            function.write_unified(&mut out, context);
+        }
+        out
+    }
+}
+
+/// Compares two modules function by function.
+///
+/// Both modules must consist of functions (see [`cfg::functions()`]).
+pub fn diff(old: &Module, new: &Module, options: &DiffOptions) -> ModuleDiff {
+    let mut literals = HashMap::new();
+    let old = Normalizer::new(old, options, &mut literals).functions();
+    let new = Normalizer::new(new, options, &mut literals).functions();
+
+    let mut functions = Vec::new();
+    let mut matched = vec![false; new.len()];
+    for (name, arity, old_code) in &old {
+        let j = new.iter().position(|x| x.0 == *name && x.1 == *arity);
+        let edits = match j {
+            Some(j) => {
+                matched[j] = true;
+                edit_script(old_code, &new[j].2)
+            }
+            None => old_code.iter().cloned().map(Edit::Remove).collect(),
+        };
+        functions.push(FunctionDiff {
+            name: name.clone(),
+            arity: *arity,
+            edits,
+        });
+    }
+    for ((name, arity, new_code), _) in new.into_iter().zip(matched).filter(|x| !x.1) {
+        functions.push(FunctionDiff {
+            name,
+            arity,
+            edits: new_code.into_iter().map(Edit::Insert).collect(),
+        });
+    }
+    ModuleDiff { functions }
+}
+
+// Computes a shortest edit script with a longest common subsequence, pairing removed and
+// inserted instructions between the same equal ones as changes.
+fn edit_script(old: &[String], new: &[String]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // Steps over the middle parts: `Some(true)` for equal, `Some(false)` for removed and `None`
+    // for inserted.
+    let mut steps = Vec::new();
+    if (a.len() + 1).saturating_mul(b.len() + 1) <= MAX_TABLE_SIZE {
+        let width = b.len() + 1;
+        let mut table = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                table[i * width + j] = if a[i] == b[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                steps.push(Some(true));
+                i += 1;
+                j += 1;
+            } else if j == b.len()
+                || (i < a.len() && table[(i + 1) * width + j] >= table[i * width + j + 1])
+            {
+                steps.push(Some(false));
+                i += 1;
+            } else {
+                steps.push(None);
+                j += 1;
+            }
+        }
+    } else {
+        steps.extend(a.iter().map(|_| Some(false)));
+        steps.extend(b.iter().map(|_| None));
+    }
+
+    let mut edits = old[..prefix]
+        .iter()
+        .cloned()
+        .map(Edit::Equal)
+        .collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    let mut k = 0;
+    while k < steps.len() {
+        if steps[k] == Some(true) {
+            edits.push(Edit::Equal(a[i].clone()));
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+        let end = steps[k..]
+            .iter()
+            .position(|x| *x == Some(true))
+            .map_or(steps.len(), |n| k + n);
+        let removed = steps[k..end].iter().filter(|x| x.is_some()).count();
+        let inserted = end - k - removed;
+        let changed = removed.min(inserted);
+        for n in 0..changed {
+            edits.push(Edit::Change {
+                old: a[i + n].clone(),
+                new: b[j + n].clone(),
+            });
+        }
+        edits.extend(
+            a[i + changed..i + removed]
+                .iter()
+                .cloned()
+                .map(Edit::Remove),
+        );
+        edits.extend(
+            b[j + changed..j + inserted]
+                .iter()
+                .cloned()
+                .map(Edit::Insert),
+        );
+        i += removed;
+        j += inserted;
+        k = end;
+    }
+    edits.extend(old[old.len() - suffix..].iter().cloned().map(Edit::Equal));
+    edits
+}
+
+// Formats the instructions of a module in the normalized form.
+struct Normalizer<'a, 'b> {
+    module: &'a Module<'a>,
+    options: &'b DiffOptions,
+
+    // Literal contents and the numbers shared by both modules.
+    literals: &'b mut HashMap<&'a [u8], usize>,
+
+    // The entry labels of the functions and their `name/arity`.
+    entries: HashMap<usize, String>,
+
+    // The labels defined in the current function and their new numbers.
+    labels: HashMap<usize, usize>,
+}
+
+impl<'a, 'b> Normalizer<'a, 'b> {
+    fn new(
+        module: &'a Module<'a>,
+        options: &'b DiffOptions,
+        literals: &'b mut HashMap<&'a [u8], usize>,
+    ) -> Self {
+        let mut normalizer = Self {
+            module,
+            options,
+            literals,
+            entries: HashMap::new(),
+            labels: HashMap::new(),
+        };
+        for window in module.code.windows(2) {
+            if let [Instruction::FuncInfo(x), Instruction::Label(label)] = window {
+                let name = format!("{}/{}", normalizer.atom(x.function), x.arity);
+                normalizer.entries.insert(label.literal, name);
+            }
+        }
+        normalizer
+    }
+
+    // Returns the name, arity and normalized instructions of each function.
+    fn functions(mut self) -> Vec<(String, usize, Vec<String>)> {
+        let mut functions = Vec::new();
+        for range in cfg::functions(self.module.code) {
+            let code = &self.module.code[range];
+            let Some((name, arity)) = code.iter().find_map(|x| match x {
+                Instruction::FuncInfo(x) => Some((self.atom(x.function), x.arity)),
+                _ => None,
+            }) else {
+                continue;
+            };
+
+            self.labels.clear();
+            for instruction in code {
+                if let Instruction::Label(x) = instruction {
+                    let next = self.labels.len() + 1;
+                    self.labels.entry(x.literal).or_insert(next);
+                }
+            }
+            let code = code
+                .iter()
+                .filter(|x| self.options.lines || !matches!(x, Instruction::Line(_)))
+                .map(|x| self.instruction(x))
+                .collect();
+            functions.push((name, arity, code));
+        }
+        functions
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) -> String {
+        if let Instruction::Label(x) = instruction {
+            return format!("label {}", self.labels[&x.literal]);
+        }
+        let import = import_operand(instruction);
+        let mut text = instruction.name().to_owned();
+        for (i, operand) in instruction.operands().enumerate() {
+            text.push_str(if i == 0 { " " } else { ", " });
+            let index = match operand {
+                Operand::Usize(x) | Operand::Term(Term::Usize(x)) => Some(*x),
+                _ => None,
+            };
+            match (import == Some(i), index) {
+                (true, Some(index)) => text.push_str(&self.import(index)),
+                _ => text.push_str(&self.operand(operand)),
+            }
+        }
+        text
+    }
+
+    fn operand(&mut self, operand: Operand) -> String {
+        match operand {
+            Operand::Atom(x) => self.atom(*x),
+            Operand::Label(x) => self.label(*x),
+            Operand::Term(x) => self.term(x),
+            Operand::Register(x) => match x {
+                Register::X(x) => format!("x({})", x.value),
+                Register::Y(x) => format!("y({})", x.value),
+                Register::Typed(x) => typed_register(x),
+            },
+            Operand::List(x) => self.list(&x.items),
+            Operand::YRegisters(x) => {
+                let items = x.iter().map(|x| format!("y({})", x.value));
+                format!("[{}]", items.collect::<Vec<_>>().join(", "))
+            }
+            Operand::Usize(_) | Operand::Allocation(_) => operand.to_string(),
+        }
+    }
+
+    fn term(&mut self, term: &Term) -> String {
+        match term {
+            Term::Atom(x) => self.atom(*x),
+            Term::Label(x) => self.label(*x),
+            Term::XRegister(x) => format!("x({})", x.value),
+            Term::YRegister(x) => format!("y({})", x.value),
+            Term::TypedRegister(x) => typed_register(x),
+            Term::List(x) => self.list(&x.items),
+            Term::Literal(x) => match self.module.literals.get(x.value) {
+                Some(bytes) => {
+                    let next = self.literals.len();
+                    format!("literal({})", self.literals.entry(bytes).or_insert(next))
+                }
+                None => x.to_string(),
+            },
+            _ => term.to_string(),
+        }
+    }
+
+    fn list(&mut self, items: &[Term]) -> String {
+        let items = items.iter().map(|x| self.term(x)).collect::<Vec<_>>();
+        format!("[{}]", items.join(", "))
+    }
+
+    fn label(&self, label: Label) -> String {
+        if let Some(n) = self.labels.get(&label.value) {
+            format!("f({n})")
+        } else if let Some(name) = self.entries.get(&label.value) {
+            name.clone()
+        } else {
+            label.to_string()
+        }
+    }
+
+    fn atom(&self, atom: Atom) -> String {
+        match atom
+            .value
+            .checked_sub(1)
+            .and_then(|i| self.module.atoms.get(i))
+        {
+            Some(name) => quote_atom(name),
+            None => atom.to_string(),
+        }
+    }
+
+    fn import(&self, index: usize) -> String {
+        match self.module.imports.get(index) {
+            Some(x) => format!(
+                "{}:{}/{}",
+                quote_atom(&x.module),
+                quote_atom(&x.function),
+                x.arity
+            ),
+            None => format!("import({index})"),
+        }
+    }
+}
+
+fn typed_register(register: &TypedRegister) -> String {
+    match register {
+        TypedRegister::X { register, .. } => format!("x({})", register.value),
+        TypedRegister::Y { register, .. } => format!("y({})", register.value),
+    }
+}
+
+// Returns the position of the operand that is an index of the import table.
+fn import_operand(instruction: &Instruction) -> Option<usize> {
+    use Instruction as I;
+
+    match instruction {
+        I::CallExt(_) | I::CallExtLast(_) | I::CallExtOnly(_) => Some(1),
+        I::Bif0(_) => Some(0),
+        I::Bif1(_) | I::Bif2(_) => Some(1),
+        I::GcBif1(_) | I::GcBif2(_) | I::GcBif3(_) => Some(2),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    #[test]
+    fn diff_works() {
+        let old = beam_asm! {
+            label 1;
+            func_info m foo 1;
+            label 2;
+            is_atom {f,3} {x,0};
+            move {literal,0} {x,0};
+            call_ext_only 1 0;
+            label 3;
+            call_only 1 {f,5};
+            label 4;
+            func_info m bar 1;
+            label 5;
+            line 1;
+            return;
+            label 6;
+            func_info m baz 0;
+            label 7;
+            return;
+        };
+        let new = beam_asm! {
+            label 11;
+            func_info m bar 1;
+            label 12;
+            line 7;
+            return;
+            label 13;
+            func_info m foo 1;
+            label 14;
+            is_integer {f,15} {x,0};
+            move {literal,1} {x,0};
+            move {atom,5} {x,1};
+            call_ext_only 1 1;
+            label 15;
+            call_only 1 {f,12};
+            label 16;
+            func_info m qux 0;
+            label 17;
+            return;
+        };
+        let old_atoms = ["m", "foo", "bar", "baz"].map(String::from);
+        let new_atoms = ["m", "bar", "foo", "qux", "Foo"].map(String::from);
+        let old_literals = [vec![131, 106]];
+        let new_literals = [vec![131, 97, 1], vec![131, 106]];
+        let old_imports = [Import::new("lists", "reverse", 1)];
+        let new_imports = [
+            Import::new("erlang", "+", 2),
+            Import::new("lists", "reverse", 1),
+        ];
+        let old = Module {
+            code: &old,
+            atoms: &old_atoms,
+            literals: &old_literals,
+            imports: &old_imports,
+        };
+        let new = Module {
+            code: &new,
+            atoms: &new_atoms,
+            literals: &new_literals,
+            imports: &new_imports,
+        };
+        let diff = diff(&old, &new, &DiffOptions::default());
+        let functions = diff
+            .functions
+            .iter()
+            .map(|x| {
+                (
+                    format!("{}/{}", x.name, x.arity),
+                    x.changed(),
+                    x.inserted(),
+                    x.removed(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            functions,
+            [
+                ("foo/1".to_owned(), 1, 1, 0),
+                ("bar/1".to_owned(), 0, 0, 0),
+                ("baz/0".to_owned(), 0, 0, 4),
+                ("qux/0".to_owned(), 0, 4, 0),
+            ]
+        );
+        assert_eq!(
+            diff.unified("a", "b", 1),
+            [
+                "--- a",
+                "+++ b",
+                "@@ -3,4 +3,5 @@ foo/1",
+                " label 2",
+                "-is_atom f(3), x(0)",
+                "+is_integer f(3), x(0)",
+                " move literal(0), x(0)",
+                "+move 'Foo', x(1)",
+                " call_ext_only 1, lists:reverse/1",
+                "@@ -1,4 +0,0 @@ baz/0",
+                "-label 1",
+                "-func_info m, baz, 0",
+                "-label 2",
+                "-return",
+                "@@ -0,0 +1,4 @@ qux/0",
+                "+label 1",
+                "+func_info m, qux, 0",
+                "+label 2",
+                "+return",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            diff.functions[0].edits[6],
+            Edit::Equal("call_ext_only 1, lists:reverse/1".to_owned())
+        );
+        assert_eq!(
+            diff.functions[0].edits[8],
+            Edit::Equal("call_only 1, bar/1".to_owned())
+        );
+
+        let options = DiffOptions { lines: true };
+        assert!(super::diff(&old, &old, &options).is_equal());
+        assert_eq!(super::diff(&old, &new, &options).functions[1].changed(), 1);
+    }
+}
@@ -0,0 +1,228 @@
+//! Textual representation of instructions.
+//!
+//! Instructions are formatted as their `genop.tab` name followed by comma-separated operands
+//! (e.g., `is_nil f(3), x(0)`). Atoms, literals and imports are shown as indices, because the
+//! tables that resolve them live outside of the code chunk.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::instruction::{self, Instruction};
+//! use beamcode::term::{Label, Term, XRegister};
+//!
+//! let instruction = Instruction::IsNil(instruction::IsNil {
+//!     label: Label { value: 3 },
+//!     arg1: Term::XRegister(XRegister { value: 0, ty: None }),
+//! });
+//! assert_eq!(instruction.to_string(), "is_nil f(3), x(0)");
+//! ```
+use crate::instruction::Instruction;
+use crate::operand::Operand;
+use crate::term::{
+    Allocation, AllocationList, AllocationListItem, Atom, FloatingPointRegister, Label, List,
+    Literal, Register, Term, TypedRegister, XRegister, YRegister,
+};
+use std::fmt;
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        for (i, operand) in self.operands().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{separator}{operand}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usize(x) => write!(f, "{x}"),
+            Self::Atom(x) => write!(f, "{x}"),
+            Self::Label(x) => write!(f, "{x}"),
+            Self::Term(x) => write!(f, "{x}"),
+            Self::Register(x) => write!(f, "{x}"),
+            Self::Allocation(x) => write!(f, "{x}"),
+            Self::List(x) => write!(f, "{x}"),
+            Self::YRegisters(x) => write_list(f, x.iter()),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usize(x) => write!(f, "{x}"),
+            Self::Integer(x) => write!(f, "{x}"),
+            Self::Atom(x) => write!(f, "{x}"),
+            Self::XRegister(x) => write!(f, "{x}"),
+            Self::YRegister(x) => write!(f, "{x}"),
+            Self::Label(x) => write!(f, "{x}"),
+            Self::Character(x) => write!(f, "{x:?}"),
+            Self::List(x) => write!(f, "{x}"),
+            Self::FloatingPointRegister(x) => write!(f, "{x}"),
+            Self::AllocationList(x) => write!(f, "{x}"),
+            Self::Literal(x) => write!(f, "{x}"),
+            Self::TypedRegister(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl fmt::Display for Allocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Words(x) => write!(f, "{x}"),
+            Self::List(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl fmt::Display for AllocationList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "alloc")?;
+        write_list(f, self.items.iter())
+    }
+}
+
+impl fmt::Display for AllocationListItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Words(x) => write!(f, "words({x})"),
+            Self::Floats(x) => write!(f, "floats({x})"),
+            Self::Funs(x) => write!(f, "funs({x})"),
+        }
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.value == 0 {
+            write!(f, "nil")
+        } else {
+            write!(f, "atom({})", self.value)
+        }
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "f({})", self.value)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "literal({})", self.value)
+    }
+}
+
+impl fmt::Display for XRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x({})", self.value)?;
+        write_type(f, self.ty)
+    }
+}
+
+impl fmt::Display for YRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "y({})", self.value)?;
+        write_type(f, self.ty)
+    }
+}
+
+impl fmt::Display for FloatingPointRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fr({})", self.value)
+    }
+}
+
+impl fmt::Display for TypedRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::X { register, ty } => write!(f, "x({}):t({ty})", register.value),
+            Self::Y { register, ty } => write!(f, "y({}):t({ty})", register.value),
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::X(x) => write!(f, "{x}"),
+            Self::Y(x) => write!(f, "{x}"),
+            Self::Typed(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, self.items.iter())
+    }
+}
+
+fn write_type(f: &mut fmt::Formatter<'_>, ty: Option<usize>) -> fmt::Result {
+    if let Some(ty) = ty {
+        write!(f, ":t({ty})")?;
+    }
+    Ok(())
+}
+
+fn write_list<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl Iterator<Item = T>,
+) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, item) in items.enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    write!(f, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction;
+
+    #[test]
+    fn display_works() {
+        let instruction = Instruction::SelectVal(instruction::SelectVal {
+            arg: Term::YRegister(YRegister {
+                value: 1,
+                ty: Some(2),
+            }),
+            fail_label: Label { value: 2 },
+            destinations: List {
+                items: vec![
+                    Term::Atom(Atom { value: 3 }),
+                    Term::Label(Label { value: 4 }),
+                    Term::Integer(10.into()),
+                    Term::Label(Label { value: 5 }),
+                ],
+            },
+        });
+        assert_eq!(
+            instruction.to_string(),
+            "select_val y(1):t(2), f(2), [atom(3), f(4), 10, f(5)]"
+        );
+        assert_eq!(
+            Instruction::Return(instruction::Return {}).to_string(),
+            "return"
+        );
+        assert_eq!(
+            Instruction::AllocateHeap(instruction::AllocateHeap {
+                stack_need: Allocation::Words(1),
+                heap_need: Allocation::List(AllocationList {
+                    items: vec![AllocationListItem::Words(2), AllocationListItem::Floats(1)]
+                }),
+                live: 0
+            })
+            .to_string(),
+            "allocate_heap 1, alloc[words(2), floats(1)], 0"
+        );
+    }
+}
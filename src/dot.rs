@@ -0,0 +1,244 @@
+//! [Graphviz](https://graphviz.org/) DOT output of control-flow graphs and call graphs.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::cfg::Cfg;
+//! use beamcode::dot;
+//! use beamcode::instruction::{self, Instruction};
+//!
+//! let code = vec![
+//!     Instruction::Label(instruction::Label { literal: 1 }),
+//!     Instruction::Return(instruction::Return {}),
+//! ];
+//! let mut buf = Vec::new();
+//! dot::write_cfg(&mut buf, &Cfg::new(&code), &code).unwrap();
+//! assert!(String::from_utf8(buf).unwrap().starts_with("digraph cfg {"));
+//! ```
+use crate::call_graph::{CallGraph, CallKind, Callee};
+use crate::cfg::{BlockId, Cfg, EdgeKind};
+use crate::etf::quote_atom;
+use crate::flow::Successors;
+use crate::instruction::Instruction;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Writes the control-flow graph of a function.
+///
+/// `instructions` must be the slice that was passed to [`Cfg::new()`].
+/// Each node lists the disassembled instructions of a basic block.
+/// Edges are labelled `true` / `false` for conditional branches, with the matched values
+/// (or `fail`) for `select_val` and `select_tuple_arity`, and `exception` for `try` and `catch`.
+pub fn write_cfg<W: Write>(
+    writer: &mut W,
+    cfg: &Cfg,
+    instructions: &[Instruction],
+) -> std::io::Result<()> {
+    writeln!(writer, "digraph cfg {{")?;
+    writeln!(writer, "  node [shape=box, fontname=\"monospace\"];")?;
+    for (i, block) in cfg.blocks().iter().enumerate() {
+        let mut label = String::new();
+        for instruction in &instructions[block.range.clone()] {
+            label.push_str(&escape(&instruction.to_string()));
+            label.push_str("\\l");
+        }
+        let peripheries = if BlockId(i) == cfg.entry() {
+            ", peripheries=2"
+        } else {
+            ""
+        };
+        writeln!(writer, "  b{i} [label=\"{label}\"{peripheries}];")?;
+    }
+
+    for (i, block) in cfg.blocks().iter().enumerate() {
+        let successors = block
+            .range
+            .clone()
+            .last()
+            .map_or(Successors::FallThrough, |i| instructions[i].successors());
+        let select_labels = select_labels(cfg, &successors);
+        for edge in &block.successors {
+            let target = edge.target.0;
+            let attrs = match edge.kind {
+                EdgeKind::FallThrough if matches!(successors, Successors::Branch(_)) => {
+                    " [label=\"true\"]".to_owned()
+                }
+                EdgeKind::FallThrough | EdgeKind::Jump => String::new(),
+                EdgeKind::Branch => " [label=\"false\"]".to_owned(),
+                EdgeKind::Select => {
+                    let label = select_labels.get(&edge.target).map_or("", |s| s.as_str());
+                    format!(" [label=\"{}\"]", escape(label))
+                }
+                EdgeKind::Exception => " [label=\"exception\", style=dashed]".to_owned(),
+            };
+            writeln!(writer, "  b{i} -> b{target}{attrs};")?;
+        }
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+fn select_labels(cfg: &Cfg, successors: &Successors) -> HashMap<BlockId, String> {
+    let mut labels = HashMap::<BlockId, Vec<String>>::new();
+    if let Successors::Select { fail, destinations } = successors {
+        for (value, label) in destinations {
+            if let Some(id) = cfg.block_of_label(*label) {
+                labels.entry(id).or_default().push(value.to_string());
+            }
+        }
+        if let Some(id) = cfg.block_of_label(*fail) {
+            labels.entry(id).or_default().push("fail".to_owned());
+        }
+    }
+    labels
+        .into_iter()
+        .map(|(id, values)| (id, values.join(", ")))
+        .collect()
+}
+
+/// Writes the call graph of a module.
+///
+/// Tail calls are labelled `tail` and fun creations are drawn as dashed edges labelled `fun`.
+/// Functions are named by `atoms` (the atom table, which may be empty to show atoms by index).
+/// Imports and unresolved lambdas are shown as indices into their tables.
+pub fn write_call_graph<W: Write>(
+    writer: &mut W,
+    graph: &CallGraph,
+    atoms: &[String],
+) -> std::io::Result<()> {
+    writeln!(writer, "digraph call_graph {{")?;
+    writeln!(writer, "  node [shape=box];")?;
+    for (i, function) in graph.functions().iter().enumerate() {
+        let name = match function
+            .name
+            .value
+            .checked_sub(1)
+            .and_then(|x| atoms.get(x))
+        {
+            Some(name) => quote_atom(name),
+            None => function.name.to_string(),
+        };
+        let label = format!("{name}/{}", function.arity);
+        writeln!(writer, "  f{i} [label=\"{}\"];", escape(&label))?;
+    }
+
+    let mut external = Vec::new();
+    let mut edges = Vec::new();
+    for call in graph.calls() {
+        let edge = (call.caller, call.callee, call.kind);
+        if edges.contains(&edge) {
+            continue;
+        }
+        edges.push(edge);
+        if !matches!(call.callee, Callee::Local(_)) && !external.contains(&call.callee) {
+            external.push(call.callee);
+        }
+    }
+    external.sort();
+    for callee in external {
+        let (id, label) = node(callee);
+        writeln!(writer, "  {id} [label=\"{label}\", shape=ellipse];")?;
+    }
+    for (caller, callee, kind) in edges {
+        let attrs = match kind {
+            CallKind::Call => "",
+            CallKind::TailCall => " [label=\"tail\"]",
+            CallKind::MakeFun => " [label=\"fun\", style=dashed]",
+        };
+        writeln!(writer, "  f{caller} -> {}{attrs};", node(callee).0)?;
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+fn node(callee: Callee) -> (String, String) {
+    match callee {
+        Callee::Local(i) => (format!("f{i}"), String::new()),
+        Callee::Import(i) => (format!("import{i}"), format!("import({i})")),
+        Callee::Lambda(i) => (format!("lambda{i}"), format!("lambda({i})")),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction;
+    use crate::term::{Atom, Label, List, Term, XRegister};
+
+    #[test]
+    fn write_works() {
+        let x0 = Term::XRegister(XRegister { value: 0, ty: None });
+        let code = vec![
+            Instruction::Label(instruction::Label { literal: 1 }),
+            Instruction::FuncInfo(instruction::FuncInfo {
+                module: Atom { value: 1 },
+                function: Atom { value: 2 },
+                arity: 1,
+            }),
+            Instruction::Label(instruction::Label { literal: 2 }),
+            Instruction::SelectVal(instruction::SelectVal {
+                arg: x0.clone(),
+                fail_label: Label { value: 1 },
+                destinations: List {
+                    items: vec![
+                        Term::Atom(Atom { value: 3 }),
+                        Term::Label(Label { value: 3 }),
+                    ],
+                },
+            }),
+            Instruction::Label(instruction::Label { literal: 3 }),
+            Instruction::CallExtOnly(instruction::CallExtOnly {
+                arity: 1,
+                destination: 0,
+            }),
+            Instruction::IntCodeEnd(instruction::IntCodeEnd {}),
+        ];
+
+        let cfg = Cfg::new(&code[0..6]);
+        let mut buf = Vec::new();
+        write_cfg(&mut buf, &cfg, &code[0..6]).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"digraph cfg {
+  node [shape=box, fontname="monospace"];
+  b0 [label="label 1\lfunc_info atom(1), atom(2), 1\l"];
+  b1 [label="label 2\lselect_val x(0), f(1), [atom(3), f(3)]\l", peripheries=2];
+  b2 [label="label 3\lcall_ext_only 1, 0\l"];
+  b1 -> b0 [label="fail"];
+  b1 -> b2 [label="atom(3)"];
+}
+"#
+        );
+
+        let mut buf = Vec::new();
+        write_call_graph(&mut buf, &CallGraph::new(&code, &[]), &[]).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"digraph call_graph {
+  node [shape=box];
+  f0 [label="atom(2)/1"];
+  import0 [label="import(0)", shape=ellipse];
+  f0 -> import0 [label="tail"];
+}
+"#
+        );
+
+        let atoms = ["foo".to_owned(), "Bar".to_owned()];
+        let mut buf = Vec::new();
+        write_call_graph(&mut buf, &CallGraph::new(&code, &[]), &atoms).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"digraph call_graph {
+  node [shape=box];
+  f0 [label="'Bar'/1"];
+  import0 [label="import(0)", shape=ellipse];
+  f0 -> import0 [label="tail"];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Decoding of terms in the external term format.
+//!
+//! BEAM files store literals (`LitT`) and the `Attr` and `CInf` chunks in this format.
+//! Only the tags produced by `term_to_binary/1` for plain data are supported (e.g., funs,
+//! pids and compressed terms are not).
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::etf::{self, Term};
+//!
+//! let term = etf::decode(&[131, 104, 2, 119, 2, 111, 107, 97, 1]).unwrap();
+//! assert_eq!(term, Term::Tuple(vec![Term::Atom("ok".to_owned()), Term::Integer(1.into())]));
+//! assert_eq!(term.to_string(), "{ok,1}");
+//! ```
+use crate::DecodeError;
+use byteorder::{BigEndian, ReadBytesExt as _};
+use num::BigInt;
+use std::fmt;
+
+/// The version byte that precedes an encoded term.
+pub const VERSION: u8 = 131;
+
+/// A decoded term.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Atom(String),
+    Integer(BigInt),
+    Float(f64),
+    Tuple(Vec<Term>),
+
+    /// The elements and the tail (`Term::Nil` for proper lists) of a non-empty list.
+    List(Vec<Term>, Box<Term>),
+
+    Nil,
+    Binary(Vec<u8>),
+    Map(Vec<(Term, Term)>),
+}
+
+impl Term {
+    /// Returns the name if this is an atom.
+    pub fn as_atom(&self) -> Option<&str> {
+        match self {
+            Self::Atom(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this is a proper list (including `[]`).
+    pub fn as_list(&self) -> Option<&[Term]> {
+        match self {
+            Self::Nil => Some(&[]),
+            Self::List(x, tail) if **tail == Self::Nil => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn join(f: &mut fmt::Formatter<'_>, terms: &[Term]) -> fmt::Result {
+            for (i, term) in terms.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{term}")?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Self::Atom(x) => write!(f, "{}", quote_atom(x)),
+            Self::Integer(x) => write!(f, "{x}"),
+            Self::Float(x) => write!(f, "{x:?}"),
+            Self::Tuple(x) => {
+                write!(f, "{{")?;
+                join(f, x)?;
+                write!(f, "}}")
+            }
+            Self::List(x, tail) => {
+                write!(f, "[")?;
+                join(f, x)?;
+                if **tail != Self::Nil {
+                    write!(f, "|{tail}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Nil => write!(f, "[]"),
+            Self::Binary(x) => {
+                write!(f, "<<")?;
+                for (i, byte) in x.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { "," };
+                    write!(f, "{separator}{byte}")?;
+                }
+                write!(f, ">>")
+            }
+            Self::Map(x) => {
+                write!(f, "#{{")?;
+                for (i, (key, value)) in x.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { "," };
+                    write!(f, "{separator}{key} => {value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Quotes an atom name as Erlang does if needed (e.g., `'Elixir.Foo'`).
+pub fn quote_atom(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@');
+    if plain {
+        name.to_owned()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Decodes a term preceded by [`VERSION`].
+pub fn decode(bytes: &[u8]) -> Result<Term, DecodeError> {
+    let mut reader = bytes;
+    let version = reader.read_u8()?;
+    if version != VERSION {
+        return Err(DecodeError::UnknownExternalTermFormatVersion { version });
+    }
+    decode_term(&mut reader)
+}
+
+fn decode_term(reader: &mut &[u8]) -> Result<Term, DecodeError> {
+    let term = match reader.read_u8()? {
+        97 => Term::Integer(BigInt::from(reader.read_u8()?)),
+        98 => Term::Integer(BigInt::from(reader.read_i32::<BigEndian>()?)),
+        70 => Term::Float(reader.read_f64::<BigEndian>()?),
+        100 => {
+            let len = reader.read_u16::<BigEndian>()?;
+            Term::Atom(latin1(&read_bytes(reader, len.into())?))
+        }
+        115 => {
+            let len = reader.read_u8()?;
+            Term::Atom(latin1(&read_bytes(reader, len.into())?))
+        }
+        118 => {
+            let len = reader.read_u16::<BigEndian>()?;
+            Term::Atom(String::from_utf8_lossy(&read_bytes(reader, len.into())?).into_owned())
+        }
+        119 => {
+            let len = reader.read_u8()?;
+            Term::Atom(String::from_utf8_lossy(&read_bytes(reader, len.into())?).into_owned())
+        }
+        104 => {
+            let n = reader.read_u8()?;
+            Term::Tuple(decode_terms(reader, n.into())?)
+        }
+        105 => {
+            let n = reader.read_u32::<BigEndian>()?;
+            Term::Tuple(decode_terms(reader, n as usize)?)
+        }
+        106 => Term::Nil,
+        107 => {
+            let len = reader.read_u16::<BigEndian>()?;
+            let bytes = read_bytes(reader, len.into())?;
+            let elements = bytes.into_iter().map(|x| Term::Integer(BigInt::from(x)));
+            Term::List(elements.collect(), Box::new(Term::Nil))
+        }
+        108 => {
+            let n = reader.read_u32::<BigEndian>()?;
+            let elements = decode_terms(reader, n as usize)?;
+            Term::List(elements, Box::new(decode_term(reader)?))
+        }
+        109 => {
+            let len = reader.read_u32::<BigEndian>()?;
+            Term::Binary(read_bytes(reader, len as usize)?)
+        }
+        110 => {
+            let len = reader.read_u8()?;
+            decode_big(reader, len.into())?
+        }
+        111 => {
+            let len = reader.read_u32::<BigEndian>()?;
+            decode_big(reader, len as usize)?
+        }
+        116 => {
+            let n = reader.read_u32::<BigEndian>()?;
+            let entries = (0..n)
+                .map(|_| Ok((decode_term(reader)?, decode_term(reader)?)))
+                .collect::<Result<_, DecodeError>>()?;
+            Term::Map(entries)
+        }
+        tag => return Err(DecodeError::UnsupportedExternalTermTag { tag }),
+    };
+    Ok(term)
+}
+
+fn decode_terms(reader: &mut &[u8], n: usize) -> Result<Vec<Term>, DecodeError> {
+    (0..n).map(|_| decode_term(reader)).collect()
+}
+
+fn decode_big(reader: &mut &[u8], len: usize) -> Result<Term, DecodeError> {
+    let sign = if reader.read_u8()? == 0 {
+        num::bigint::Sign::Plus
+    } else {
+        num::bigint::Sign::Minus
+    };
+    let digits = read_bytes(reader, len)?;
+    Ok(Term::Integer(BigInt::from_bytes_le(sign, &digits)))
+}
+
+fn read_bytes(reader: &mut &[u8], len: usize) -> Result<Vec<u8>, DecodeError> {
+    if reader.len() < len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes.to_owned())
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&x| char::from(x)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_works() {
+        // [{a,<<1,2>>},"hi",-300,1.5,#{'B' => []}|x] (with a small big integer).
+        let bytes = [
+            131, 108, 0, 0, 0, 5, 104, 2, 100, 0, 1, 97, 109, 0, 0, 0, 2, 1, 2, 107, 0, 2, 104,
+            105, 98, 255, 255, 254, 212, 70, 63, 248, 0, 0, 0, 0, 0, 0, 116, 0, 0, 0, 1, 115, 1,
+            66, 106, 119, 1, 120,
+        ];
+        let term = decode(&bytes).unwrap();
+        assert_eq!(
+            term.to_string(),
+            "[{a,<<1,2>>},[104,105],-300,1.5,#{'B' => []}|x]"
+        );
+        assert_eq!(term.as_list(), None);
+
+        let big = decode(&[131, 110, 2, 1, 0, 1]).unwrap();
+        assert_eq!(big, Term::Integer(BigInt::from(-256)));
+        assert!(matches!(
+            decode(&[131, 112]),
+            Err(DecodeError::UnsupportedExternalTermTag { tag: 112 })
+        ));
+        assert!(decode(&[131, 109, 0, 0, 0, 9, 1]).is_err());
+    }
+}
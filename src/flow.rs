@@ -0,0 +1,293 @@
+//! Control-flow classification of instructions.
+//!
+//! Note that almost every instruction may raise an exception (e.g., `badarg` from a BIF without a
+//! fail label). Such implicit exceptions are not reported by [`Instruction::successors()`].
+use crate::instruction::Instruction;
+use crate::term::{Label, Term};
+
+/// How the control flows after an instruction is executed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Successors {
+    /// Continues with the next instruction.
+    FallThrough,
+
+    /// Unconditionally jumps to the label.
+    Jump(Label),
+
+    /// Either continues with the next instruction or jumps to the (fail) label.
+    Branch(Label),
+
+    /// Jumps to the label associated with the matched value, or to `fail` if none matched.
+    Select {
+        fail: Label,
+        destinations: Vec<(Term, Label)>,
+    },
+
+    /// Returns to the caller.
+    Return,
+
+    /// Calls a function without returning to the current one.
+    TailCall,
+
+    /// Raises an exception.
+    Raise,
+
+    /// Reaches the end of the code.
+    End,
+}
+
+impl Successors {
+    /// Returns `true` if the next instruction can be executed after the instruction.
+    pub fn falls_through(&self) -> bool {
+        matches!(self, Self::FallThrough | Self::Branch(_))
+    }
+
+    /// Returns `true` if the instruction ends a basic block.
+    pub fn is_terminator(&self) -> bool {
+        !matches!(self, Self::FallThrough)
+    }
+
+    /// Returns the labels that the instruction can jump to.
+    pub fn labels(&self) -> Vec<Label> {
+        match self {
+            Self::Jump(label) | Self::Branch(label) => vec![*label],
+            Self::Select { fail, destinations } => std::iter::once(*fail)
+                .chain(destinations.iter().map(|(_, label)| *label))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Instruction {
+    /// Classifies the control flow after this instruction.
+    pub fn successors(&self) -> Successors {
+        use Instruction as I;
+
+        match self {
+            I::Jump(x) => Successors::Jump(x.label),
+            I::LoopRecEnd(x) => jump(&x.arg1),
+            I::Wait(x) => jump(&x.arg1),
+
+            I::SelectVal(x) => select(x.fail_label, &x.destinations.items),
+            I::SelectTupleArity(x) => match (&x.arg2, &x.arg3) {
+                (Term::Label(fail), Term::List(list)) => select(*fail, &list.items),
+                _ => Successors::FallThrough,
+            },
+
+            I::Return(_) => Successors::Return,
+            I::CallOnly(_)
+            | I::CallLast(_)
+            | I::CallExtOnly(_)
+            | I::CallExtLast(_)
+            | I::ApplyLast(_) => Successors::TailCall,
+            I::FuncInfo(_)
+            | I::Badmatch(_)
+            | I::CaseEnd(_)
+            | I::IfEnd(_)
+            | I::TryCaseEnd(_)
+            | I::Badrecord(_)
+            | I::Raise(_)
+            | I::RawRaise(_) => Successors::Raise,
+            I::IntCodeEnd(_) => Successors::End,
+
+            // Tests.
+            I::IsEqExact(x) => branch_label(x.label),
+            I::IsNeExact(x) => branch_label(x.label),
+            I::IsInteger(x) => branch_label(x.label),
+            I::IsFloat(x) => branch_label(x.label),
+            I::IsNumber(x) => branch_label(x.label),
+            I::IsAtom(x) => branch_label(x.label),
+            I::IsPid(x) => branch_label(x.label),
+            I::IsReference(x) => branch_label(x.label),
+            I::IsPort(x) => branch_label(x.label),
+            I::IsNil(x) => branch_label(x.label),
+            I::IsBinary(x) => branch_label(x.label),
+            I::IsConstant(x) => branch_label(x.label),
+            I::IsList(x) => branch_label(x.label),
+            I::IsNonemptyList(x) => branch_label(x.label),
+            I::IsTuple(x) => branch_label(x.label),
+            I::IsTaggedTuple(x) => branch_label(x.label),
+            I::TestArity(x) => branch_label(x.label),
+            I::IsLt(x) => branch(&x.arg1),
+            I::IsGe(x) => branch(&x.arg1),
+            I::IsEq(x) => branch(&x.arg1),
+            I::IsNe(x) => branch(&x.arg1),
+            I::IsFunction(x) => branch(&x.arg1),
+            I::IsFunction2(x) => branch(&x.arg1),
+            I::IsBoolean(x) => branch(&x.arg1),
+            I::IsBitstr(x) => branch(&x.arg1),
+            I::IsMap(x) => branch(&x.arg1),
+            I::HasMapFields(x) => branch(&x.arg1),
+            I::GetMapElement(x) => branch(&x.arg1),
+
+            // Instructions with (optional) fail labels.
+            I::Bif1(x) => branch(&x.arg1),
+            I::Bif2(x) => branch(&x.arg1),
+            I::GcBif1(x) => branch(&x.arg1),
+            I::GcBif2(x) => branch(&x.arg1),
+            I::GcBif3(x) => branch(&x.arg1),
+            I::MPlus(x) => branch(&x.arg1),
+            I::MMinus(x) => branch(&x.arg1),
+            I::MTimes(x) => branch(&x.arg1),
+            I::MDiv(x) => branch(&x.arg1),
+            I::IntDiv(x) => branch(&x.arg1),
+            I::IntRem(x) => branch(&x.arg1),
+            I::IntBand(x) => branch(&x.arg1),
+            I::IntBor(x) => branch(&x.arg1),
+            I::IntBxor(x) => branch(&x.arg1),
+            I::IntBsl(x) => branch(&x.arg1),
+            I::IntBsr(x) => branch(&x.arg1),
+            I::IntBnot(x) => branch(&x.arg1),
+            I::Fcheckerror(x) => branch(&x.arg1),
+            I::Fadd(x) => branch(&x.arg1),
+            I::Fsub(x) => branch(&x.arg1),
+            I::Fmul(x) => branch(&x.arg1),
+            I::Fdiv(x) => branch(&x.arg1),
+            I::Fnegate(x) => branch(&x.arg1),
+            I::PutMapAssoc(x) => branch(&x.arg1),
+            I::PutMapExact(x) => branch(&x.arg1),
+
+            // Receiving messages.
+            I::LoopRec(x) => branch(&x.arg1),
+            I::WaitTimeout(x) => branch(&x.arg1),
+
+            // Binary matching and construction.
+            I::BsStartMatch(x) => branch(&x.arg1),
+            I::BsStartMatch2(x) => branch(&x.arg1),
+            I::BsStartMatch3(x) => branch_label(x.fail),
+            I::BsStartMatch4(x) => branch(&x.arg1),
+            I::BsGetInteger(x) => branch(&x.arg1),
+            I::BsGetFloat(x) => branch(&x.arg1),
+            I::BsGetBinary(x) => branch(&x.arg1),
+            I::BsGetInteger2(x) => branch(&x.arg1),
+            I::BsGetFloat2(x) => branch(&x.arg1),
+            I::BsGetBinary2(x) => branch(&x.arg1),
+            I::BsSkipBits(x) => branch(&x.arg1),
+            I::BsSkipBits2(x) => branch(&x.arg1),
+            I::BsTestTail(x) => branch(&x.arg1),
+            I::BsTestTail2(x) => branch(&x.arg1),
+            I::BsTestUnit(x) => branch(&x.arg1),
+            I::BsMatchString(x) => branch(&x.arg1),
+            I::BsGetUtf8(x) => branch(&x.arg1),
+            I::BsGetUtf16(x) => branch(&x.arg1),
+            I::BsGetUtf32(x) => branch(&x.arg1),
+            I::BsSkipUtf8(x) => branch(&x.arg1),
+            I::BsSkipUtf16(x) => branch(&x.arg1),
+            I::BsSkipUtf32(x) => branch(&x.arg1),
+            I::BsFinal(x) => branch(&x.arg1),
+            I::BsInit2(x) => branch(&x.arg1),
+            I::BsInitBits(x) => branch(&x.arg1),
+            I::BsAdd(x) => branch(&x.arg1),
+            I::BsBitsToBytes(x) => branch(&x.arg1),
+            I::BsAppend(x) => branch(&x.arg1),
+            I::BsPrivateAppend(x) => branch(&x.arg1),
+            I::BsPutInteger(x) => branch(&x.arg1),
+            I::BsPutBinary(x) => branch(&x.arg1),
+            I::BsPutFloat(x) => branch(&x.arg1),
+            I::BsUtf8Size(x) => branch(&x.arg1),
+            I::BsUtf16Size(x) => branch(&x.arg1),
+            I::BsPutUtf8(x) => branch(&x.arg1),
+            I::BsPutUtf16(x) => branch(&x.arg1),
+            I::BsPutUtf32(x) => branch(&x.arg1),
+            I::BsCreateBin(x) => branch(&x.arg1),
+
+            _ => Successors::FallThrough,
+        }
+    }
+}
+
+fn jump(label: &Term) -> Successors {
+    if let Term::Label(label) = label {
+        Successors::Jump(*label)
+    } else {
+        Successors::FallThrough
+    }
+}
+
+fn branch(label: &Term) -> Successors {
+    if let Term::Label(label) = label {
+        branch_label(*label)
+    } else {
+        Successors::FallThrough
+    }
+}
+
+// Label 0 means that the instruction raises an exception instead of branching on failure.
+fn branch_label(label: Label) -> Successors {
+    if label.value == 0 {
+        Successors::FallThrough
+    } else {
+        Successors::Branch(label)
+    }
+}
+
+fn select(fail: Label, items: &[Term]) -> Successors {
+    let destinations = items
+        .chunks(2)
+        .filter_map(|pair| match pair {
+            [value, Term::Label(label)] => Some((value.clone(), *label)),
+            _ => None,
+        })
+        .collect();
+    Successors::Select { fail, destinations }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction;
+    use crate::term::{Atom, List, XRegister};
+
+    fn x0() -> Term {
+        Term::XRegister(XRegister { value: 0, ty: None })
+    }
+
+    #[test]
+    fn successors_works() {
+        let select = Instruction::SelectVal(instruction::SelectVal {
+            arg: x0(),
+            fail_label: Label { value: 2 },
+            destinations: List {
+                items: vec![
+                    Term::Atom(Atom { value: 1 }),
+                    Term::Label(Label { value: 3 }),
+                    Term::Atom(Atom { value: 2 }),
+                    Term::Label(Label { value: 4 }),
+                ],
+            },
+        });
+        let successors = select.successors();
+        assert!(!successors.falls_through());
+        assert_eq!(
+            successors.labels(),
+            [Label { value: 2 }, Label { value: 3 }, Label { value: 4 }]
+        );
+
+        let test = Instruction::IsNil(instruction::IsNil {
+            label: Label { value: 5 },
+            arg1: x0(),
+        });
+        assert_eq!(test.successors(), Successors::Branch(Label { value: 5 }));
+        assert!(test.successors().falls_through());
+
+        let bif = Instruction::GcBif1(instruction::GcBif1 {
+            arg1: Term::Label(Label { value: 0 }),
+            arg2: Term::Usize(1),
+            arg3: Term::Usize(0),
+            arg4: x0(),
+            arg5: x0(),
+        });
+        assert_eq!(bif.successors(), Successors::FallThrough);
+
+        let call = Instruction::CallOnly(instruction::CallOnly {
+            arity: 1,
+            label: Label { value: 2 },
+        });
+        assert_eq!(call.successors(), Successors::TailCall);
+        assert!(call.successors().is_terminator());
+
+        let raise = Instruction::CaseEnd(instruction::CaseEnd { arg1: x0() });
+        assert_eq!(raise.successors(), Successors::Raise);
+    }
+}
@@ -0,0 +1,463 @@
+//! Stack frame and heap need analysis.
+//!
+//! The stack frame is allocated by `allocate*` and released by `deallocate`, `trim` and the
+//! `*_last` calls. Heap space is reserved by `test_heap` and `allocate_heap*`, consumed by the
+//! instructions that build terms (e.g., `put_list` and `make_fun3`), and invalidated by
+//! instructions that may garbage collect.
+use crate::cfg::{BlockId, Cfg, EdgeKind};
+use crate::instruction::Instruction;
+use crate::term::{Allocation, AllocationListItem, Term};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The state of the stack frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    /// No frame is allocated.
+    None,
+
+    /// A frame with the given number of Y registers is allocated.
+    Allocated(usize),
+
+    /// Incoming paths disagree.
+    Unknown,
+}
+
+impl Frame {
+    pub(crate) fn conflicts_with(self, other: Self) -> bool {
+        self != other && self != Self::Unknown && other != Self::Unknown
+    }
+}
+
+/// A problem found by [`FrameAnalysis`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("no stack frame")]
+    NoStackFrame,
+
+    #[error("stack frame already allocated")]
+    FrameAlreadyAllocated,
+
+    #[error("stack frame size mismatch: frame_size={frame_size}, size={size}")]
+    FrameSizeMismatch { frame_size: usize, size: usize },
+
+    #[error("stack frame not deallocated")]
+    FrameNotDeallocated,
+
+    #[error("inconsistent stack frames on incoming paths")]
+    InconsistentFrame,
+
+    #[error("trimming more than the stack frame: n={n}, frame_size={frame_size}")]
+    OverTrim { n: usize, frame_size: usize },
+
+    #[error("heap need exceeds the reserved space: need=({need}), available=({available})")]
+    HeapExceeded { need: HeapNeed, available: HeapNeed },
+}
+
+/// An amount of heap space.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HeapNeed {
+    pub words: usize,
+    pub floats: usize,
+    pub funs: usize,
+}
+
+impl HeapNeed {
+    fn covers(self, other: Self) -> bool {
+        self.words >= other.words && self.floats >= other.floats && self.funs >= other.funs
+    }
+
+    fn min(self, other: Self) -> Self {
+        Self {
+            words: self.words.min(other.words),
+            floats: self.floats.min(other.floats),
+            funs: self.funs.min(other.funs),
+        }
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        Self {
+            words: self.words.saturating_sub(other.words),
+            floats: self.floats.saturating_sub(other.floats),
+            funs: self.funs.saturating_sub(other.funs),
+        }
+    }
+}
+
+impl From<&Allocation> for HeapNeed {
+    fn from(allocation: &Allocation) -> Self {
+        let mut need = Self::default();
+        match allocation {
+            Allocation::Words(n) => need.words = *n,
+            Allocation::List(list) => {
+                for item in &list.items {
+                    match item {
+                        AllocationListItem::Words(n) => need.words += n,
+                        AllocationListItem::Floats(n) => need.floats += n,
+                        AllocationListItem::Funs(n) => need.funs += n,
+                    }
+                }
+            }
+        }
+        need
+    }
+}
+
+impl fmt::Display for HeapNeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "words={}, floats={}, funs={}",
+            self.words, self.floats, self.funs
+        )
+    }
+}
+
+/// The stack frame and the remaining reserved heap space before an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameState {
+    pub frame: Frame,
+    pub heap: HeapNeed,
+}
+
+impl FrameState {
+    pub(crate) fn entry() -> Self {
+        Self {
+            frame: Frame::None,
+            heap: HeapNeed::default(),
+        }
+    }
+
+    pub(crate) fn join(&self, other: &Self) -> Self {
+        Self {
+            frame: if self.frame == other.frame {
+                self.frame
+            } else {
+                Frame::Unknown
+            },
+            heap: self.heap.min(other.heap),
+        }
+    }
+
+    /// Updates the state by executing an instruction.
+    pub(crate) fn execute(&mut self, instruction: &Instruction, report: &mut impl FnMut(Error)) {
+        let need = heap_consumption(instruction);
+        if !self.heap.covers(need) {
+            report(Error::HeapExceeded {
+                need,
+                available: self.heap,
+            });
+        }
+        self.heap = self.heap.saturating_sub(need);
+
+        match instruction {
+            Instruction::Allocate(x) => self.allocate(words(&x.stack_need), report),
+            Instruction::AllocateHeap(x) => self.allocate(words(&x.stack_need), report),
+            Instruction::AllocateZero(x) => self.allocate(usize_term(&x.arg1), report),
+            Instruction::AllocateHeapZero(x) => self.allocate(words(&x.stack_need), report),
+            Instruction::Deallocate(x) => self.deallocate(x.n, report),
+            Instruction::CallLast(x) => self.deallocate(usize_term(&x.arg3), report),
+            Instruction::CallExtLast(x) => self.deallocate(x.deallocate, report),
+            Instruction::ApplyLast(x) => self.deallocate(usize_term(&x.arg2), report),
+            Instruction::Trim(x) => self.trim(usize_term(&x.arg1), usize_term(&x.arg2), report),
+            Instruction::Return(_) | Instruction::CallOnly(_) | Instruction::CallExtOnly(_) => {
+                if matches!(self.frame, Frame::Allocated(_)) {
+                    report(Error::FrameNotDeallocated);
+                }
+            }
+            _ => {}
+        }
+
+        if instruction.preserved_x_registers().is_some()
+            || matches!(
+                instruction,
+                Instruction::MakeFun(_) | Instruction::MakeFun2(_)
+            )
+        {
+            self.heap = match instruction {
+                Instruction::TestHeap(x) => HeapNeed::from(&x.heap_need),
+                Instruction::AllocateHeap(x) => HeapNeed::from(&x.heap_need),
+                Instruction::AllocateHeapZero(x) => HeapNeed::from(&x.heap_need),
+                _ => HeapNeed::default(),
+            };
+        }
+    }
+
+    fn allocate(&mut self, size: usize, report: &mut impl FnMut(Error)) {
+        if matches!(self.frame, Frame::Allocated(_)) {
+            report(Error::FrameAlreadyAllocated);
+        }
+        self.frame = Frame::Allocated(size);
+    }
+
+    fn deallocate(&mut self, size: usize, report: &mut impl FnMut(Error)) {
+        match self.frame {
+            Frame::None => report(Error::NoStackFrame),
+            Frame::Allocated(frame_size) if frame_size != size => {
+                report(Error::FrameSizeMismatch { frame_size, size })
+            }
+            _ => {}
+        }
+        self.frame = Frame::None;
+    }
+
+    fn trim(&mut self, n: usize, remaining: usize, report: &mut impl FnMut(Error)) {
+        match self.frame {
+            Frame::None => report(Error::NoStackFrame),
+            Frame::Allocated(frame_size) if frame_size < n => {
+                report(Error::OverTrim { n, frame_size })
+            }
+            Frame::Allocated(frame_size) if frame_size != n + remaining => {
+                report(Error::FrameSizeMismatch {
+                    frame_size,
+                    size: n + remaining,
+                })
+            }
+            _ => {}
+        }
+        if let Frame::Allocated(frame_size) = self.frame {
+            self.frame = Frame::Allocated(frame_size.saturating_sub(n));
+        }
+    }
+}
+
+// Returns the heap space that the instruction consumes from the reservation.
+//
+// `put` fills the space consumed by the preceding `put_tuple`, and `put_literal` does not build
+// terms. `put_map_assoc`, `put_map_exact`, `make_fun` and `make_fun2` allocate on their own
+// and may garbage collect, which invalidates the reservation (see `FrameState::execute()`).
+fn heap_consumption(instruction: &Instruction) -> HeapNeed {
+    let words = match instruction {
+        Instruction::PutList(_) => 2,
+        Instruction::PutTuple(x) => usize_term(&x.arg1) + 1,
+        Instruction::PutTuple2(x) => x.elements.items.len() + 1,
+        Instruction::PutString(x) => usize_term(&x.arg1) * 2,
+        _ => 0,
+    };
+    let floats = match instruction {
+        Instruction::Fmove(x) if !matches!(x.arg2, Term::FloatingPointRegister(_)) => {
+            usize::from(matches!(x.arg1, Term::FloatingPointRegister(_)))
+        }
+        _ => 0,
+    };
+    HeapNeed {
+        words,
+        floats,
+        funs: usize::from(matches!(instruction, Instruction::MakeFun3(_))),
+    }
+}
+
+fn words(allocation: &Allocation) -> usize {
+    HeapNeed::from(allocation).words
+}
+
+pub(crate) fn usize_term(term: &Term) -> usize {
+    match term {
+        Term::Usize(n) => *n,
+        _ => 0,
+    }
+}
+
+/// The result of the stack frame and heap need analysis of a function.
+#[derive(Debug, Clone)]
+pub struct FrameAnalysis {
+    states: Vec<Option<FrameState>>,
+    diagnostics: Vec<(usize, Error)>,
+}
+
+impl FrameAnalysis {
+    /// Analyzes a function (see [`Cfg::new()`]).
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let cfg = Cfg::new(instructions);
+        let mut states = vec![None; instructions.len()];
+        let mut diagnostics = Vec::new();
+        if cfg.blocks().is_empty() {
+            return Self {
+                states,
+                diagnostics,
+            };
+        }
+
+        let mut block_states = vec![None; cfg.blocks().len()];
+        block_states[cfg.entry().0] = Some(FrameState::entry());
+        let mut conflicts = BTreeSet::new();
+        let mut worklist = vec![cfg.entry()];
+        while let Some(id) = worklist.pop() {
+            let Some(mut state) = block_states[id.0].clone() else {
+                continue;
+            };
+            for instruction in &instructions[cfg.block(id).range.clone()] {
+                state.execute(instruction, &mut |_| {});
+            }
+            for edge in &cfg.block(id).successors {
+                let mut output = state.clone();
+                if edge.kind == EdgeKind::Exception {
+                    output.heap = HeapNeed::default();
+                }
+                let new = match &block_states[edge.target.0] {
+                    None => output,
+                    Some(old) => {
+                        if old.frame.conflicts_with(output.frame) {
+                            conflicts.insert(edge.target);
+                        }
+                        old.join(&output)
+                    }
+                };
+                if block_states[edge.target.0].as_ref() != Some(&new) {
+                    block_states[edge.target.0] = Some(new);
+                    worklist.push(edge.target);
+                }
+            }
+        }
+
+        for (i, state) in block_states.into_iter().enumerate() {
+            let Some(mut state) = state else {
+                continue;
+            };
+            let range = cfg.block(BlockId(i)).range.clone();
+            if conflicts.contains(&BlockId(i)) {
+                diagnostics.push((range.start, Error::InconsistentFrame));
+            }
+            for position in range {
+                states[position] = Some(state.clone());
+                state.execute(&instructions[position], &mut |kind| {
+                    diagnostics.push((position, kind))
+                });
+            }
+        }
+        diagnostics.sort_by_key(|x| x.0);
+        Self {
+            states,
+            diagnostics,
+        }
+    }
+
+    /// Returns the state before the instruction, or `None` if it is unreachable.
+    pub fn state(&self, position: usize) -> Option<&FrameState> {
+        self.states.get(position).and_then(|x| x.as_ref())
+    }
+
+    /// Returns the problems found and their positions in instruction order.
+    pub fn diagnostics(&self) -> &[(usize, Error)] {
+        &self.diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    #[test]
+    fn frame_analysis_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 1;
+            label 2;
+            allocate 2 1;
+            test_heap {alloc,[{words,3},{floats,1}]} 1;
+            put_list {x,0} nil {x,0};
+            put_list {x,0} nil {x,0};
+            fmove {fr,0} {x,1};
+            trim 3 0;
+            call 1 {f,2};
+            deallocate 2;
+            return;
+        };
+        let analysis = FrameAnalysis::new(&code);
+        assert_eq!(analysis.state(0), None);
+        assert_eq!(
+            analysis.state(5),
+            Some(&FrameState {
+                frame: Frame::Allocated(2),
+                heap: HeapNeed {
+                    words: 3,
+                    floats: 1,
+                    funs: 0
+                }
+            })
+        );
+        assert_eq!(
+            analysis.state(7).map(|x| x.heap),
+            Some(HeapNeed {
+                words: 0,
+                floats: 1,
+                funs: 0
+            })
+        );
+        assert_eq!(
+            analysis.diagnostics(),
+            [
+                (
+                    6,
+                    Error::HeapExceeded {
+                        need: HeapNeed {
+                            words: 2,
+                            floats: 0,
+                            funs: 0
+                        },
+                        available: HeapNeed {
+                            words: 1,
+                            floats: 1,
+                            funs: 0
+                        }
+                    }
+                ),
+                (
+                    8,
+                    Error::OverTrim {
+                        n: 3,
+                        frame_size: 2
+                    }
+                ),
+                (
+                    10,
+                    Error::FrameSizeMismatch {
+                        frame_size: 0,
+                        size: 2
+                    }
+                ),
+            ]
+        );
+
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 0;
+            label 2;
+            test_heap {alloc,[{words,0},{floats,0},{funs,1}]} 0;
+            make_fun3 0 {x,0} {list,[]};
+            make_fun3 0 {x,0} {list,[]};
+            make_fun2 0;
+            put_list {x,0} nil {x,0};
+            return;
+        };
+        let analysis = FrameAnalysis::new(&code);
+        assert_eq!(
+            analysis.diagnostics(),
+            [
+                (
+                    5,
+                    Error::HeapExceeded {
+                        need: HeapNeed {
+                            words: 0,
+                            floats: 0,
+                            funs: 1
+                        },
+                        available: HeapNeed::default()
+                    }
+                ),
+                (
+                    7,
+                    Error::HeapExceeded {
+                        need: HeapNeed {
+                            words: 2,
+                            floats: 0,
+                            funs: 0
+                        },
+                        available: HeapNeed::default()
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -7,13 +7,29 @@
 //! - erlang/otp/lib/compiler/src/beam_opcodes.erl (generated file)
 use crate::term::{self, Allocation, Atom, List, Register, Term, YRegister};
 use crate::{Decode, Encode};
-use beamcode_derive::Opcode;
+use beamcode_derive::{Emit, Opcode, Operands, Walk, WalkMut};
 
-pub trait Opcode {
+mod private {
+    pub trait Sealed {}
+}
+
+/// The opcode of an instruction type.
+///
+/// This trait is sealed: it is implemented by `#[derive(Opcode)]` for the types of this module
+/// only, so that constants can be added without breaking other crates.
+pub trait Opcode: private::Sealed {
     const CODE: u8;
+
+    /// The name of the opcode as defined in `genop.tab` (e.g., `"is_nil"`).
+    const NAME: &'static str;
+
+    /// Whether the opcode is no longer emitted by the compiler (`#[opcode(N, deprecated)]`).
+    const DEPRECATED: bool = false;
 }
 
-#[derive(Debug, Clone, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", rename_all = "snake_case"))]
 pub enum Instruction {
     Allocate(Allocate),
     AllocateHeap(AllocateHeap),
@@ -32,6 +48,7 @@ pub enum Instruction {
     BsBitsToBytes(BsBitsToBytes),
     /// Deprecated.
     BsBitsToBytes2(BsBitsToBytes2),
+    /// Deprecated.
     BsContextToBinary(BsContextToBinary),
     BsCreateBin(BsCreateBin),
     /// Deprecated.
@@ -69,10 +86,14 @@ pub enum Instruction {
     BsPutUtf8(BsPutUtf8),
     /// Deprecated.
     BsRestore(BsRestore),
+    /// Deprecated.
     BsRestore2(BsRestore2),
+    /// Deprecated.
     BsSave(BsSave),
+    /// Deprecated.
     BsSave2(BsSave2),
     BsSetPosition(BsSetPosition),
+    /// Deprecated.
     BsSkipBits(BsSkipBits),
     BsSkipBits2(BsSkipBits2),
     BsSkipUtf32(BsSkipUtf32),
@@ -80,6 +101,7 @@ pub enum Instruction {
     BsSkipUtf8(BsSkipUtf8),
     /// Deprecated.
     BsStartMatch(BsStartMatch),
+    /// Deprecated.
     BsStartMatch2(BsStartMatch2),
     BsStartMatch3(BsStartMatch3),
     BsStartMatch4(BsStartMatch4),
@@ -117,6 +139,7 @@ pub enum Instruction {
     GcBif3(GcBif3),
     GetHd(GetHd),
     GetList(GetList),
+    #[cfg_attr(feature = "serde", serde(rename = "get_map_elements"))]
     GetMapElement(GetMapElement),
     GetTl(GetTl),
     GetTupleElement(GetTupleElement),
@@ -187,6 +210,7 @@ pub enum Instruction {
     MTimes(MTimes),
     NifStart(NifStart),
     OnLoad(OnLoad),
+    /// Deprecated.
     Put(Put),
     PutList(PutList),
     /// Deprecated.
@@ -195,15 +219,18 @@ pub enum Instruction {
     PutMapExact(PutMapExact),
     /// Deprecated.
     PutString(PutString),
+    /// Deprecated.
     PutTuple(PutTuple),
     PutTuple2(PutTuple2),
     Raise(Raise),
     RawRaise(RawRaise),
+    /// Deprecated.
     RecvMark(RecvMark),
     RecvMarkerBind(RecvMarkerBind),
     RecvMarkerClear(RecvMarkerClear),
     RecvMarkerReserve(RecvMarkerReserve),
     RecvMarkerUse(RecvMarkerUse),
+    /// Deprecated.
     RecvSet(RecvSet),
     Return(Return),
     RemoveMessage(RemoveMessage),
@@ -224,13 +251,16 @@ pub enum Instruction {
     WaitTimeout(WaitTimeout),
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+// No emit method is derived as labels are defined by `CodeBuilder::bind()`.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(1)]
 pub struct Label {
     pub literal: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(2)]
 pub struct FuncInfo {
     pub module: Atom,
@@ -238,18 +268,21 @@ pub struct FuncInfo {
     pub arity: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(3)]
 pub struct IntCodeEnd {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(4)]
 pub struct Call {
     pub arity: usize,
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(5)]
 pub struct CallLast {
     pub arg1: Term,
@@ -257,21 +290,24 @@ pub struct CallLast {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(6)]
 pub struct CallOnly {
     pub arity: usize,
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(7)]
 pub struct CallExt {
     pub arity: usize,
     pub destination: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(8)]
 pub struct CallExtLast {
     pub arity: usize,
@@ -279,14 +315,16 @@ pub struct CallExtLast {
     pub deallocate: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(9)]
 pub struct Bif0 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(10)]
 pub struct Bif1 {
     pub arg1: Term,
@@ -295,7 +333,8 @@ pub struct Bif1 {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(11)]
 pub struct Bif2 {
     pub arg1: Term,
@@ -305,14 +344,16 @@ pub struct Bif2 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(12)]
 pub struct Allocate {
     pub stack_need: Allocation,
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(13)]
 pub struct AllocateHeap {
     pub stack_need: Allocation,
@@ -320,14 +361,16 @@ pub struct AllocateHeap {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(14)]
 pub struct AllocateZero {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(15)]
 pub struct AllocateHeapZero {
     pub stack_need: Allocation,
@@ -335,61 +378,72 @@ pub struct AllocateHeapZero {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(16)]
 pub struct TestHeap {
     pub heap_need: Allocation,
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(17)]
 pub struct Init {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(18)]
 pub struct Deallocate {
     pub n: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(19)]
 pub struct Return {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(20)]
 pub struct Send {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(21)]
 pub struct RemoveMessage {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(22)]
 pub struct Timeout {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(23)]
 pub struct LoopRec {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(24)]
 pub struct LoopRecEnd {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(25)]
 pub struct Wait {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(26)]
 pub struct WaitTimeout {
     pub arg1: Term,
@@ -397,8 +451,9 @@ pub struct WaitTimeout {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(27)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(27, deprecated)]
 pub struct MPlus {
     pub arg1: Term,
     pub arg2: Term,
@@ -407,8 +462,9 @@ pub struct MPlus {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(28)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(28, deprecated)]
 pub struct MMinus {
     pub arg1: Term,
     pub arg2: Term,
@@ -417,8 +473,9 @@ pub struct MMinus {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(29)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(29, deprecated)]
 pub struct MTimes {
     pub arg1: Term,
     pub arg2: Term,
@@ -427,8 +484,9 @@ pub struct MTimes {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(30)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(30, deprecated)]
 pub struct MDiv {
     pub arg1: Term,
     pub arg2: Term,
@@ -437,8 +495,9 @@ pub struct MDiv {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(31)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(31, deprecated)]
 pub struct IntDiv {
     pub arg1: Term,
     pub arg2: Term,
@@ -447,8 +506,9 @@ pub struct IntDiv {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(32)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(32, deprecated)]
 pub struct IntRem {
     pub arg1: Term,
     pub arg2: Term,
@@ -457,8 +517,9 @@ pub struct IntRem {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(33)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(33, deprecated)]
 pub struct IntBand {
     pub arg1: Term,
     pub arg2: Term,
@@ -467,8 +528,9 @@ pub struct IntBand {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(34)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(34, deprecated)]
 pub struct IntBor {
     pub arg1: Term,
     pub arg2: Term,
@@ -477,8 +539,9 @@ pub struct IntBor {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(35)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(35, deprecated)]
 pub struct IntBxor {
     pub arg1: Term,
     pub arg2: Term,
@@ -487,8 +550,9 @@ pub struct IntBxor {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(36)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(36, deprecated)]
 pub struct IntBsl {
     pub arg1: Term,
     pub arg2: Term,
@@ -497,8 +561,9 @@ pub struct IntBsl {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(37)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(37, deprecated)]
 pub struct IntBsr {
     pub arg1: Term,
     pub arg2: Term,
@@ -507,15 +572,17 @@ pub struct IntBsr {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(38)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(38, deprecated)]
 pub struct IntBnot {
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(39)]
 pub struct IsLt {
     pub arg1: Term,
@@ -523,7 +590,8 @@ pub struct IsLt {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(40)]
 pub struct IsGe {
     pub arg1: Term,
@@ -531,7 +599,8 @@ pub struct IsGe {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(41)]
 pub struct IsEq {
     pub arg1: Term,
@@ -539,7 +608,8 @@ pub struct IsEq {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(42)]
 pub struct IsNe {
     pub arg1: Term,
@@ -547,7 +617,8 @@ pub struct IsNe {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(43)]
 pub struct IsEqExact {
     pub label: term::Label,
@@ -555,7 +626,8 @@ pub struct IsEqExact {
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(44)]
 pub struct IsNeExact {
     pub label: term::Label,
@@ -563,63 +635,72 @@ pub struct IsNeExact {
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(45)]
 pub struct IsInteger {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(46)]
 pub struct IsFloat {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(47)]
 pub struct IsNumber {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(48)]
 pub struct IsAtom {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(49)]
 pub struct IsPid {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(50)]
 pub struct IsReference {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(51)]
 pub struct IsPort {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(52)]
 pub struct IsNil {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(53)]
 pub struct IsBinary {
     pub label: term::Label,
@@ -627,35 +708,40 @@ pub struct IsBinary {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(54)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(54, deprecated)]
 pub struct IsConstant {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(55)]
 pub struct IsList {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(56)]
 pub struct IsNonemptyList {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(57)]
 pub struct IsTuple {
     pub label: term::Label,
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(58)]
 pub struct TestArity {
     pub label: term::Label,
@@ -663,7 +749,8 @@ pub struct TestArity {
     pub arity: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(59)]
 pub struct SelectVal {
     pub arg: Term,
@@ -671,7 +758,8 @@ pub struct SelectVal {
     pub destinations: List,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(60)]
 pub struct SelectTupleArity {
     pub arg1: Term,
@@ -679,33 +767,38 @@ pub struct SelectTupleArity {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(61)]
 pub struct Jump {
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(62)]
 pub struct Catch {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(63)]
 pub struct CatchEnd {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(64)]
 pub struct Move {
     pub src: Term,
     pub dst: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(65)]
 pub struct GetList {
     pub source: Term,
@@ -713,7 +806,8 @@ pub struct GetList {
     pub tail: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(66)]
 pub struct GetTupleElement {
     pub source: Register,
@@ -721,7 +815,8 @@ pub struct GetTupleElement {
     pub destination: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(67)]
 pub struct SetTupleElement {
     pub arg1: Term,
@@ -730,15 +825,17 @@ pub struct SetTupleElement {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(68)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(68, deprecated)]
 pub struct PutString {
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(69)]
 pub struct PutList {
     pub head: Term,
@@ -746,58 +843,69 @@ pub struct PutList {
     pub destination: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(70)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(70, deprecated)]
 pub struct PutTuple {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(71)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(71, deprecated)]
 pub struct Put {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(72)]
 pub struct Badmatch {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(73)]
 pub struct IfEnd {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(74)]
 pub struct CaseEnd {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(75)]
 pub struct CallFun {
     pub arg1: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(76)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(76, deprecated)]
 pub struct MakeFun {
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(77)]
 pub struct IsFunction {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(78)]
 pub struct CallExtOnly {
     pub arity: usize,
@@ -805,16 +913,18 @@ pub struct CallExtOnly {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(79)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(79, deprecated)]
 pub struct BsStartMatch {
     pub arg1: Term,
     pub arg2: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(80)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(80, deprecated)]
 pub struct BsGetInteger {
     pub arg1: Term,
     pub arg2: Term,
@@ -824,8 +934,9 @@ pub struct BsGetInteger {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(81)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(81, deprecated)]
 pub struct BsGetFloat {
     pub arg1: Term,
     pub arg2: Term,
@@ -835,8 +946,9 @@ pub struct BsGetFloat {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(82)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(82, deprecated)]
 pub struct BsGetBinary {
     pub arg1: Term,
     pub arg2: Term,
@@ -845,8 +957,10 @@ pub struct BsGetBinary {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(83)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(83, deprecated)]
 pub struct BsSkipBits {
     pub arg1: Term,
     pub arg2: Term,
@@ -855,43 +969,50 @@ pub struct BsSkipBits {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(84)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(84, deprecated)]
 pub struct BsTestTail {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(85)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(85, deprecated)]
 pub struct BsSave {
     pub arg1: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(86)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(86, deprecated)]
 pub struct BsRestore {
     pub arg1: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(87)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(87, deprecated)]
 pub struct BsInit {
     pub arg1: Term,
     pub arg2: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(88)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(88, deprecated)]
 pub struct BsFinal {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(89)]
 pub struct BsPutInteger {
     pub arg1: Term,
@@ -901,7 +1022,8 @@ pub struct BsPutInteger {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(90)]
 pub struct BsPutBinary {
     pub arg1: Term,
@@ -911,7 +1033,8 @@ pub struct BsPutBinary {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(91)]
 pub struct BsPutFloat {
     pub arg1: Term,
@@ -921,44 +1044,51 @@ pub struct BsPutFloat {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(92)]
 pub struct BsPutString {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(93)]
 pub struct BsNeedBuf {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(94)]
 pub struct Fclearerror {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(95)]
 pub struct Fcheckerror {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(96)]
 pub struct Fmove {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(97)]
 pub struct Fconv {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(98)]
 pub struct Fadd {
     pub arg1: Term,
@@ -967,7 +1097,8 @@ pub struct Fadd {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(99)]
 pub struct Fsub {
     pub arg1: Term,
@@ -976,7 +1107,8 @@ pub struct Fsub {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(100)]
 pub struct Fmul {
     pub arg1: Term,
@@ -985,7 +1117,8 @@ pub struct Fmul {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(101)]
 pub struct Fdiv {
     pub arg1: Term,
@@ -994,7 +1127,8 @@ pub struct Fdiv {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(102)]
 pub struct Fnegate {
     pub arg1: Term,
@@ -1002,45 +1136,52 @@ pub struct Fnegate {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(103)]
 pub struct MakeFun2 {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(104)]
 pub struct Try {
     pub register: Register,
     pub label: term::Label,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(105)]
 pub struct TryEnd {
     pub register: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(106)]
 pub struct TryCase {
     pub register: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(107)]
 pub struct TryCaseEnd {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(108)]
 pub struct Raise {
     pub stacktrace: Term,
     pub exc_value: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(109)]
 pub struct BsInit2 {
     pub arg1: Term,
@@ -1052,15 +1193,17 @@ pub struct BsInit2 {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(110)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(110, deprecated)]
 pub struct BsBitsToBytes {
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(111)]
 pub struct BsAdd {
     pub arg1: Term,
@@ -1070,27 +1213,31 @@ pub struct BsAdd {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(112)]
 pub struct Apply {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(113)]
 pub struct ApplyLast {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(114)]
 pub struct IsBoolean {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(115)]
 pub struct IsFunction2 {
     pub arg1: Term,
@@ -1098,8 +1245,10 @@ pub struct IsFunction2 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(116)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(116, deprecated)]
 pub struct BsStartMatch2 {
     pub arg1: Term,
     pub arg2: Term,
@@ -1108,7 +1257,8 @@ pub struct BsStartMatch2 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(117)]
 pub struct BsGetInteger2 {
     pub arg1: Term,
@@ -1120,7 +1270,8 @@ pub struct BsGetInteger2 {
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(118)]
 pub struct BsGetFloat2 {
     pub arg1: Term,
@@ -1132,7 +1283,8 @@ pub struct BsGetFloat2 {
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(119)]
 pub struct BsGetBinary2 {
     pub arg1: Term,
@@ -1144,7 +1296,8 @@ pub struct BsGetBinary2 {
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(120)]
 pub struct BsSkipBits2 {
     pub arg1: Term,
@@ -1154,7 +1307,8 @@ pub struct BsSkipBits2 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(121)]
 pub struct BsTestTail2 {
     pub arg1: Term,
@@ -1162,21 +1316,26 @@ pub struct BsTestTail2 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(122)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(122, deprecated)]
 pub struct BsSave2 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(123)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(123, deprecated)]
 pub struct BsRestore2 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(124)]
 pub struct GcBif1 {
     pub arg1: Term,
@@ -1186,7 +1345,8 @@ pub struct GcBif1 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(125)]
 pub struct GcBif2 {
     pub arg1: Term,
@@ -1198,43 +1358,50 @@ pub struct GcBif2 {
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(126)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(126, deprecated)]
 pub struct BsFinal2 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(127)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(127, deprecated)]
 pub struct BsBitsToBytes2 {
     pub arg1: Term,
     pub arg2: Term,
 }
 
 /// Deprecated.
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(128)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(128, deprecated)]
 pub struct PutLiteral {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(129)]
 pub struct IsBitstr {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(130)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(130, deprecated)]
 pub struct BsContextToBinary {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(131)]
 pub struct BsTestUnit {
     pub arg1: Term,
@@ -1242,7 +1409,8 @@ pub struct BsTestUnit {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(132)]
 pub struct BsMatchString {
     pub arg1: Term,
@@ -1251,11 +1419,13 @@ pub struct BsMatchString {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(133)]
 pub struct BsInitWritable {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(134)]
 pub struct BsAppend {
     pub arg1: Term,
@@ -1268,7 +1438,8 @@ pub struct BsAppend {
     pub arg8: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(135)]
 pub struct BsPrivateAppend {
     pub arg1: Term,
@@ -1279,14 +1450,16 @@ pub struct BsPrivateAppend {
     pub arg6: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(136)]
 pub struct Trim {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(137)]
 pub struct BsInitBits {
     pub arg1: Term,
@@ -1297,7 +1470,8 @@ pub struct BsInitBits {
     pub arg6: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(138)]
 pub struct BsGetUtf8 {
     pub arg1: Term,
@@ -1307,7 +1481,8 @@ pub struct BsGetUtf8 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(139)]
 pub struct BsSkipUtf8 {
     pub arg1: Term,
@@ -1316,7 +1491,8 @@ pub struct BsSkipUtf8 {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(140)]
 pub struct BsGetUtf16 {
     pub arg1: Term,
@@ -1326,7 +1502,8 @@ pub struct BsGetUtf16 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(141)]
 pub struct BsSkipUtf16 {
     pub arg1: Term,
@@ -1335,7 +1512,8 @@ pub struct BsSkipUtf16 {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(142)]
 pub struct BsGetUtf32 {
     pub arg1: Term,
@@ -1345,7 +1523,8 @@ pub struct BsGetUtf32 {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(143)]
 pub struct BsSkipUtf32 {
     pub arg1: Term,
@@ -1354,7 +1533,8 @@ pub struct BsSkipUtf32 {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(144)]
 pub struct BsUtf8Size {
     pub arg1: Term,
@@ -1362,7 +1542,8 @@ pub struct BsUtf8Size {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(145)]
 pub struct BsPutUtf8 {
     pub arg1: Term,
@@ -1370,7 +1551,8 @@ pub struct BsPutUtf8 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(146)]
 pub struct BsUtf16Size {
     pub arg1: Term,
@@ -1378,7 +1560,8 @@ pub struct BsUtf16Size {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(147)]
 pub struct BsPutUtf16 {
     pub arg1: Term,
@@ -1386,7 +1569,8 @@ pub struct BsPutUtf16 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(148)]
 pub struct BsPutUtf32 {
     pub arg1: Term,
@@ -1394,23 +1578,29 @@ pub struct BsPutUtf32 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(149)]
 pub struct OnLoad {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(150)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(150, deprecated)]
 pub struct RecvMark {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(151)]
+/// Deprecated.
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(151, deprecated)]
 pub struct RecvSet {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(152)]
 pub struct GcBif3 {
     pub arg1: Term,
@@ -1422,13 +1612,15 @@ pub struct GcBif3 {
     pub arg7: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(153)]
 pub struct Line {
     pub literal: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(154)]
 pub struct PutMapAssoc {
     pub arg1: Term,
@@ -1438,7 +1630,8 @@ pub struct PutMapAssoc {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(155)]
 pub struct PutMapExact {
     pub arg1: Term,
@@ -1448,14 +1641,16 @@ pub struct PutMapExact {
     pub arg5: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(156)]
 pub struct IsMap {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(157)]
 pub struct HasMapFields {
     pub arg1: Term,
@@ -1463,15 +1658,17 @@ pub struct HasMapFields {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
-#[opcode(158)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[opcode(158, name = "get_map_elements")]
 pub struct GetMapElement {
     pub arg1: Term,
     pub arg2: Term,
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(159)]
 pub struct IsTaggedTuple {
     pub label: term::Label,
@@ -1480,36 +1677,42 @@ pub struct IsTaggedTuple {
     pub atom: Atom,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(160)]
 pub struct BuildStacktrace {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(161)]
 pub struct RawRaise {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(162)]
 pub struct GetHd {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(163)]
 pub struct GetTl {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(164)]
 pub struct PutTuple2 {
     pub destination: Register,
     pub elements: List,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(165)]
 pub struct BsGetTail {
     pub context: Term,
@@ -1517,7 +1720,8 @@ pub struct BsGetTail {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(166)]
 pub struct BsStartMatch3 {
     pub fail: term::Label,
@@ -1526,7 +1730,8 @@ pub struct BsStartMatch3 {
     pub destination: Register,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(167)]
 pub struct BsGetPosition {
     pub context: Term,
@@ -1534,21 +1739,24 @@ pub struct BsGetPosition {
     pub live: usize,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(168)]
 pub struct BsSetPosition {
     pub context: Term,
     pub position: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(169)]
 pub struct Swap {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(170)]
 pub struct BsStartMatch4 {
     pub arg1: Term,
@@ -1557,7 +1765,8 @@ pub struct BsStartMatch4 {
     pub arg4: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(171)]
 pub struct MakeFun3 {
     pub arg1: Term,
@@ -1565,38 +1774,44 @@ pub struct MakeFun3 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(172)]
 pub struct InitYregs {
     pub registers: Vec<YRegister>,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(173)]
 pub struct RecvMarkerBind {
     pub arg1: Term,
     pub arg2: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(174)]
 pub struct RecvMarkerClear {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(175)]
 pub struct RecvMarkerReserve {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(176)]
 pub struct RecvMarkerUse {
     pub arg1: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(177)]
 pub struct BsCreateBin {
     pub arg1: Term,
@@ -1607,7 +1822,8 @@ pub struct BsCreateBin {
     pub arg6: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(178)]
 pub struct CallFun2 {
     pub arg1: Term,
@@ -1615,11 +1831,13 @@ pub struct CallFun2 {
     pub arg3: Term,
 }
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(179)]
 pub struct NifStart {}
 
-#[derive(Debug, Clone, Opcode, Decode, Encode)]
+#[derive(Debug, Clone, Opcode, Decode, Encode, Operands, Walk, WalkMut, Emit)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[opcode(180)]
 pub struct Badrecord {
     pub arg1: Term,
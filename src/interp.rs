@@ -0,0 +1,1813 @@
+//! Reference interpreter of BEAM instructions.
+//!
+//! The interpreter executes the code of a single [`Module`] in a [`Process`] with X, Y and
+//! floating point registers. It is intended for testing code generators, so it favors simplicity
+//! and strictness over speed: unsupported instructions and malformed code are reported as
+//! [`Error`]s rather than being silently ignored.
+//!
+//! Bit syntax is supported with match contexts (`bs_start_match3` and later) and `bs_create_bin`.
+//! Native endian segments are treated as little endian.
+//!
+//! Exceptions are caught by the catch tags that `try` and `catch` store in Y registers, and
+//! their stacktraces are made of the `line` instructions preceding the current position and the
+//! return addresses (see [`Module::lines`]).
+//!
+//! Messages are exchanged through a deterministic mailbox with a virtual clock: the process can
+//! send messages to itself and to scripted [`Peer`]s, and `receive` with a timeout advances the
+//! clock instead of sleeping.
+//!
+//! Calls to other modules are only supported for the built-in functions of `erlang`
+//! (e.g., arithmetic operators, `element/2` and `length/1`).
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::interp::{Import, Module, Process, Value};
+//!
+//! let code = beam_asm! {
+//!     label 1;
+//!     func_info m add 2;
+//!     label 2;
+//!     gc_bif2 {f,0} 2 0 {x,0} {x,1} {x,0};
+//!     return;
+//! };
+//! let module = Module {
+//!     code,
+//!     atoms: vec!["m".to_owned(), "add".to_owned()],
+//!     imports: vec![Import::new("erlang", "+", 2)],
+//!     ..Default::default()
+//! };
+//! let mut process = Process::new(&module);
+//! let result = process.call("add", vec![Value::from(1), Value::from(2)]).unwrap();
+//! assert_eq!(result, Value::from(3));
+//! ```
+use self::bits::Flags;
+use self::mailbox::{Mailbox, Wakeup, SELF};
+use crate::frame::HeapNeed;
+use crate::instruction::Instruction;
+use crate::term::{Allocation, Atom, Label, Register, Term, TypedRegister};
+use num::ToPrimitive;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+pub use self::bits::{Bitstring, MatchContext};
+pub use self::debugger::{Debugger, TraceEntry, Tracer};
+pub use self::mailbox::Peer;
+pub use self::value::Value;
+pub use crate::module::Import;
+
+mod bif;
+mod bits;
+mod debugger;
+mod mailbox;
+mod value;
+
+/// The code and tables of a module.
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    pub code: Vec<Instruction>,
+
+    /// The atom table (`AtU8` chunk).
+    ///
+    /// `atom(1)` refers to the first entry, which is the name of the module.
+    pub atoms: Vec<String>,
+
+    pub imports: Vec<Import>,
+
+    /// The literal table (`LitT` chunk).
+    pub literals: Vec<Value>,
+
+    /// The string table (`StrT` chunk) referred to by `bs_match_string` and `bs_create_bin`.
+    pub strings: Vec<u8>,
+
+    /// The line table (`Line` chunk).
+    ///
+    /// `line 1` refers to the first entry, and `line 0` to an unknown location.
+    pub lines: Vec<Location>,
+}
+
+impl Module {
+    fn name(&self) -> &str {
+        self.atoms.first().map_or("", |x| x.as_str())
+    }
+}
+
+/// A location in source code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Location {
+    pub fn new(file: &str, line: usize) -> Self {
+        Self {
+            file: file.to_owned(),
+            line,
+        }
+    }
+
+    // Makes the `[{file, File}, {line, Line}]` list of a stacktrace entry.
+    fn to_value(&self) -> Value {
+        let file = self
+            .file
+            .chars()
+            .map(|c| Value::from(i64::from(u32::from(c))));
+        Value::list(vec![
+            Value::tuple(vec![Value::atom("file"), Value::list(file.collect())]),
+            Value::tuple(vec![Value::atom("line"), Value::from(self.line as i64)]),
+        ])
+    }
+}
+
+/// The class of an [`Exception`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
+    Error,
+    Exit,
+    Throw,
+}
+
+impl Class {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Atom(x) if &**x == "error" => Some(Self::Error),
+            Value::Atom(x) if &**x == "exit" => Some(Self::Exit),
+            Value::Atom(x) if &**x == "throw" => Some(Self::Throw),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Exit => write!(f, "exit"),
+            Self::Throw => write!(f, "throw"),
+        }
+    }
+}
+
+/// An Erlang exception.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exception {
+    pub class: Class,
+    pub reason: Value,
+
+    /// The entries of the stacktrace (`{Module, Function, Arity, Location}` tuples).
+    ///
+    /// This is filled in when the exception is raised by a [`Process`].
+    pub stacktrace: Vec<Value>,
+}
+
+impl Exception {
+    pub fn new(class: Class, reason: Value) -> Self {
+        Self {
+            class,
+            reason,
+            stacktrace: Vec::new(),
+        }
+    }
+
+    pub fn error(reason: Value) -> Self {
+        Self::new(Class::Error, reason)
+    }
+
+    pub fn exit(reason: Value) -> Self {
+        Self::new(Class::Exit, reason)
+    }
+
+    pub fn throw(reason: Value) -> Self {
+        Self::new(Class::Throw, reason)
+    }
+
+    fn error_tuple(tag: &str, value: Value) -> Self {
+        Self::error(Value::tuple(vec![Value::atom(tag), value]))
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.class, self.reason)
+    }
+}
+
+/// Interpreter errors.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum Error {
+    #[error("uncaught exception: {0}")]
+    Exception(Exception),
+
+    #[error("undefined function: {module}:{function}/{arity}")]
+    UndefinedFunction {
+        module: String,
+        function: String,
+        arity: usize,
+    },
+
+    #[error("undefined label: {label}")]
+    UndefinedLabel { label: Label },
+
+    #[error("unsupported instruction: {name}")]
+    UnsupportedInstruction { name: &'static str },
+
+    #[error("invalid operand: {operand}")]
+    InvalidOperand { operand: String },
+
+    #[error("unexpected value: {value}")]
+    UnexpectedValue { value: Value },
+
+    #[error("no stack frame")]
+    NoStackFrame,
+
+    #[error("no exception has been caught")]
+    NoException,
+
+    #[error("process waits for a message that never arrives")]
+    Blocked,
+
+    #[error("unexpected end of code")]
+    UnexpectedEndOfCode,
+
+    #[error("process is not running")]
+    NotRunning,
+}
+
+impl From<Exception> for Error {
+    fn from(exception: Exception) -> Self {
+        Self::Exception(exception)
+    }
+}
+
+/// The result of [`Process::step()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Running,
+
+    /// The function called by [`Process::start()`] has returned the value.
+    Returned(Value),
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    y: Vec<Value>,
+    cp: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingTuple {
+    destination: Term,
+    arity: usize,
+    elements: Vec<Value>,
+}
+
+/// An Erlang process executing the code of a [`Module`].
+#[derive(Debug)]
+pub struct Process<'a> {
+    module: &'a Module,
+    labels: HashMap<usize, usize>,
+    pc: Option<usize>,
+    cp: Option<usize>,
+    x: Vec<Value>,
+    fr: Vec<f64>,
+    stack: Vec<Frame>,
+    tuple: Option<PendingTuple>,
+    caught: Option<Exception>,
+    mailbox: Mailbox<'a>,
+}
+
+impl<'a> Process<'a> {
+    /// Makes a new [`Process`] instance.
+    pub fn new(module: &'a Module) -> Self {
+        let labels = module
+            .code
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| match x {
+                Instruction::Label(x) => Some((x.literal, i)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            module,
+            labels,
+            pc: None,
+            cp: None,
+            x: Vec::new(),
+            fr: Vec::new(),
+            stack: Vec::new(),
+            tuple: None,
+            caught: None,
+            mailbox: Mailbox::new(),
+        }
+    }
+
+    /// Calls a function of the module and runs until it returns.
+    pub fn call(&mut self, function: &str, args: Vec<Value>) -> Result<Value, Error> {
+        self.start(function, args)?;
+        self.run()
+    }
+
+    /// Prepares to call a function of the module with the arguments in X registers.
+    ///
+    /// The execution state (including the stack) is reset, but the mailbox is kept.
+    pub fn start(&mut self, function: &str, args: Vec<Value>) -> Result<(), Error> {
+        let entry = self.function_entry(function, args.len())?;
+        self.pc = Some(entry);
+        self.cp = None;
+        self.x = args;
+        self.fr.clear();
+        self.stack.clear();
+        self.tuple = None;
+        self.caught = None;
+        Ok(())
+    }
+
+    /// Runs until the function called by [`Process::start()`] returns.
+    pub fn run(&mut self) -> Result<Value, Error> {
+        loop {
+            if let Status::Returned(value) = self.step()? {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> Result<Status, Error> {
+        let pc = self.pc.ok_or(Error::NotRunning)?;
+        let instruction = self.module.code.get(pc).ok_or(Error::UnexpectedEndOfCode)?;
+        self.pc = Some(pc + 1);
+        match self.execute(instruction) {
+            Ok(None) => Ok(Status::Running),
+            Ok(Some(value)) => {
+                self.pc = None;
+                Ok(Status::Returned(value))
+            }
+            Err(Error::Exception(exception)) => self.raise(exception, pc),
+            Err(e) => {
+                self.pc = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// Same as [`Process::step()`], but also passes the executed instruction and the resulting
+    /// registers to the tracer.
+    pub fn step_traced(&mut self, tracer: &mut dyn Tracer) -> Result<Status, Error> {
+        let position = self.pc.ok_or(Error::NotRunning)?;
+        let result = self.step();
+        if let Some(instruction) = self.module.code.get(position) {
+            tracer.trace(&TraceEntry {
+                position,
+                instruction: instruction.clone(),
+                x: self.x.clone(),
+                y: self.y_registers().to_vec(),
+                fr: self.fr.clone(),
+            });
+        }
+        result
+    }
+
+    /// Same as [`Process::run()`], but traces each instruction (see [`Process::step_traced()`]).
+    pub fn run_traced(&mut self, tracer: &mut dyn Tracer) -> Result<Value, Error> {
+        loop {
+            if let Status::Returned(value) = self.step_traced(tracer)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Returns the index of the instruction to be executed next, or `None` if not running.
+    pub fn position(&self) -> Option<usize> {
+        self.pc
+    }
+
+    pub fn x_registers(&self) -> &[Value] {
+        &self.x
+    }
+
+    /// Returns the Y registers of the current stack frame.
+    pub fn y_registers(&self) -> &[Value] {
+        self.stack.last().map_or(&[], |x| &x.y)
+    }
+
+    pub fn float_registers(&self) -> &[f64] {
+        &self.fr
+    }
+
+    /// Returns the stacktrace at the current position (see [`Exception::stacktrace`]).
+    pub fn stacktrace(&self) -> Vec<Value> {
+        self.pc
+            .map_or_else(Vec::new, |position| self.stacktrace_at(position))
+    }
+
+    /// Returns the process identifier of this process.
+    pub fn pid(&self) -> Value {
+        Value::Pid(SELF)
+    }
+
+    /// Adds a peer and returns its process identifier.
+    pub fn add_peer(&mut self, peer: impl Peer + 'a) -> Value {
+        self.mailbox.add_peer(peer)
+    }
+
+    /// Schedules a message to this process after `delay` milliseconds of virtual time.
+    pub fn send_after(&mut self, delay: u64, message: Value) {
+        self.mailbox.send_after(delay, message);
+    }
+
+    /// Returns the virtual time in milliseconds.
+    pub fn now(&self) -> u64 {
+        self.mailbox.now()
+    }
+
+    /// Returns the messages that have been delivered and not removed yet.
+    pub fn messages(&self) -> impl '_ + Iterator<Item = &Value> {
+        self.mailbox.messages()
+    }
+
+    fn function_entry(&self, function: &str, arity: usize) -> Result<usize, Error> {
+        self.module
+            .code
+            .iter()
+            .position(|x| {
+                matches!(x, Instruction::FuncInfo(x)
+                    if x.arity == arity && self.atom(x.function).is_atom(function))
+            })
+            .map(|i| i + 1)
+            .ok_or_else(|| Error::UndefinedFunction {
+                module: self.module.name().to_owned(),
+                function: function.to_owned(),
+                arity,
+            })
+    }
+
+    // Unwinds the stack to the innermost catch tag and jumps to its handler.
+    fn raise(&mut self, mut exception: Exception, position: usize) -> Result<Status, Error> {
+        if exception.stacktrace.is_empty() {
+            exception.stacktrace = self.stacktrace_at(position);
+        }
+
+        // Like the BEAM, the stack is scanned from the top, so lower Y registers come first.
+        let handler = self.stack.iter().enumerate().rev().find_map(|(i, frame)| {
+            frame.y.iter().find_map(|y| match y {
+                Value::CatchTag(label) => Some((i, *label)),
+                _ => None,
+            })
+        });
+        let Some((depth, label)) = handler else {
+            self.pc = None;
+            return Err(Error::Exception(exception));
+        };
+        self.stack.truncate(depth + 1);
+        self.cp = None;
+        self.tuple = None;
+        self.caught = Some(exception);
+        if let Err(e) = self.jump(label) {
+            self.pc = None;
+            return Err(e);
+        }
+        Ok(Status::Running)
+    }
+
+    fn stacktrace_at(&self, position: usize) -> Vec<Value> {
+        let returns = self
+            .cp
+            .iter()
+            .chain(self.stack.iter().rev().filter_map(|x| x.cp.as_ref()))
+            .map(|cp| cp - 1);
+        std::iter::once(position)
+            .chain(returns)
+            .filter_map(|position| self.stacktrace_entry(position))
+            .collect()
+    }
+
+    // Makes the `{Module, Function, Arity, Location}` tuple of the function containing the
+    // instruction at `position`.
+    fn stacktrace_entry(&self, position: usize) -> Option<Value> {
+        let mut line = None;
+        for instruction in self.module.code.get(..=position)?.iter().rev() {
+            match instruction {
+                Instruction::Line(x) if line.is_none() => line = Some(x.literal),
+                Instruction::FuncInfo(x) => {
+                    let location = line
+                        .and_then(|x| self.module.lines.get(x.checked_sub(1)?))
+                        .map_or(Value::Nil, Location::to_value);
+                    return Some(Value::tuple(vec![
+                        self.atom(x.module),
+                        self.atom(x.function),
+                        Value::from(x.arity as i64),
+                        location,
+                    ]));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn execute(&mut self, instruction: &Instruction) -> Result<Option<Value>, Error> {
+        use Instruction as I;
+
+        match instruction {
+            I::Label(_) | I::Line(_) | I::TestHeap(_) | I::Fclearerror(_) | I::Fcheckerror(_) => {}
+            I::FuncInfo(_) => return Err(Exception::error(Value::atom("function_clause")).into()),
+
+            // Calls.
+            I::Call(x) => {
+                self.cp = self.pc;
+                self.jump(x.label)?;
+            }
+            I::CallOnly(x) => self.jump(x.label)?,
+            I::CallLast(x) => {
+                self.deallocate()?;
+                self.jump(self.label(&x.arg2)?)?;
+            }
+            I::CallExt(x) => return self.call_ext(x.destination, false),
+            I::CallExtOnly(x) => return self.call_ext(x.destination, true),
+            I::CallExtLast(x) => {
+                self.deallocate()?;
+                return self.call_ext(x.destination, true);
+            }
+            I::Return(_) => return Ok(self.return_()),
+            I::Jump(x) => self.jump(x.label)?,
+
+            // Stack frames.
+            I::Allocate(x) => self.allocate(words(&x.stack_need)),
+            I::AllocateHeap(x) => self.allocate(words(&x.stack_need)),
+            I::AllocateZero(x) => self.allocate(self.usize(&x.arg1)?),
+            I::AllocateHeapZero(x) => self.allocate(words(&x.stack_need)),
+            I::Deallocate(_) => self.deallocate()?,
+            I::Trim(x) => {
+                let n = self.usize(&x.arg1)?;
+                let frame = self.stack.last_mut().ok_or(Error::NoStackFrame)?;
+                frame.y.drain(..n.min(frame.y.len()));
+            }
+            I::Init(x) => self.set(&x.arg1, Value::Nil)?,
+            I::InitYregs(x) => {
+                for register in &x.registers {
+                    self.set(&Term::YRegister(*register), Value::Nil)?;
+                }
+            }
+
+            // Moves and data construction.
+            I::Move(x) => self.set_register(&x.dst, self.get(&x.src)?)?,
+            I::Swap(x) => {
+                let a = self.get(&x.arg1)?;
+                let b = self.get(&x.arg2)?;
+                self.set(&x.arg1, b)?;
+                self.set(&x.arg2, a)?;
+            }
+            I::GetList(x) => {
+                let (head, tail) = self.cons(&x.source)?;
+                self.set_register(&x.head, head)?;
+                self.set_register(&x.tail, tail)?;
+            }
+            I::GetHd(x) => self.set(&x.arg2, self.cons(&x.arg1)?.0)?,
+            I::GetTl(x) => self.set(&x.arg2, self.cons(&x.arg1)?.1)?,
+            I::GetTupleElement(x) => {
+                let tuple = self.get_register(&x.source)?;
+                let element = self.tuple_elements(&tuple)?.get(x.element).cloned();
+                let element = element.ok_or(Error::UnexpectedValue { value: tuple })?;
+                self.set_register(&x.destination, element)?;
+            }
+            I::SetTupleElement(x) => {
+                let tuple = self.get(&x.arg2)?;
+                let mut elements = self.tuple_elements(&tuple)?.to_vec();
+                let element = elements
+                    .get_mut(self.usize(&x.arg3)?)
+                    .ok_or(Error::UnexpectedValue { value: tuple })?;
+                *element = self.get(&x.arg1)?;
+                self.set(&x.arg2, Value::tuple(elements))?;
+            }
+            I::PutList(x) => {
+                let value = Value::cons(self.get(&x.head)?, self.get(&x.tail)?);
+                self.set_register(&x.destination, value)?;
+            }
+            I::PutTuple2(x) => {
+                let elements = x
+                    .elements
+                    .items
+                    .iter()
+                    .map(|x| self.get(x))
+                    .collect::<Result<_, _>>()?;
+                self.set_register(&x.destination, Value::tuple(elements))?;
+            }
+            I::PutTuple(x) => {
+                self.tuple = Some(PendingTuple {
+                    destination: x.arg2.clone(),
+                    arity: self.usize(&x.arg1)?,
+                    elements: Vec::new(),
+                });
+                self.complete_tuple()?;
+            }
+            I::Put(x) => {
+                let value = self.get(&x.arg1)?;
+                let tuple = self.tuple.as_mut().ok_or_else(|| Error::InvalidOperand {
+                    operand: x.arg1.to_string(),
+                })?;
+                tuple.elements.push(value);
+                self.complete_tuple()?;
+            }
+
+            // Type tests and comparisons.
+            I::IsLt(x) => self.compare(&x.arg1, &x.arg2, &x.arg3, |o| o.is_lt())?,
+            I::IsGe(x) => self.compare(&x.arg1, &x.arg2, &x.arg3, |o| o.is_ge())?,
+            I::IsEq(x) => self.compare(&x.arg1, &x.arg2, &x.arg3, |o| o.is_eq())?,
+            I::IsNe(x) => self.compare(&x.arg1, &x.arg2, &x.arg3, |o| o.is_ne())?,
+            I::IsEqExact(x) => {
+                let ok = self.get(&x.arg1)? == self.get(&x.arg2)?;
+                self.test(x.label, ok)?;
+            }
+            I::IsNeExact(x) => {
+                let ok = self.get(&x.arg1)? != self.get(&x.arg2)?;
+                self.test(x.label, ok)?;
+            }
+            I::IsInteger(x) => {
+                self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Integer(_)))?
+            }
+            I::IsFloat(x) => self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Float(_)))?,
+            I::IsNumber(x) => self.test_type(x.label, &x.arg1, |v| v.to_f64().is_some())?,
+            I::IsAtom(x) => self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Atom(_)))?,
+            I::IsNil(x) => self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Nil))?,
+            I::IsList(x) => self.test_type(x.label, &x.arg1, |v| {
+                matches!(v, Value::Nil | Value::Cons(_))
+            })?,
+            I::IsNonemptyList(x) => {
+                self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Cons(_)))?
+            }
+            I::IsTuple(x) => self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Tuple(_)))?,
+            I::IsConstant(x) => self.test_type(x.label, &x.arg1, |v| {
+                !matches!(v, Value::Cons(_) | Value::Tuple(_))
+            })?,
+            I::IsBoolean(x) => {
+                self.test_type(self.label(&x.arg1)?, &x.arg2, |v| v.as_bool().is_some())?
+            }
+            I::TestArity(x) => {
+                let arity = x.arity;
+                self.test_type(
+                    x.label,
+                    &x.arg1,
+                    |v| matches!(v, Value::Tuple(t) if t.len() == arity),
+                )?
+            }
+            I::IsTaggedTuple(x) => {
+                let (arity, tag) = (x.arity, self.atom(x.atom));
+                let ok = matches!(self.get_register(&x.register)?, Value::Tuple(t)
+                    if t.len() == arity && t.first() == Some(&tag));
+                self.test(x.label, ok)?;
+            }
+            I::IsPid(x) => self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Pid(_)))?,
+            I::IsReference(x) => {
+                self.test_type(x.label, &x.arg1, |v| matches!(v, Value::Reference(_)))?
+            }
+            // There are no port, fun or map values.
+            I::IsPort(x) => self.test_type(x.label, &x.arg1, |_| false)?,
+            I::IsBinary(x) => self.test_type(
+                x.label,
+                &x.arg1,
+                |v| matches!(v, Value::Bitstring(x) if x.len().is_multiple_of(8)),
+            )?,
+            I::IsBitstr(x) => self.test_type(self.label(&x.arg1)?, &x.arg2, |v| {
+                matches!(v, Value::Bitstring(_))
+            })?,
+            I::IsFunction(x) => self.test_type(self.label(&x.arg1)?, &x.arg2, |_| false)?,
+            I::IsFunction2(x) => {
+                self.get(&x.arg3)?;
+                self.test_type(self.label(&x.arg1)?, &x.arg2, |_| false)?
+            }
+            I::IsMap(x) => self.test_type(self.label(&x.arg1)?, &x.arg2, |_| false)?,
+            I::SelectVal(x) => {
+                let value = self.get(&x.arg)?;
+                let mut target = x.fail_label;
+                for pair in x.destinations.items.chunks(2) {
+                    if let [candidate, label] = pair {
+                        if self.get(candidate)? == value {
+                            target = self.label(label)?;
+                            break;
+                        }
+                    }
+                }
+                self.jump(target)?;
+            }
+            I::SelectTupleArity(x) => {
+                let tuple = self.get(&x.arg1)?;
+                let mut target = self.label(&x.arg2)?;
+                if let (Value::Tuple(elements), Term::List(destinations)) = (&tuple, &x.arg3) {
+                    for pair in destinations.items.chunks(2) {
+                        if let [arity, label] = pair {
+                            if self.usize(arity)? == elements.len() {
+                                target = self.label(label)?;
+                                break;
+                            }
+                        }
+                    }
+                }
+                self.jump(target)?;
+            }
+
+            // Built-in functions.
+            I::Bif0(x) => self.bif(&Term::Label(Label { value: 0 }), &x.arg1, &[], &x.arg2)?,
+            I::Bif1(x) => self.bif(&x.arg1, &x.arg2, &[&x.arg3], &x.arg4)?,
+            I::Bif2(x) => self.bif(&x.arg1, &x.arg2, &[&x.arg3, &x.arg4], &x.arg5)?,
+            I::GcBif1(x) => self.bif(&x.arg1, &x.arg3, &[&x.arg4], &x.arg5)?,
+            I::GcBif2(x) => self.bif(&x.arg1, &x.arg3, &[&x.arg4, &x.arg5], &x.arg6)?,
+            I::GcBif3(x) => self.bif(&x.arg1, &x.arg3, &[&x.arg4, &x.arg5, &x.arg6], &x.arg7)?,
+
+            // Bit syntax.
+            I::BsStartMatch3(x) => {
+                let value = self.get(&x.bin)?;
+                match start_match(&value) {
+                    Some(context) => self.set_register(&x.destination, context)?,
+                    None => self.jump(x.fail)?,
+                }
+            }
+            I::BsStartMatch4(x) => {
+                let value = self.get(&x.arg3)?;
+                match (start_match(&value), &x.arg1) {
+                    (Some(context), _) => self.set(&x.arg4, context)?,
+                    (None, Term::Label(label)) => self.jump(*label)?,
+                    (None, _) => return Err(Error::UnexpectedValue { value }),
+                }
+            }
+            I::BsGetPosition(x) => {
+                let position = self.match_context(&x.context)?.position();
+                self.set_register(&x.destination, Value::from(position as i64))?;
+            }
+            I::BsSetPosition(x) => {
+                let context = self.match_context(&x.context)?;
+                let value = self.get(&x.position)?;
+                let position = value
+                    .as_usize()
+                    .filter(|x| *x <= context.bitstring().len())
+                    .ok_or(Error::UnexpectedValue { value })?;
+                context.set_position(position);
+            }
+            I::BsGetTail(x) => {
+                let context = self.match_context(&x.context)?;
+                let tail = context
+                    .bitstring()
+                    .slice(context.position(), context.remaining());
+                self.set_register(&x.destination, Value::from(tail))?;
+            }
+            I::BsGetInteger2(x) => {
+                let len = self.segment_len(&x.arg4, &x.arg5)?;
+                let flags = Flags::from_bits(self.usize(&x.arg6)?);
+                self.bs_get(&x.arg1, &x.arg2, Some(&x.arg7), |bits, start| {
+                    let value = bits::get_integer(bits, start, len?, flags)?;
+                    Some((Value::from(value), len?))
+                })?
+            }
+            I::BsGetFloat2(x) => {
+                let len = self.segment_len(&x.arg4, &x.arg5)?;
+                let flags = Flags::from_bits(self.usize(&x.arg6)?);
+                self.bs_get(&x.arg1, &x.arg2, Some(&x.arg7), |bits, start| {
+                    let value = bits::get_float(bits, start, len?, flags)?;
+                    Some((Value::from(value), len?))
+                })?
+            }
+            I::BsGetBinary2(x) => {
+                let len = self.binary_segment_len(&x.arg4, &x.arg5)?;
+                self.bs_get(&x.arg1, &x.arg2, Some(&x.arg7), |bits, start| {
+                    let len = len(bits.len() - start)?;
+                    (start + len <= bits.len()).then(|| (Value::from(bits.slice(start, len)), len))
+                })?
+            }
+            I::BsSkipBits2(x) => {
+                let len = self.binary_segment_len(&x.arg3, &x.arg4)?;
+                self.bs_get(&x.arg1, &x.arg2, None, |bits, start| {
+                    let len = len(bits.len() - start)?;
+                    (start + len <= bits.len()).then_some((Value::Nil, len))
+                })?
+            }
+            I::BsTestTail2(x) => {
+                let ok = self.match_context(&x.arg2)?.remaining() == self.usize(&x.arg3)?;
+                self.test(self.label(&x.arg1)?, ok)?;
+            }
+            I::BsTestUnit(x) => {
+                let remaining = self.match_context(&x.arg2)?.remaining();
+                let ok = remaining.is_multiple_of(self.usize(&x.arg3)?);
+                self.test(self.label(&x.arg1)?, ok)?;
+            }
+            I::BsMatchString(x) => {
+                let len = self.usize(&x.arg3)?;
+                let offset = self.usize(&x.arg4)?;
+                let string = self
+                    .module
+                    .strings
+                    .get(offset..offset + len.div_ceil(8))
+                    .ok_or_else(|| Error::InvalidOperand {
+                        operand: x.arg4.to_string(),
+                    })?;
+                let string = Bitstring::from(string).slice(0, len);
+                self.bs_get(&x.arg1, &x.arg2, None, |bits, start| {
+                    let ok = start + len <= bits.len() && bits.slice(start, len) == string;
+                    ok.then_some((Value::Nil, len))
+                })?
+            }
+            I::BsGetUtf8(x) => {
+                self.bs_get_utf(&x.arg1, &x.arg2, Some(&x.arg5), |bits, start| {
+                    bits::get_utf8(bits, start)
+                })?
+            }
+            I::BsSkipUtf8(x) => self.bs_get_utf(&x.arg1, &x.arg2, None, |bits, start| {
+                bits::get_utf8(bits, start)
+            })?,
+            I::BsGetUtf16(x) => {
+                let flags = Flags::from_bits(self.usize(&x.arg4)?);
+                self.bs_get_utf(&x.arg1, &x.arg2, Some(&x.arg5), |bits, start| {
+                    bits::get_utf16(bits, start, flags)
+                })?
+            }
+            I::BsSkipUtf16(x) => {
+                let flags = Flags::from_bits(self.usize(&x.arg4)?);
+                self.bs_get_utf(&x.arg1, &x.arg2, None, |bits, start| {
+                    bits::get_utf16(bits, start, flags)
+                })?
+            }
+            I::BsGetUtf32(x) => {
+                let flags = Flags::from_bits(self.usize(&x.arg4)?);
+                self.bs_get_utf(&x.arg1, &x.arg2, Some(&x.arg5), |bits, start| {
+                    bits::get_utf32(bits, start, flags)
+                })?
+            }
+            I::BsSkipUtf32(x) => {
+                let flags = Flags::from_bits(self.usize(&x.arg4)?);
+                self.bs_get_utf(&x.arg1, &x.arg2, None, |bits, start| {
+                    bits::get_utf32(bits, start, flags)
+                })?
+            }
+            I::BsCreateBin(x) => {
+                let Term::List(segments) = &x.arg6 else {
+                    return Err(Error::InvalidOperand {
+                        operand: x.arg6.to_string(),
+                    });
+                };
+                match self.create_bin(&segments.items) {
+                    Ok(bits) => self.set(&x.arg5, Value::from(bits))?,
+                    Err(Error::Exception(exception)) => self.fail(&x.arg1, exception)?,
+                    Err(e) => return Err(e),
+                }
+            }
+
+            // Exception handling.
+            I::Try(x) => self.set_register(&x.register, Value::CatchTag(x.label))?,
+            I::Catch(x) => self.set(&x.arg1, Value::CatchTag(self.label(&x.arg2)?))?,
+            I::TryEnd(x) => self.set_register(&x.register, Value::Nil)?,
+            I::CatchEnd(x) => {
+                self.set(&x.arg1, Value::Nil)?;
+                if let Some(exception) = self.caught.take() {
+                    let value = match exception.class {
+                        Class::Throw => exception.reason,
+                        Class::Error => {
+                            let stacktrace = Value::list(exception.stacktrace);
+                            let reason = Value::tuple(vec![exception.reason, stacktrace]);
+                            Value::tuple(vec![Value::atom("EXIT"), reason])
+                        }
+                        Class::Exit => Value::tuple(vec![Value::atom("EXIT"), exception.reason]),
+                    };
+                    self.set_x(0, value);
+                }
+            }
+            I::TryCase(x) => {
+                self.set_register(&x.register, Value::Nil)?;
+                let exception = self.caught.take().ok_or(Error::NoException)?;
+                self.set_x(0, Value::atom(&exception.class.to_string()));
+                self.set_x(1, exception.reason.clone());
+                self.set_x(2, Value::RawStacktrace(Rc::new(exception)));
+            }
+            I::TryCaseEnd(x) => {
+                return Err(Exception::error_tuple("try_clause", self.get(&x.arg1)?).into())
+            }
+            I::Raise(x) => {
+                let reason = self.get(&x.exc_value)?;
+                let exception = match self.get(&x.stacktrace)? {
+                    Value::RawStacktrace(raw) => Exception {
+                        reason,
+                        ..Exception::clone(&raw)
+                    },
+                    _ => Exception::error(reason),
+                };
+                return Err(exception.into());
+            }
+            I::RawRaise(_) => {
+                let class = self.get_x(0)?;
+                match Class::from_value(&class) {
+                    Some(class) => {
+                        let mut exception = Exception::new(class, self.get_x(1)?);
+                        exception.stacktrace = match self.get_x(2)? {
+                            Value::RawStacktrace(raw) => raw.stacktrace.clone(),
+                            value => value.to_vec().unwrap_or_default(),
+                        };
+                        return Err(exception.into());
+                    }
+                    None => self.set_x(0, Value::atom("badarg")),
+                }
+            }
+            I::BuildStacktrace(_) => match self.get_x(0)? {
+                Value::RawStacktrace(raw) => self.set_x(0, Value::list(raw.stacktrace.clone())),
+                value => return Err(Error::UnexpectedValue { value }),
+            },
+
+            // Messages.
+            I::Send(_) => {
+                let message = self.mailbox.send(&self.get_x(0)?, self.get_x(1)?)?;
+                self.x.truncate(1);
+                self.set_x(0, message);
+            }
+            I::LoopRec(x) => match self.mailbox.current() {
+                Some(message) => self.set(&x.arg2, message)?,
+                None => self.jump(self.label(&x.arg1)?)?,
+            },
+            I::LoopRecEnd(x) => {
+                self.mailbox.advance();
+                self.jump(self.label(&x.arg1)?)?;
+            }
+            I::RemoveMessage(_) => self.mailbox.remove(),
+            I::Timeout(_) => self.mailbox.timeout(),
+            I::Wait(x) => self.wait(&x.arg1, None)?,
+            I::WaitTimeout(x) => {
+                let timeout = self.get(&x.arg2)?;
+                let timeout = if timeout.is_atom("infinity") {
+                    None
+                } else {
+                    let timeout = timeout.as_integer().and_then(|x| x.to_u64());
+                    Some(timeout.ok_or_else(|| Exception::error(Value::atom("timeout_value")))?)
+                };
+                self.wait(&x.arg1, timeout)?;
+            }
+            I::RecvMarkerReserve(x) => {
+                let marker = self.mailbox.reserve_marker();
+                self.set(&x.arg1, marker)?;
+            }
+            I::RecvMarkerBind(x) => {
+                let marker = self.get(&x.arg1)?;
+                self.mailbox.bind_marker(&marker, &self.get(&x.arg2)?);
+            }
+            I::RecvMarkerClear(x) => self.mailbox.clear_marker(&self.get(&x.arg1)?),
+            I::RecvMarkerUse(x) => self.mailbox.use_marker(&self.get(&x.arg1)?),
+
+            // Floating point arithmetic.
+            I::Fmove(x) => {
+                let value = self.get(&x.arg1)?;
+                if let Term::FloatingPointRegister(r) = &x.arg2 {
+                    let Value::Float(f) = value else {
+                        return Err(Error::UnexpectedValue { value });
+                    };
+                    self.set_fr(r.value, f);
+                } else {
+                    self.set(&x.arg2, value)?;
+                }
+            }
+            I::Fconv(x) => {
+                let f = self.get(&x.arg1)?.to_f64();
+                let f = f.ok_or_else(|| Exception::error(Value::atom("badarith")))?;
+                self.set_fr(self.fr_index(&x.arg2)?, f);
+            }
+            I::Fadd(x) => self.float_op(&x.arg2, &x.arg3, &x.arg4, |a, b| a + b)?,
+            I::Fsub(x) => self.float_op(&x.arg2, &x.arg3, &x.arg4, |a, b| a - b)?,
+            I::Fmul(x) => self.float_op(&x.arg2, &x.arg3, &x.arg4, |a, b| a * b)?,
+            I::Fdiv(x) => self.float_op(&x.arg2, &x.arg3, &x.arg4, |a, b| a / b)?,
+            I::Fnegate(x) => self.float_op(&x.arg2, &x.arg2, &x.arg3, |a, _| -a)?,
+
+            // Exceptions.
+            I::Badmatch(x) => {
+                return Err(Exception::error_tuple("badmatch", self.get(&x.arg1)?).into())
+            }
+            I::CaseEnd(x) => {
+                return Err(Exception::error_tuple("case_clause", self.get(&x.arg1)?).into())
+            }
+            I::Badrecord(x) => {
+                return Err(Exception::error_tuple("badrecord", self.get(&x.arg1)?).into())
+            }
+            I::IfEnd(_) => return Err(Exception::error(Value::atom("if_clause")).into()),
+
+            _ => {
+                return Err(Error::UnsupportedInstruction {
+                    name: instruction.name(),
+                })
+            }
+        }
+        Ok(None)
+    }
+
+    fn jump(&mut self, label: Label) -> Result<(), Error> {
+        let position = self
+            .labels
+            .get(&label.value)
+            .ok_or(Error::UndefinedLabel { label })?;
+        self.pc = Some(*position);
+        Ok(())
+    }
+
+    fn test(&mut self, fail: Label, ok: bool) -> Result<(), Error> {
+        if !ok {
+            self.jump(fail)?;
+        }
+        Ok(())
+    }
+
+    fn test_type(
+        &mut self,
+        fail: Label,
+        term: &Term,
+        f: impl FnOnce(&Value) -> bool,
+    ) -> Result<(), Error> {
+        let ok = f(&self.get(term)?);
+        self.test(fail, ok)
+    }
+
+    fn compare(
+        &mut self,
+        fail: &Term,
+        a: &Term,
+        b: &Term,
+        f: impl FnOnce(std::cmp::Ordering) -> bool,
+    ) -> Result<(), Error> {
+        let ok = f(self.get(a)?.compare(&self.get(b)?));
+        self.test(self.label(fail)?, ok)
+    }
+
+    fn return_(&mut self) -> Option<Value> {
+        match self.cp.take() {
+            Some(cp) => {
+                self.pc = Some(cp);
+                None
+            }
+            None => Some(self.x.first().cloned().unwrap_or(Value::Nil)),
+        }
+    }
+
+    fn call_ext(&mut self, import: usize, tail: bool) -> Result<Option<Value>, Error> {
+        let import = self
+            .module
+            .imports
+            .get(import)
+            .ok_or_else(|| Error::InvalidOperand {
+                operand: import.to_string(),
+            })?;
+        if import.module == self.module.name() {
+            if !tail {
+                self.cp = self.pc;
+            }
+            self.pc = Some(self.function_entry(&import.function, import.arity)?);
+            return Ok(None);
+        }
+
+        let args = self.x.get(..import.arity).ok_or(Error::InvalidOperand {
+            operand: format!("x({})", import.arity.saturating_sub(1)),
+        })?;
+        let args = args.to_vec();
+        let value = self.call_bif(import, &args)??;
+        self.x.truncate(1);
+        self.set_x(0, value);
+        Ok(if tail { self.return_() } else { None })
+    }
+
+    fn bif(&mut self, fail: &Term, bif: &Term, args: &[&Term], dst: &Term) -> Result<(), Error> {
+        let import = self.usize(bif)?;
+        let import = self
+            .module
+            .imports
+            .get(import)
+            .ok_or_else(|| Error::InvalidOperand {
+                operand: bif.to_string(),
+            })?;
+        let args = args
+            .iter()
+            .map(|x| self.get(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        match self.call_bif(import, &args)? {
+            Ok(value) => self.set(dst, value),
+            Err(exception) => self.fail(fail, exception),
+        }
+    }
+
+    // Calls a built-in function, including those depending on the process (e.g., `self/0`).
+    fn call_bif(
+        &mut self,
+        import: &Import,
+        args: &[Value],
+    ) -> Result<Result<Value, Exception>, Error> {
+        let result = match (import.module.as_str(), import.function.as_str(), args) {
+            ("erlang", "self", []) => Some(Ok(self.pid())),
+            ("erlang", "make_ref", []) => Some(Ok(self.mailbox.make_ref())),
+            ("erlang", "send" | "!", [to, message]) => Some(self.mailbox.send(to, message.clone())),
+            _ => bif::call(&import.module, &import.function, args),
+        };
+        result.ok_or_else(|| Error::UndefinedFunction {
+            module: import.module.clone(),
+            function: import.function.clone(),
+            arity: import.arity,
+        })
+    }
+
+    fn wait(&mut self, label: &Term, timeout: Option<u64>) -> Result<(), Error> {
+        match self.mailbox.wait(timeout) {
+            Wakeup::Message => self.jump(self.label(label)?),
+            Wakeup::Timeout => Ok(()),
+            Wakeup::Never => Err(Error::Blocked),
+        }
+    }
+
+    // Jumps to the fail label, or raises the exception if it is `{f,0}`.
+    fn fail(&mut self, fail: &Term, exception: Exception) -> Result<(), Error> {
+        match self.label(fail)? {
+            Label { value: 0 } => Err(exception.into()),
+            label => self.jump(label),
+        }
+    }
+
+    // Matches a segment at the position of a match context.
+    //
+    // `f` returns the value and the length of the segment, or `None` if it does not match.
+    fn bs_get(
+        &mut self,
+        fail: &Term,
+        context: &Term,
+        dst: Option<&Term>,
+        f: impl FnOnce(&Bitstring, usize) -> Option<(Value, usize)>,
+    ) -> Result<(), Error> {
+        let context = self.match_context(context)?;
+        match f(context.bitstring(), context.position()) {
+            Some((value, len)) => {
+                context.set_position(context.position() + len);
+                match dst {
+                    Some(dst) => self.set(dst, value),
+                    None => Ok(()),
+                }
+            }
+            None => self.jump(self.label(fail)?),
+        }
+    }
+
+    fn bs_get_utf(
+        &mut self,
+        fail: &Term,
+        context: &Term,
+        dst: Option<&Term>,
+        f: impl FnOnce(&Bitstring, usize) -> Option<(char, usize)>,
+    ) -> Result<(), Error> {
+        self.bs_get(fail, context, dst, |bits, start| {
+            f(bits, start).map(|(c, len)| (Value::from(i64::from(u32::from(c))), len))
+        })
+    }
+
+    fn match_context(&self, term: &Term) -> Result<Rc<MatchContext>, Error> {
+        match self.get(term)? {
+            Value::MatchContext(context) => Ok(context),
+            value => Err(Error::UnexpectedValue { value }),
+        }
+    }
+
+    // Returns the length of a segment in bits, or `None` if the size is not a valid integer.
+    fn segment_len(&self, size: &Term, unit: &Term) -> Result<Option<usize>, Error> {
+        let unit = self.usize(unit)?;
+        Ok(self.get(size)?.as_usize().and_then(|x| x.checked_mul(unit)))
+    }
+
+    // Same as `segment_len()`, but returns a function of the remaining bits to support `all`.
+    fn binary_segment_len(
+        &self,
+        size: &Term,
+        unit: &Term,
+    ) -> Result<impl Fn(usize) -> Option<usize>, Error> {
+        let all = self.get(size)?.is_atom("all");
+        let unit = self.usize(unit)?;
+        let len = self.segment_len(size, &Term::Usize(unit))?;
+        Ok(move |remaining: usize| {
+            if all {
+                remaining.is_multiple_of(unit).then_some(remaining)
+            } else {
+                len
+            }
+        })
+    }
+
+    // Builds a bitstring from the segments of `bs_create_bin`.
+    fn create_bin(&self, segments: &[Term]) -> Result<Bitstring, Error> {
+        let badarg = || Exception::error(Value::atom("badarg"));
+        let mut output = Bitstring::new();
+        for segment in segments.chunks(6) {
+            let [Term::Atom(kind), _, unit, flags, src, size] = segment else {
+                return Err(Error::InvalidOperand {
+                    operand: format!("{segment:?}"),
+                });
+            };
+            let Value::Atom(kind) = self.atom(*kind) else {
+                return Err(Error::InvalidOperand {
+                    operand: format!("{segment:?}"),
+                });
+            };
+            let flags = self.segment_flags(flags)?;
+            let code_point = || {
+                let value = self.get(src)?;
+                let c = value.as_integer().and_then(|x| x.to_u32());
+                c.and_then(char::from_u32)
+                    .ok_or_else(|| Error::from(badarg()))
+            };
+            match &*kind {
+                "integer" => {
+                    let Value::Integer(value) = self.get(src)? else {
+                        return Err(badarg().into());
+                    };
+                    let len = self.segment_len(size, unit)?.ok_or_else(badarg)?;
+                    bits::put_integer(&mut output, &value, len, flags);
+                }
+                "float" => {
+                    let value = self.get(src)?.to_f64().ok_or_else(badarg)?;
+                    let len = self.segment_len(size, unit)?.ok_or_else(badarg)?;
+                    bits::put_float(&mut output, value, len, flags).ok_or_else(badarg)?;
+                }
+                "binary" | "append" | "private_append" => {
+                    let Value::Bitstring(value) = self.get(src)? else {
+                        return Err(badarg().into());
+                    };
+                    let len = self.binary_segment_len(size, unit)?(value.len())
+                        .filter(|x| *x <= value.len())
+                        .ok_or_else(badarg)?;
+                    output.extend(&value, 0, len);
+                }
+                "utf8" => bits::put_utf8(&mut output, code_point()?),
+                "utf16" => bits::put_utf16(&mut output, code_point()?, flags),
+                "utf32" => bits::put_utf32(&mut output, code_point()?, flags),
+                "string" => {
+                    let offset = self.usize(src)?;
+                    let string = self
+                        .module
+                        .strings
+                        .get(offset..offset + self.usize(size)?)
+                        .ok_or_else(|| Error::InvalidOperand {
+                            operand: src.to_string(),
+                        })?;
+                    output.extend(&Bitstring::from(string), 0, string.len() * 8);
+                }
+                _ => {
+                    return Err(Error::UnexpectedValue {
+                        value: Value::Atom(kind),
+                    })
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    // Decodes the flags of a `bs_create_bin` segment (`[]` or a literal list of atoms).
+    fn segment_flags(&self, term: &Term) -> Result<Flags, Error> {
+        let flags = self.get(term)?;
+        let flags = flags
+            .to_vec()
+            .ok_or(Error::UnexpectedValue { value: flags })?;
+        Ok(Flags {
+            little: flags
+                .iter()
+                .any(|x| x.is_atom("little") || x.is_atom("native")),
+            signed: flags.iter().any(|x| x.is_atom("signed")),
+        })
+    }
+
+    fn float_op(
+        &mut self,
+        a: &Term,
+        b: &Term,
+        dst: &Term,
+        f: impl FnOnce(f64, f64) -> f64,
+    ) -> Result<(), Error> {
+        let a = self.fr.get(self.fr_index(a)?).copied().unwrap_or(0.0);
+        let b = self.fr.get(self.fr_index(b)?).copied().unwrap_or(0.0);
+        let result = f(a, b);
+        if !result.is_finite() {
+            return Err(Exception::error(Value::atom("badarith")).into());
+        }
+        self.set_fr(self.fr_index(dst)?, result);
+        Ok(())
+    }
+
+    fn allocate(&mut self, size: usize) {
+        self.stack.push(Frame {
+            y: vec![Value::Nil; size],
+            cp: self.cp.take(),
+        });
+    }
+
+    fn deallocate(&mut self) -> Result<(), Error> {
+        let frame = self.stack.pop().ok_or(Error::NoStackFrame)?;
+        self.cp = frame.cp;
+        Ok(())
+    }
+
+    fn complete_tuple(&mut self) -> Result<(), Error> {
+        if let Some(tuple) = self.tuple.take_if(|x| x.elements.len() == x.arity) {
+            self.set(&tuple.destination, Value::tuple(tuple.elements))?;
+        }
+        Ok(())
+    }
+
+    fn cons(&self, term: &Term) -> Result<(Value, Value), Error> {
+        match self.get(term)? {
+            Value::Cons(cell) => Ok((cell.0.clone(), cell.1.clone())),
+            value => Err(Error::UnexpectedValue { value }),
+        }
+    }
+
+    fn tuple_elements<'b>(&self, value: &'b Value) -> Result<&'b [Value], Error> {
+        match value {
+            Value::Tuple(elements) => Ok(elements),
+            _ => Err(Error::UnexpectedValue {
+                value: value.clone(),
+            }),
+        }
+    }
+
+    fn atom(&self, atom: Atom) -> Value {
+        match atom.value.checked_sub(1) {
+            None => Value::Nil,
+            Some(i) => Value::atom(self.module.atoms.get(i).map_or("", |x| x.as_str())),
+        }
+    }
+
+    fn get(&self, term: &Term) -> Result<Value, Error> {
+        let invalid = || Error::InvalidOperand {
+            operand: term.to_string(),
+        };
+        match term {
+            Term::Usize(n) => Ok(Value::from(*n as i64)),
+            Term::Integer(n) => Ok(Value::Integer(n.clone())),
+            Term::Character(c) => Ok(Value::from(i64::from(u32::from(*c)))),
+            Term::Atom(x) => Ok(self.atom(*x)),
+            Term::XRegister(x) => self.x.get(x.value).cloned().ok_or_else(invalid),
+            Term::YRegister(x) => self.y_registers().get(x.value).cloned().ok_or_else(invalid),
+            Term::TypedRegister(TypedRegister::X { register, .. }) => {
+                self.x.get(register.value).cloned().ok_or_else(invalid)
+            }
+            Term::TypedRegister(TypedRegister::Y { register, .. }) => self
+                .y_registers()
+                .get(register.value)
+                .cloned()
+                .ok_or_else(invalid),
+            Term::FloatingPointRegister(x) => self
+                .fr
+                .get(x.value)
+                .map(|f| Value::Float(*f))
+                .ok_or_else(invalid),
+            Term::Literal(x) => self
+                .module
+                .literals
+                .get(x.value)
+                .cloned()
+                .ok_or_else(invalid),
+            Term::Label(_) | Term::List(_) | Term::AllocationList(_) => Err(invalid()),
+        }
+    }
+
+    fn get_x(&self, i: usize) -> Result<Value, Error> {
+        self.x.get(i).cloned().ok_or_else(|| Error::InvalidOperand {
+            operand: format!("x({i})"),
+        })
+    }
+
+    fn get_register(&self, register: &Register) -> Result<Value, Error> {
+        self.get(&register_term(register))
+    }
+
+    fn set(&mut self, term: &Term, value: Value) -> Result<(), Error> {
+        match term {
+            Term::XRegister(x) => self.set_x(x.value, value),
+            Term::TypedRegister(TypedRegister::X { register, .. }) => {
+                self.set_x(register.value, value)
+            }
+            Term::YRegister(x) => self.set_y(x.value, value)?,
+            Term::TypedRegister(TypedRegister::Y { register, .. }) => {
+                self.set_y(register.value, value)?
+            }
+            _ => {
+                return Err(Error::InvalidOperand {
+                    operand: term.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn set_register(&mut self, register: &Register, value: Value) -> Result<(), Error> {
+        self.set(&register_term(register), value)
+    }
+
+    fn set_x(&mut self, i: usize, value: Value) {
+        if self.x.len() <= i {
+            self.x.resize(i + 1, Value::Nil);
+        }
+        self.x[i] = value;
+    }
+
+    fn set_y(&mut self, i: usize, value: Value) -> Result<(), Error> {
+        let frame = self.stack.last_mut().ok_or(Error::NoStackFrame)?;
+        let y = frame.y.get_mut(i).ok_or_else(|| Error::InvalidOperand {
+            operand: format!("y({i})"),
+        })?;
+        *y = value;
+        Ok(())
+    }
+
+    fn set_fr(&mut self, i: usize, value: f64) {
+        if self.fr.len() <= i {
+            self.fr.resize(i + 1, 0.0);
+        }
+        self.fr[i] = value;
+    }
+
+    fn fr_index(&self, term: &Term) -> Result<usize, Error> {
+        match term {
+            Term::FloatingPointRegister(x) => Ok(x.value),
+            _ => Err(Error::InvalidOperand {
+                operand: term.to_string(),
+            }),
+        }
+    }
+
+    fn usize(&self, term: &Term) -> Result<usize, Error> {
+        match term {
+            Term::Usize(n) => Ok(*n),
+            _ => Err(Error::InvalidOperand {
+                operand: term.to_string(),
+            }),
+        }
+    }
+
+    fn label(&self, term: &Term) -> Result<Label, Error> {
+        match term {
+            Term::Label(label) => Ok(*label),
+            _ => Err(Error::InvalidOperand {
+                operand: term.to_string(),
+            }),
+        }
+    }
+}
+
+fn register_term(register: &Register) -> Term {
+    match register {
+        Register::X(x) => Term::XRegister(*x),
+        Register::Y(x) => Term::YRegister(*x),
+        Register::Typed(x) => Term::TypedRegister(*x),
+    }
+}
+
+// Makes a match context, or returns an existing one.
+fn start_match(value: &Value) -> Option<Value> {
+    match value {
+        Value::Bitstring(bits) => Some(Value::MatchContext(Rc::new(MatchContext::new(
+            bits.clone(),
+        )))),
+        Value::MatchContext(_) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn words(allocation: &Allocation) -> usize {
+    HeapNeed::from(allocation).words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    fn module(code: Vec<Instruction>, atoms: &[&str], imports: Vec<Import>) -> Module {
+        Module {
+            code,
+            atoms: atoms.iter().map(|x| x.to_string()).collect(),
+            imports,
+            literals: vec![Value::tuple(vec![Value::atom("ok"), Value::from(1)])],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn interpreter_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info m fact 1;
+            label 2;
+            is_eq_exact {f,3} {x,0} {integer,0};
+            move {integer,1} {x,0};
+            return;
+            label 3;
+            allocate 1 1;
+            move {x,0} {y,0};
+            gc_bif2 {f,0} 1 0 {x,0} {integer,1} {x,0};
+            call 1 {f,2};
+            gc_bif2 {f,0} 1 1 {y,0} {x,0} {x,0};
+            deallocate 1;
+            return;
+
+            label 4;
+            func_info m classify 1;
+            label 5;
+            is_tuple {f,7} {x,0};
+            select_tuple_arity {x,0} {f,7} {list,[2, {f,6}]};
+            label 6;
+            get_tuple_element {x,0} 1 {x,1};
+            put_list {x,1} {atom,0} {x,1};
+            put_tuple2 {x,0} [{atom,1}, {x,1}];
+            return;
+            label 7;
+            select_val {x,0} {f,8} [ok, {f,9}];
+            label 8;
+            case_end {x,0};
+            label 9;
+            move {literal,0} {x,0};
+            call_only 1 {f,5};
+
+            label 10;
+            func_info m kind 1;
+            label 11;
+            is_map {f,12} {x,0};
+            return;
+            label 12;
+            is_function {f,13} {x,1};
+            return;
+            label 13;
+            return;
+        };
+        let module = module(
+            code,
+            &["m", "fact", "classify", "ok", "kind"],
+            vec![Import::new("erlang", "-", 2), Import::new("erlang", "*", 2)],
+        );
+        let mut process = Process::new(&module);
+
+        let result = process.call("fact", vec![Value::from(25)]).unwrap();
+        assert_eq!(result.to_string(), "15511210043330985984000000");
+
+        let result = process.call("classify", vec![Value::atom("ok")]).unwrap();
+        assert_eq!(result.to_string(), "{m,[1]}");
+
+        let error = process
+            .call("classify", vec![Value::from(1.5)])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "uncaught exception: error:{case_clause,1.5}"
+        );
+
+        let error = process.call("fact", vec![Value::atom("a")]).unwrap_err();
+        assert_eq!(error.to_string(), "uncaught exception: error:badarith");
+
+        assert!(matches!(
+            process.call("fact", vec![]),
+            Err(Error::UndefinedFunction { .. })
+        ));
+
+        assert!(matches!(
+            process.call("kind", vec![Value::from(1)]),
+            Err(Error::InvalidOperand { operand }) if operand == "x(1)"
+        ));
+    }
+
+    #[test]
+    fn bit_syntax_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info m parse 1;
+            label 2;
+            bs_start_match4 {f,3} 1 {x,0} {x,0};
+            bs_match_string {f,3} {x,0} 16 0;
+            bs_get_integer2 {f,3} {x,0} 1 {integer,8} 1 0 {x,1};
+            bs_get_position {x,0} {x,2} 2;
+            bs_get_utf8 {f,3} {x,0} 3 0 {x,3};
+            bs_get_binary2 {f,3} {x,0} 4 {x,1} 8 0 {x,4};
+            bs_get_integer2 {f,3} {x,0} 5 {integer,16} 1 6 {x,5};
+            bs_test_tail2 {f,3} {x,0} 3;
+            bs_get_tail {x,0} {x,6} 7;
+            bs_set_position {x,0} {x,2};
+            bs_get_utf8 {f,3} {x,0} 7 0 {x,8};
+            bs_create_bin {f,0} 0 9 1 {x,7} {list,[
+                utf16, 1, 1, {literal,0}, {x,3}, undefined,
+                binary, 2, 8, {atom,0}, {x,4}, all,
+                integer, 3, 1, {atom,0}, {x,5}, {integer,4},
+                string, 4, 8, {atom,0}, 0, 2
+            ]};
+            put_tuple2 {x,0} [{x,1}, {x,3}, {x,4}, {x,5}, {x,6}, {x,7}, {x,8}];
+            return;
+            label 3;
+            move nomatch {x,0};
+            return;
+        };
+        let module = Module {
+            code,
+            atoms: [
+                "m",
+                "parse",
+                "utf16",
+                "undefined",
+                "binary",
+                "all",
+                "integer",
+                "string",
+                "nomatch",
+            ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect(),
+            literals: vec![Value::list(vec![Value::atom("little")])],
+            strings: b"hi".to_vec(),
+            ..Default::default()
+        };
+        let mut process = Process::new(&module);
+
+        let input = b"hi\x03\xc3\xa9abc\xfe\xff\xa0";
+        let input = Bitstring::from(&input[..]).slice(0, 83);
+        let result = process.call("parse", vec![Value::from(input)]).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "{3,233,<<97,98,99>>,-2,<<5:3>>,<<233,0,97,98,99,230,134,9:4>>,233}"
+        );
+
+        let result = process.call("parse", vec![Value::binary(b"ho")]).unwrap();
+        assert!(result.is_atom("nomatch"));
+        let result = process.call("parse", vec![Value::from(1)]).unwrap();
+        assert!(result.is_atom("nomatch"));
+    }
+
+    #[test]
+    fn exceptions_work() {
+        let code = beam_asm! {
+            label 1;
+            func_info m safe_div 2;
+            label 2;
+            allocate 1 2;
+            try {y,0} {f,3};
+            line 1;
+            call 2 {f,6};
+            try_end {y,0};
+            deallocate 1;
+            return;
+            label 3;
+            try_case {y,0};
+            is_eq_exact {f,4} {x,0} throw;
+            move {x,1} {x,0};
+            deallocate 1;
+            return;
+            label 4;
+            raw_raise;
+
+            label 5;
+            func_info m do_div 2;
+            label 6;
+            is_ne_exact {f,7} {x,1} {integer,0};
+            line 2;
+            gc_bif2 {f,0} 2 0 {x,0} {x,1} {x,0};
+            return;
+            label 7;
+            move zero {x,0};
+            line 3;
+            call_ext_only 1 1;
+
+            label 8;
+            func_info m catch_div 2;
+            label 9;
+            allocate 1 2;
+            catch {y,0} {f,10};
+            call 2 {f,6};
+            label 10;
+            catch_end {y,0};
+            deallocate 1;
+            return;
+        };
+        let module = Module {
+            code,
+            atoms: ["m", "safe_div", "throw", "do_div", "zero", "catch_div"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            imports: vec![
+                Import::new("erlang", "div", 2),
+                Import::new("erlang", "throw", 1),
+            ],
+            lines: vec![
+                Location::new("m", 10),
+                Location::new("m", 20),
+                Location::new("m", 30),
+            ],
+            ..Default::default()
+        };
+        let mut process = Process::new(&module);
+        let call = |process: &mut Process, function, a: Value, b: Value| {
+            process.call(function, vec![a, b]).map(|x| x.to_string())
+        };
+
+        let result = call(&mut process, "safe_div", Value::from(6), Value::from(3));
+        assert_eq!(result.unwrap(), "2");
+        let result = call(&mut process, "safe_div", Value::from(1), Value::from(0));
+        assert_eq!(result.unwrap(), "zero");
+        let result = call(&mut process, "safe_div", Value::atom("a"), Value::from(1));
+        let Err(Error::Exception(exception)) = result else {
+            panic!("{result:?}");
+        };
+        assert_eq!(exception.class, Class::Error);
+        assert!(exception.reason.is_atom("badarith"));
+        assert_eq!(
+            Value::list(exception.stacktrace).to_string(),
+            "[{m,do_div,2,[{file,[109]},{line,20}]},{m,safe_div,2,[{file,[109]},{line,10}]}]"
+        );
+
+        let result = call(&mut process, "catch_div", Value::from(1), Value::from(0));
+        assert_eq!(result.unwrap(), "zero");
+        let result = call(&mut process, "catch_div", Value::atom("a"), Value::from(1));
+        assert_eq!(
+            result.unwrap(),
+            "{'EXIT',{badarith,[{m,do_div,2,[{file,[109]},{line,20}]},{m,catch_div,2,[]}]}}"
+        );
+    }
+
+    #[test]
+    fn receive_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info m rpc 1;
+            label 2;
+            allocate 2 1;
+            move {x,0} {y,0};
+            recv_marker_reserve {x,1};
+            bif0 0 {x,2};
+            recv_marker_bind {x,1} {x,2};
+            move {x,2} {y,1};
+            bif0 1 {x,1};
+            put_tuple2 {x,1} [{x,1}, {x,2}];
+            move {y,0} {x,0};
+            send;
+            recv_marker_use {y,1};
+            label 3;
+            loop_rec {f,5} {x,0};
+            is_tagged_tuple {f,4} {x,0} 3 pong;
+            get_tuple_element {x,0} 1 {x,1};
+            is_eq_exact {f,4} {x,1} {y,1};
+            get_tuple_element {x,0} 2 {x,0};
+            remove_message;
+            recv_marker_clear {y,1};
+            deallocate 2;
+            return;
+            label 4;
+            loop_rec_end {f,3};
+            label 5;
+            wait_timeout {f,3} {integer,100};
+            timeout;
+            recv_marker_clear {y,1};
+            move timeout {x,0};
+            deallocate 2;
+            return;
+
+            label 6;
+            func_info m block 0;
+            label 7;
+            loop_rec {f,8} {x,0};
+            loop_rec_end {f,7};
+            label 8;
+            wait {f,7};
+        };
+        let module = Module {
+            code,
+            atoms: ["m", "rpc", "pong", "timeout", "block"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            imports: vec![
+                Import::new("erlang", "make_ref", 0),
+                Import::new("erlang", "self", 0),
+            ],
+            ..Default::default()
+        };
+        let mut process = Process::new(&module);
+        let server = process.add_peer(|message: Value| {
+            let Value::Tuple(request) = message else {
+                return Vec::new();
+            };
+            let reply =
+                |reference, n| Value::tuple(vec![Value::atom("pong"), reference, Value::from(n)]);
+            vec![
+                (10, reply(request[1].clone(), 42)),
+                (5, reply(Value::Reference(99), 0)),
+            ]
+        });
+        let silent = process.add_peer(|_| Vec::new());
+        process.send_after(0, Value::atom("noise"));
+
+        let result = process.call("rpc", vec![server]).unwrap();
+        assert_eq!(result, Value::from(42));
+        assert_eq!(process.now(), 10);
+        let messages = process
+            .messages()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["noise", "{pong,#Ref<0.0.0.99>,0}"]);
+
+        let result = process.call("rpc", vec![silent]).unwrap();
+        assert!(result.is_atom("timeout"));
+        assert_eq!(process.now(), 110);
+
+        assert!(matches!(process.call("block", vec![]), Err(Error::Blocked)));
+    }
+}
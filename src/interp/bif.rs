@@ -0,0 +1,200 @@
+use super::{Exception, Value};
+use num::{BigInt, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+
+type BifResult = Result<Value, Exception>;
+
+/// Calls a built-in function, or returns `None` if it is not implemented.
+pub(super) fn call(module: &str, function: &str, args: &[Value]) -> Option<BifResult> {
+    if module != "erlang" {
+        return None;
+    }
+    let result = match (function, args) {
+        ("+", [a, b]) => arith(a, b, |a, b| Some(a + b), |a, b| a + b),
+        ("-", [a, b]) => arith(a, b, |a, b| Some(a - b), |a, b| a - b),
+        ("*", [a, b]) => arith(a, b, |a, b| Some(a * b), |a, b| a * b),
+        ("/", [a, b]) => match (a.to_f64(), b.to_f64()) {
+            (Some(a), Some(b)) => float(a / b),
+            _ => Err(badarith()),
+        },
+        ("div", [a, b]) => integer_arith(a, b, |a, b| (!b.is_zero()).then(|| a / b)),
+        ("rem", [a, b]) => integer_arith(a, b, |a, b| (!b.is_zero()).then(|| a % b)),
+        ("band", [a, b]) => integer_arith(a, b, |a, b| Some(a & b)),
+        ("bor", [a, b]) => integer_arith(a, b, |a, b| Some(a | b)),
+        ("bxor", [a, b]) => integer_arith(a, b, |a, b| Some(a ^ b)),
+        ("bsl", [a, b]) => integer_arith(a, b, |a, b| shift(a, b.to_i64()?)),
+        ("bsr", [a, b]) => integer_arith(a, b, |a, b| shift(a, -b.to_i64()?)),
+        ("bnot", [a]) => match a {
+            Value::Integer(a) => Ok(Value::Integer(!a)),
+            _ => Err(badarith()),
+        },
+        ("-", [a]) => match a {
+            Value::Integer(a) => Ok(Value::Integer(-a)),
+            Value::Float(a) => Ok(Value::Float(-a)),
+            _ => Err(badarith()),
+        },
+        ("+", [a]) => match a {
+            Value::Integer(_) | Value::Float(_) => Ok(a.clone()),
+            _ => Err(badarith()),
+        },
+        ("abs", [a]) => match a {
+            Value::Integer(a) => Ok(Value::Integer(a.abs())),
+            Value::Float(a) => Ok(Value::Float(a.abs())),
+            _ => Err(badarg()),
+        },
+        ("float", [a]) => a.to_f64().map(Value::Float).ok_or_else(badarg),
+        ("trunc", [a]) => match a {
+            Value::Integer(_) => Ok(a.clone()),
+            Value::Float(a) => float_to_integer(a.trunc()),
+            _ => Err(badarg()),
+        },
+        ("round", [a]) => match a {
+            Value::Integer(_) => Ok(a.clone()),
+            Value::Float(a) => float_to_integer(a.round()),
+            _ => Err(badarg()),
+        },
+        ("max", [a, b]) => Ok(if a.compare(b) == Ordering::Less { b } else { a }.clone()),
+        ("min", [a, b]) => Ok(if b.compare(a) == Ordering::Less { b } else { a }.clone()),
+
+        ("==", [a, b]) => Ok(Value::boolean(a.compare(b).is_eq())),
+        ("/=", [a, b]) => Ok(Value::boolean(a.compare(b).is_ne())),
+        ("=:=", [a, b]) => Ok(Value::boolean(a == b)),
+        ("=/=", [a, b]) => Ok(Value::boolean(a != b)),
+        ("<", [a, b]) => Ok(Value::boolean(a.compare(b).is_lt())),
+        (">", [a, b]) => Ok(Value::boolean(a.compare(b).is_gt())),
+        ("=<", [a, b]) => Ok(Value::boolean(a.compare(b).is_le())),
+        (">=", [a, b]) => Ok(Value::boolean(a.compare(b).is_ge())),
+        ("not", [a]) => a.as_bool().map(|a| Value::boolean(!a)).ok_or_else(badarg),
+        ("and", [a, b]) => boolean(a, b, |a, b| a && b),
+        ("or", [a, b]) => boolean(a, b, |a, b| a || b),
+        ("xor", [a, b]) => boolean(a, b, |a, b| a ^ b),
+
+        ("is_integer", [a]) => Ok(Value::boolean(matches!(a, Value::Integer(_)))),
+        ("is_float", [a]) => Ok(Value::boolean(matches!(a, Value::Float(_)))),
+        ("is_number", [a]) => Ok(Value::boolean(a.to_f64().is_some())),
+        ("is_atom", [a]) => Ok(Value::boolean(matches!(a, Value::Atom(_)))),
+        ("is_boolean", [a]) => Ok(Value::boolean(a.as_bool().is_some())),
+        ("is_list", [a]) => Ok(Value::boolean(matches!(a, Value::Nil | Value::Cons(_)))),
+        ("is_tuple", [a]) => Ok(Value::boolean(matches!(a, Value::Tuple(_)))),
+        ("is_binary", [a]) => Ok(Value::boolean(
+            matches!(a, Value::Bitstring(x) if x.len().is_multiple_of(8)),
+        )),
+        ("is_bitstring", [a]) => Ok(Value::boolean(matches!(a, Value::Bitstring(_)))),
+        ("is_pid", [a]) => Ok(Value::boolean(matches!(a, Value::Pid(_)))),
+        ("is_reference", [a]) => Ok(Value::boolean(matches!(a, Value::Reference(_)))),
+
+        ("hd", [Value::Cons(cell)]) => Ok(cell.0.clone()),
+        ("tl", [Value::Cons(cell)]) => Ok(cell.1.clone()),
+        ("length", [a]) => a
+            .to_vec()
+            .map(|x| Value::from(x.len() as i64))
+            .ok_or_else(badarg),
+        ("++", [a, b]) => a
+            .to_vec()
+            .map(|x| {
+                x.into_iter()
+                    .rev()
+                    .fold(b.clone(), |tail, head| Value::cons(head, tail))
+            })
+            .ok_or_else(badarg),
+        ("tuple_size" | "size", [Value::Tuple(x)]) => Ok(Value::from(x.len() as i64)),
+        ("byte_size", [Value::Bitstring(x)]) => Ok(Value::from(x.len().div_ceil(8) as i64)),
+        ("size", [Value::Bitstring(x)]) => Ok(Value::from((x.len() / 8) as i64)),
+        ("bit_size", [Value::Bitstring(x)]) => Ok(Value::from(x.len() as i64)),
+        ("element", [n, Value::Tuple(x)]) => n
+            .as_usize()
+            .and_then(|n| x.get(n.checked_sub(1)?))
+            .cloned()
+            .ok_or_else(badarg),
+        ("setelement", [n, Value::Tuple(x), value]) => {
+            let mut elements = x.to_vec();
+            match n
+                .as_usize()
+                .and_then(|n| elements.get_mut(n.checked_sub(1)?))
+            {
+                Some(element) => {
+                    *element = value.clone();
+                    Ok(Value::tuple(elements))
+                }
+                None => Err(badarg()),
+            }
+        }
+        ("tuple_to_list", [Value::Tuple(x)]) => Ok(Value::list(x.to_vec())),
+        ("list_to_tuple", [a]) => a.to_vec().map(Value::tuple).ok_or_else(badarg),
+
+        ("error", [reason]) => Err(Exception::error(reason.clone())),
+        ("exit", [reason]) => Err(Exception::exit(reason.clone())),
+        ("throw", [reason]) => Err(Exception::throw(reason.clone())),
+
+        ("hd" | "tl" | "tuple_size" | "size" | "element" | "setelement", _)
+        | ("tuple_to_list" | "byte_size" | "bit_size", _) => Err(badarg()),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn badarg() -> Exception {
+    Exception::error(Value::atom("badarg"))
+}
+
+fn badarith() -> Exception {
+    Exception::error(Value::atom("badarith"))
+}
+
+fn float(x: f64) -> BifResult {
+    if x.is_finite() {
+        Ok(Value::Float(x))
+    } else {
+        Err(badarith())
+    }
+}
+
+fn float_to_integer(x: f64) -> BifResult {
+    num::FromPrimitive::from_f64(x)
+        .map(Value::Integer)
+        .ok_or_else(badarg)
+}
+
+fn arith(
+    a: &Value,
+    b: &Value,
+    integer: impl FnOnce(&BigInt, &BigInt) -> Option<BigInt>,
+    float_op: impl FnOnce(f64, f64) -> f64,
+) -> BifResult {
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            integer(a, b).map(Value::Integer).ok_or_else(badarith)
+        }
+        _ => match (a.to_f64(), b.to_f64()) {
+            (Some(a), Some(b)) => float(float_op(a, b)),
+            _ => Err(badarith()),
+        },
+    }
+}
+
+fn integer_arith(
+    a: &Value,
+    b: &Value,
+    f: impl FnOnce(&BigInt, &BigInt) -> Option<BigInt>,
+) -> BifResult {
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => f(a, b).map(Value::Integer).ok_or_else(badarith),
+        _ => Err(badarith()),
+    }
+}
+
+// Shifts `a` left by `n` bits (right if `n` is negative). Huge left shifts are rejected.
+fn shift(a: &BigInt, n: i64) -> Option<BigInt> {
+    if n >= 0 {
+        (n < 1 << 24).then(|| a << n as usize)
+    } else {
+        Some(a >> n.unsigned_abs().min(a.bits() + 1) as usize)
+    }
+}
+
+fn boolean(a: &Value, b: &Value, f: impl FnOnce(bool, bool) -> bool) -> BifResult {
+    match (a.as_bool(), b.as_bool()) {
+        (Some(a), Some(b)) => Ok(Value::boolean(f(a, b))),
+        _ => Err(badarg()),
+    }
+}
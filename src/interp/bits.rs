@@ -0,0 +1,316 @@
+use num::{BigInt, BigUint, Integer, One, ToPrimitive, Zero};
+use std::cell::Cell;
+use std::rc::Rc;
+
+// Field flags of the `bs_get_*` and `bs_skip_*` instructions.
+const FLAG_LITTLE: usize = 0x02;
+const FLAG_SIGNED: usize = 0x04;
+const FLAG_NATIVE: usize = 0x10;
+
+/// A sequence of bits.
+///
+/// A bitstring whose length is a multiple of 8 is a binary.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bitstring {
+    // The unused bits of the last byte are zero, so equal bitstrings have the same representation.
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Bitstring {
+    /// Makes an empty [`Bitstring`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the length in bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bytes, the last of which is padded with zero bits if the length is not a
+    /// multiple of 8.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub(super) fn bit(&self, i: usize) -> bool {
+        self.bytes[i / 8] & (0x80 >> (i % 8)) != 0
+    }
+
+    pub(super) fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            self.bytes[self.len / 8] |= 0x80 >> (self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    /// Appends `len` bits of `other` starting at `start`.
+    pub(super) fn extend(&mut self, other: &Self, start: usize, len: usize) {
+        if self.len.is_multiple_of(8) && start.is_multiple_of(8) && len.is_multiple_of(8) {
+            self.bytes
+                .extend_from_slice(&other.bytes[start / 8..(start + len) / 8]);
+            self.len += len;
+        } else {
+            for i in start..start + len {
+                self.push(other.bit(i));
+            }
+        }
+    }
+
+    pub(super) fn slice(&self, start: usize, len: usize) -> Self {
+        let mut bits = Self::new();
+        bits.extend(self, start, len);
+        bits
+    }
+
+    // Reads an unsigned integer of `len` bits.
+    fn read(&self, start: usize, len: usize, little: bool) -> Option<BigUint> {
+        if self.len < start.checked_add(len)? {
+            return None;
+        }
+        let mut value = BigUint::zero();
+        if little {
+            // The first byte is the least significant one, and the last one may be partial.
+            for (k, chunk) in (start..start + len).step_by(8).enumerate() {
+                let mut byte = 0u32;
+                for i in chunk..(chunk + 8).min(start + len) {
+                    byte = byte << 1 | u32::from(self.bit(i));
+                }
+                value |= BigUint::from(byte) << (8 * k);
+            }
+        } else {
+            for i in start..start + len {
+                value = value << 1u8 | BigUint::from(u8::from(self.bit(i)));
+            }
+        }
+        Some(value)
+    }
+
+    // Writes the `len` least significant bits of an unsigned integer.
+    fn write(&mut self, value: &BigUint, len: usize, little: bool) {
+        if little {
+            for chunk in (0..len).step_by(8) {
+                let end = (chunk + 8).min(len);
+                for i in (chunk..end).rev() {
+                    self.push(value.bit(i as u64));
+                }
+            }
+        } else {
+            for i in (0..len).rev() {
+                self.push(value.bit(i as u64));
+            }
+        }
+    }
+}
+
+impl From<&[u8]> for Bitstring {
+    fn from(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            len: bytes.len() * 8,
+        }
+    }
+}
+
+/// The state of matching a bitstring.
+#[derive(Debug, PartialEq)]
+pub struct MatchContext {
+    bitstring: Rc<Bitstring>,
+    position: Cell<usize>,
+}
+
+impl MatchContext {
+    pub(super) fn new(bitstring: Rc<Bitstring>) -> Self {
+        Self {
+            bitstring,
+            position: Cell::new(0),
+        }
+    }
+
+    pub fn bitstring(&self) -> &Bitstring {
+        &self.bitstring
+    }
+
+    /// Returns the position of the next bit to be matched.
+    pub fn position(&self) -> usize {
+        self.position.get()
+    }
+
+    pub(super) fn set_position(&self, position: usize) {
+        self.position.set(position);
+    }
+
+    /// Returns the number of bits not matched yet.
+    pub fn remaining(&self) -> usize {
+        self.bitstring.len() - self.position()
+    }
+}
+
+/// The flags of a segment.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Flags {
+    pub little: bool,
+    pub signed: bool,
+}
+
+impl Flags {
+    /// Decodes the field flags operand.
+    ///
+    /// `native` is treated as little endian.
+    pub fn from_bits(bits: usize) -> Self {
+        Self {
+            little: bits & (FLAG_LITTLE | FLAG_NATIVE) != 0,
+            signed: bits & FLAG_SIGNED != 0,
+        }
+    }
+}
+
+pub(super) fn get_integer(
+    bits: &Bitstring,
+    start: usize,
+    len: usize,
+    flags: Flags,
+) -> Option<BigInt> {
+    let value = BigInt::from(bits.read(start, len, flags.little)?);
+    if flags.signed && len > 0 && value.bit(len as u64 - 1) {
+        Some(value - (BigInt::one() << len))
+    } else {
+        Some(value)
+    }
+}
+
+pub(super) fn put_integer(bits: &mut Bitstring, value: &BigInt, len: usize, flags: Flags) {
+    // Two's complement of the value truncated to `len` bits.
+    let value = value.mod_floor(&(BigInt::one() << len));
+    let value = value.to_biguint().unwrap_or_default();
+    bits.write(&value, len, flags.little);
+}
+
+/// Returns `None` if there are not enough bits, the size is not 16, 32 or 64, or the value is
+/// not finite.
+pub(super) fn get_float(bits: &Bitstring, start: usize, len: usize, flags: Flags) -> Option<f64> {
+    let value = bits.read(start, len, flags.little)?.to_u64()?;
+    let value = match len {
+        16 => f16_to_f64(value as u16),
+        32 => f64::from(f32::from_bits(value as u32)),
+        64 => f64::from_bits(value),
+        _ => return None,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Returns `None` if the size is not 16, 32 or 64, or the value is out of range.
+pub(super) fn put_float(bits: &mut Bitstring, value: f64, len: usize, flags: Flags) -> Option<()> {
+    let value = match len {
+        16 => u64::from(f64_to_f16(value)?),
+        32 => {
+            let x = value as f32;
+            if !x.is_finite() {
+                return None;
+            }
+            u64::from(x.to_bits())
+        }
+        64 => value.to_bits(),
+        _ => return None,
+    };
+    bits.write(&BigUint::from(value), len, flags.little);
+    Some(())
+}
+
+fn f16_to_f64(x: u16) -> f64 {
+    let sign = if x & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((x >> 10) & 0x1f);
+    let fraction = f64::from(x & 0x3ff);
+    sign * match exponent {
+        0 => fraction * 2f64.powi(-24),
+        0x1f if fraction == 0.0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+fn f64_to_f16(x: f64) -> Option<u16> {
+    let sign = if x.is_sign_negative() { 0x8000 } else { 0 };
+    let x = x.abs();
+    let bits = if x < 2f64.powi(-14) {
+        // A subnormal number (the rounding may carry into the exponent).
+        (x * 2f64.powi(24)).round() as u32
+    } else {
+        let exponent = ((x.to_bits() >> 52) & 0x7ff) as i32 - 1023;
+        let fraction = ((x / 2f64.powi(exponent) - 1.0) * 1024.0).round() as u32;
+        (((exponent + 15) as u32) << 10) + fraction
+    };
+    (bits < 0x7c00).then_some(sign | bits as u16)
+}
+
+/// Returns the code point and its length in bits.
+pub(super) fn get_utf8(bits: &Bitstring, start: usize) -> Option<(char, usize)> {
+    let byte = |i: usize| bits.read(start + 8 * i, 8, false)?.to_u32();
+    let first = byte(0)?;
+    let (len, min) = match first {
+        0x00..=0x7f => return Some((char::from(first as u8), 8)),
+        0xc0..=0xdf => (2, 0x80),
+        0xe0..=0xef => (3, 0x800),
+        0xf0..=0xf7 => (4, 0x10000),
+        _ => return None,
+    };
+    let mut code = first & (0x7f >> len);
+    for i in 1..len {
+        let byte = byte(i)?;
+        if byte & 0xc0 != 0x80 {
+            return None;
+        }
+        code = code << 6 | (byte & 0x3f);
+    }
+    if code < min {
+        return None;
+    }
+    Some((char::from_u32(code)?, 8 * len))
+}
+
+pub(super) fn put_utf8(bits: &mut Bitstring, c: char) {
+    for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+        bits.write(&BigUint::from(byte), 8, false);
+    }
+}
+
+/// Returns the code point and its length in bits.
+pub(super) fn get_utf16(bits: &Bitstring, start: usize, flags: Flags) -> Option<(char, usize)> {
+    let unit = |i: usize| bits.read(start + 16 * i, 16, flags.little)?.to_u32();
+    let first = unit(0)?;
+    match first {
+        0xd800..=0xdbff => {
+            let second = unit(1)?;
+            if !(0xdc00..=0xdfff).contains(&second) {
+                return None;
+            }
+            let code = 0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00);
+            Some((char::from_u32(code)?, 32))
+        }
+        _ => Some((char::from_u32(first)?, 16)),
+    }
+}
+
+pub(super) fn put_utf16(bits: &mut Bitstring, c: char, flags: Flags) {
+    for unit in c.encode_utf16(&mut [0; 2]) {
+        bits.write(&BigUint::from(*unit), 16, flags.little);
+    }
+}
+
+/// Returns the code point and its length in bits.
+pub(super) fn get_utf32(bits: &Bitstring, start: usize, flags: Flags) -> Option<(char, usize)> {
+    let code = bits.read(start, 32, flags.little)?.to_u32()?;
+    Some((char::from_u32(code)?, 32))
+}
+
+pub(super) fn put_utf32(bits: &mut Bitstring, c: char, flags: Flags) {
+    bits.write(&BigUint::from(u32::from(c)), 32, flags.little);
+}
@@ -0,0 +1,250 @@
+use super::{Process, Status, Value};
+use crate::instruction::Instruction;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// An instruction executed by [`Process::step_traced()`] and the registers after it.
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub position: usize,
+    pub instruction: Instruction,
+    pub x: Vec<Value>,
+    pub y: Vec<Value>,
+    pub fr: Vec<f64>,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.instruction)
+    }
+}
+
+/// A hook called for each instruction executed by [`Process::step_traced()`].
+pub trait Tracer {
+    fn trace(&mut self, entry: &TraceEntry);
+}
+
+/// Records all the entries.
+impl Tracer for Vec<TraceEntry> {
+    fn trace(&mut self, entry: &TraceEntry) {
+        self.push(entry.clone());
+    }
+}
+
+impl<F> Tracer for F
+where
+    F: FnMut(&TraceEntry),
+{
+    fn trace(&mut self, entry: &TraceEntry) {
+        self(entry)
+    }
+}
+
+/// An interactive step debugger.
+///
+/// The following commands are read line by line:
+///
+/// | command          | description                                              |
+/// |------------------|----------------------------------------------------------|
+/// | `break <label>`  | stops before executing the `label` instruction           |
+/// | `step [<n>]`     | executes an instruction (or `n` instructions)            |
+/// | `continue`       | executes instructions until a breakpoint or the end      |
+/// | `regs`           | shows the X, Y and floating point registers              |
+/// | `stack`          | shows the stacktrace                                     |
+/// | `help`           | shows the commands                                       |
+/// | `quit`           | quits the debugger                                       |
+#[derive(Debug)]
+pub struct Debugger<'a, 'b> {
+    process: &'b mut Process<'a>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl<'a, 'b> Debugger<'a, 'b> {
+    /// Makes a new [`Debugger`] instance for a process prepared by [`Process::start()`].
+    pub fn new(process: &'b mut Process<'a>) -> Self {
+        Self {
+            process,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Reads and executes commands until `quit` or the end of the input.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            if !self.execute(&line?, &mut output)? {
+                break;
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes a command and returns `false` if the debugger should quit.
+    pub fn execute(&mut self, command: &str, mut output: impl Write) -> io::Result<bool> {
+        let words = command.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            [] => {}
+            ["break" | "b", label] => match label.parse() {
+                Ok(label) if self.label_position(label).is_some() => {
+                    self.breakpoints.insert(label);
+                    writeln!(output, "breakpoint at label {label}")?;
+                }
+                _ => writeln!(output, "unknown label: {label}")?,
+            },
+            ["step" | "s"] => self.step(1, &mut output)?,
+            ["step" | "s", n] => match n.parse() {
+                Ok(n) => self.step(n, &mut output)?,
+                Err(_) => writeln!(output, "invalid count: {n}")?,
+            },
+            ["continue" | "c"] => self.continue_(&mut output)?,
+            ["regs" | "r"] => self.print_registers(&mut output)?,
+            ["stack" | "bt"] => {
+                for (i, entry) in self.process.stacktrace().iter().enumerate() {
+                    writeln!(output, "#{i} {entry}")?;
+                }
+            }
+            ["help" | "h"] => {
+                writeln!(
+                    output,
+                    "commands: break <label>, step [<n>], continue, regs, stack, help, quit"
+                )?;
+            }
+            ["quit" | "q"] => return Ok(false),
+            _ => writeln!(output, "unknown command: {command}")?,
+        }
+        Ok(true)
+    }
+
+    fn step(&mut self, n: usize, output: &mut impl Write) -> io::Result<()> {
+        for _ in 0..n {
+            if !self.step_once(output)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn continue_(&mut self, output: &mut impl Write) -> io::Result<()> {
+        loop {
+            if !self.step_once(output)? {
+                return Ok(());
+            }
+            let position = self.process.position();
+            let breakpoint = self
+                .breakpoints
+                .iter()
+                .find(|x| self.label_position(**x) == position);
+            if let Some(label) = breakpoint {
+                writeln!(output, "stopped at label {label}")?;
+                return Ok(());
+            }
+        }
+    }
+
+    // Executes an instruction and returns `false` if the process has stopped.
+    fn step_once(&mut self, output: &mut impl Write) -> io::Result<bool> {
+        let mut entry = None;
+        let result = self
+            .process
+            .step_traced(&mut |x: &TraceEntry| entry = Some(x.to_string()));
+        if let Some(entry) = entry {
+            writeln!(output, "{entry}")?;
+        }
+        match result {
+            Ok(Status::Running) => Ok(true),
+            Ok(Status::Returned(value)) => {
+                writeln!(output, "returned: {value}")?;
+                Ok(false)
+            }
+            Err(e) => {
+                writeln!(output, "error: {e}")?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn print_registers(&self, output: &mut impl Write) -> io::Result<()> {
+        for (i, x) in self.process.x_registers().iter().enumerate() {
+            writeln!(output, "x{i} = {x}")?;
+        }
+        for (i, y) in self.process.y_registers().iter().enumerate() {
+            writeln!(output, "y{i} = {y}")?;
+        }
+        for (i, fr) in self.process.float_registers().iter().enumerate() {
+            writeln!(output, "fr{i} = {fr:?}")?;
+        }
+        Ok(())
+    }
+
+    fn label_position(&self, label: usize) -> Option<usize> {
+        self.process.labels.get(&label).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+    use crate::interp::{Import, Module};
+
+    #[test]
+    fn debugger_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info m double 1;
+            label 2;
+            allocate 1 1;
+            move {x,0} {y,0};
+            gc_bif2 {f,0} 1 0 {x,0} {y,0} {x,0};
+            label 3;
+            deallocate 1;
+            return;
+        };
+        let module = Module {
+            code,
+            atoms: vec!["m".to_owned(), "double".to_owned()],
+            imports: vec![Import::new("erlang", "+", 2)],
+            ..Default::default()
+        };
+        let mut process = Process::new(&module);
+
+        process.start("double", vec![Value::from(21)]).unwrap();
+        let mut trace = Vec::new();
+        assert_eq!(process.run_traced(&mut trace).unwrap(), Value::from(42));
+        assert_eq!(trace.len(), 7);
+        assert_eq!(trace[2].to_string(), "4: move x(0), y(0)");
+        assert_eq!(trace[2].y, [Value::from(21)]);
+
+        process.start("double", vec![Value::from(21)]).unwrap();
+        let input = "break 3\nbreak 9\nstep 3\nregs\ncontinue\nstack\nc\nquit\nstep\n";
+        let mut output = Vec::new();
+        Debugger::new(&mut process)
+            .run(input.as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            [
+                "> breakpoint at label 3",
+                "> unknown label: 9",
+                "> 2: label 2",
+                "3: allocate 1, 1",
+                "4: move x(0), y(0)",
+                "> x0 = 21",
+                "y0 = 21",
+                "> 5: gc_bif2 f(0), 1, 0, x(0), y(0), x(0)",
+                "stopped at label 3",
+                "> #0 {m,double,1,[]}",
+                "> 6: label 3",
+                "7: deallocate 1",
+                "8: return",
+                "returned: 42",
+                "> ",
+            ]
+            .join("\n")
+        );
+    }
+}
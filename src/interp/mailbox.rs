@@ -0,0 +1,224 @@
+use super::{Exception, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// The process identifier of the interpreted process.
+///
+/// Peers are numbered from 1 in the order they are added.
+pub(super) const SELF: u64 = 0;
+
+/// A scripted process that the interpreted process can send messages to.
+pub trait Peer {
+    /// Handles a message and returns the replies to the interpreted process, each with the
+    /// delay in milliseconds of virtual time before it is delivered.
+    fn receive(&mut self, message: Value) -> Vec<(u64, Value)>;
+}
+
+impl<F> Peer for F
+where
+    F: FnMut(Value) -> Vec<(u64, Value)>,
+{
+    fn receive(&mut self, message: Value) -> Vec<(u64, Value)> {
+        self(message)
+    }
+}
+
+/// The result of [`Mailbox::wait()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Wakeup {
+    Message,
+    Timeout,
+
+    /// No message will ever arrive and there is no timer.
+    Never,
+}
+
+/// A deterministic mailbox with a virtual clock.
+///
+/// Messages are delivered in the order of their delivery time and, for the same time, in the
+/// order they were sent.
+pub(super) struct Mailbox<'a> {
+    now: u64,
+
+    // Delivered messages with their sequence numbers.
+    messages: Vec<(u64, Value)>,
+    next_sequence: u64,
+
+    // Messages to be delivered, keyed by the delivery time and the order of sending.
+    scheduled: BTreeMap<(u64, u64), Value>,
+    next_order: u64,
+
+    // The index of the next message to be matched by `loop_rec`.
+    save: usize,
+    timer: Option<u64>,
+
+    // Receive markers (by reference) and the sequence numbers of the first messages after them.
+    markers: HashMap<u64, u64>,
+    next_reference: u64,
+
+    peers: Vec<Box<dyn Peer + 'a>>,
+}
+
+impl<'a> Mailbox<'a> {
+    pub fn new() -> Self {
+        Self {
+            now: 0,
+            messages: Vec::new(),
+            next_sequence: 0,
+            scheduled: BTreeMap::new(),
+            next_order: 0,
+            save: 0,
+            timer: None,
+            markers: HashMap::new(),
+            next_reference: 0,
+            peers: Vec::new(),
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn messages(&self) -> impl '_ + Iterator<Item = &Value> {
+        self.messages.iter().map(|(_, x)| x)
+    }
+
+    pub fn add_peer(&mut self, peer: impl Peer + 'a) -> Value {
+        self.peers.push(Box::new(peer));
+        Value::Pid(self.peers.len() as u64)
+    }
+
+    pub fn send_after(&mut self, delay: u64, message: Value) {
+        self.scheduled
+            .insert((self.now + delay, self.next_order), message);
+        self.next_order += 1;
+    }
+
+    /// Sends a message from the interpreted process.
+    ///
+    /// Messages to unknown processes are dropped.
+    pub fn send(&mut self, to: &Value, message: Value) -> Result<Value, Exception> {
+        let Value::Pid(pid) = to else {
+            return Err(Exception::error(Value::atom("badarg")));
+        };
+        if *pid == SELF {
+            self.send_after(0, message.clone());
+        } else if let Some(peer) = self.peers.get_mut(*pid as usize - 1) {
+            for (delay, reply) in peer.receive(message.clone()) {
+                self.send_after(delay, reply);
+            }
+        }
+        Ok(message)
+    }
+
+    /// Returns the message at the save pointer.
+    pub fn current(&mut self) -> Option<Value> {
+        self.deliver();
+        self.messages.get(self.save).map(|(_, x)| x.clone())
+    }
+
+    pub fn advance(&mut self) {
+        self.save += 1;
+    }
+
+    /// Removes the message at the save pointer and ends the receive.
+    pub fn remove(&mut self) {
+        if self.save < self.messages.len() {
+            self.messages.remove(self.save);
+        }
+        self.timeout();
+    }
+
+    /// Ends the receive without removing a message.
+    pub fn timeout(&mut self) {
+        self.save = 0;
+        self.timer = None;
+    }
+
+    /// Advances the clock until a message arrives or the timer (started if not running yet)
+    /// expires.
+    ///
+    /// If both happen at the same time, the message wins.
+    pub fn wait(&mut self, timeout: Option<u64>) -> Wakeup {
+        if self.timer.is_none() {
+            self.timer = timeout.map(|x| self.now + x);
+        }
+        let next = self.scheduled.keys().next().map(|(time, _)| *time);
+        match (next, self.timer) {
+            (Some(time), timer) if timer.is_none_or(|timer| time <= timer) => {
+                self.now = self.now.max(time);
+                self.deliver();
+                Wakeup::Message
+            }
+            (_, Some(timer)) => {
+                self.now = self.now.max(timer);
+                Wakeup::Timeout
+            }
+            _ => Wakeup::Never,
+        }
+    }
+
+    pub fn make_ref(&mut self) -> Value {
+        self.next_reference += 1;
+        Value::Reference(self.next_reference)
+    }
+
+    /// Makes a marker of the messages to be delivered from now on.
+    pub fn reserve_marker(&mut self) -> Value {
+        let marker = self.make_ref();
+        if let Value::Reference(id) = marker {
+            self.markers.insert(id, self.next_sequence);
+        }
+        marker
+    }
+
+    pub fn bind_marker(&mut self, marker: &Value, reference: &Value) {
+        if let (Value::Reference(marker), Value::Reference(reference)) = (marker, reference) {
+            if let Some(sequence) = self.markers.remove(marker) {
+                self.markers.insert(*reference, sequence);
+            }
+        }
+    }
+
+    pub fn clear_marker(&mut self, reference: &Value) {
+        if let Value::Reference(reference) = reference {
+            self.markers.remove(reference);
+        }
+    }
+
+    /// Skips the messages delivered before the marker bound to the reference.
+    pub fn use_marker(&mut self, reference: &Value) {
+        let Value::Reference(reference) = reference else {
+            return;
+        };
+        if let Some(sequence) = self.markers.get(reference) {
+            let position = self.messages.iter().position(|(x, _)| x >= sequence);
+            self.save = self.save.max(position.unwrap_or(self.messages.len()));
+        }
+    }
+
+    fn deliver(&mut self) {
+        while let Some(entry) = self.scheduled.first_entry() {
+            if entry.key().0 > self.now {
+                break;
+            }
+            let message = entry.remove();
+            self.messages.push((self.next_sequence, message));
+            self.next_sequence += 1;
+        }
+    }
+}
+
+impl fmt::Debug for Mailbox<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mailbox")
+            .field("now", &self.now)
+            .field("messages", &self.messages)
+            .field("scheduled", &self.scheduled)
+            .field("save", &self.save)
+            .field("timer", &self.timer)
+            .field("markers", &self.markers)
+            .field("peers", &self.peers.len())
+            .finish()
+    }
+}
@@ -0,0 +1,247 @@
+use super::bits::{Bitstring, MatchContext};
+use super::Exception;
+use crate::term::Label;
+use num::{BigInt, ToPrimitive};
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+/// An Erlang term manipulated by the interpreter.
+///
+/// The derived [`PartialEq`] is exact equality (`=:=`), e.g., `1` and `1.0` are different.
+/// Use [`Value::compare()`] for the Erlang term order (and `==`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(BigInt),
+    Float(f64),
+    Atom(Rc<str>),
+    Nil,
+    Cons(Rc<(Value, Value)>),
+    Tuple(Rc<[Value]>),
+    Bitstring(Rc<Bitstring>),
+    Pid(u64),
+    Reference(u64),
+
+    /// A match context created by `bs_start_match3` or `bs_start_match4`.
+    ///
+    /// Copies of a match context share the position.
+    MatchContext(Rc<MatchContext>),
+
+    /// A catch tag stored in a Y register by `try` or `catch`.
+    CatchTag(Label),
+
+    /// The raw stacktrace passed to a `try` handler, which `build_stacktrace` converts to a list.
+    RawStacktrace(Rc<Exception>),
+}
+
+impl Value {
+    pub fn atom(name: &str) -> Self {
+        Self::Atom(name.into())
+    }
+
+    pub fn boolean(b: bool) -> Self {
+        Self::atom(if b { "true" } else { "false" })
+    }
+
+    pub fn cons(head: Self, tail: Self) -> Self {
+        Self::Cons(Rc::new((head, tail)))
+    }
+
+    pub fn tuple(elements: Vec<Self>) -> Self {
+        Self::Tuple(elements.into())
+    }
+
+    pub fn binary(bytes: &[u8]) -> Self {
+        Self::from(Bitstring::from(bytes))
+    }
+
+    /// Makes a proper list.
+    pub fn list(elements: Vec<Self>) -> Self {
+        elements
+            .into_iter()
+            .rev()
+            .fold(Self::Nil, |tail, head| Self::cons(head, tail))
+    }
+
+    /// Returns the elements of a proper list.
+    pub fn to_vec(&self) -> Option<Vec<Self>> {
+        let mut elements = Vec::new();
+        let mut current = self;
+        loop {
+            match current {
+                Self::Nil => return Some(elements),
+                Self::Cons(cell) => {
+                    elements.push(cell.0.clone());
+                    current = &cell.1;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    pub fn is_atom(&self, name: &str) -> bool {
+        matches!(self, Self::Atom(x) if &**x == name)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Atom(x) if &**x == "true" => Some(true),
+            Self::Atom(x) if &**x == "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<&BigInt> {
+        match self {
+            Self::Integer(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_integer().and_then(|x| x.to_usize())
+    }
+
+    /// Returns the value as a float if it is a number.
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(x) => x.to_f64(),
+            Self::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Compares values in the Erlang term order.
+    ///
+    /// Numbers are compared by value, so `1` and `1.0` are equal (i.e., `==`).
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a.cmp(b),
+            (Self::Integer(_) | Self::Float(_), Self::Integer(_) | Self::Float(_)) => {
+                let a = self.to_f64().unwrap_or(f64::NAN);
+                let b = other.to_f64().unwrap_or(f64::NAN);
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            }
+            (Self::Atom(a), Self::Atom(b)) => a.cmp(b),
+            (Self::Reference(a), Self::Reference(b)) | (Self::Pid(a), Self::Pid(b)) => a.cmp(b),
+            (Self::Tuple(a), Self::Tuple(b)) => a.len().cmp(&b.len()).then_with(|| {
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| a.compare(b))
+                    .find(|x| x.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            }),
+            (Self::Cons(a), Self::Cons(b)) => a.0.compare(&b.0).then_with(|| a.1.compare(&b.1)),
+            (Self::Bitstring(a), Self::Bitstring(b)) => a
+                .as_bytes()
+                .cmp(b.as_bytes())
+                .then_with(|| a.len().cmp(&b.len())),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+
+    // number < atom < reference < fun < port < pid < tuple < map < nil < list < bitstring
+    fn type_rank(&self) -> u8 {
+        match self {
+            Self::Integer(_) | Self::Float(_) => 0,
+            Self::Atom(_) => 1,
+            Self::Reference(_) | Self::CatchTag(_) | Self::RawStacktrace(_) => 2,
+            Self::Pid(_) => 5,
+            Self::Tuple(_) => 6,
+            Self::Nil => 8,
+            Self::Cons(_) => 9,
+            Self::Bitstring(_) | Self::MatchContext(_) => 10,
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Self::Integer(n.into())
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(n: BigInt) -> Self {
+        Self::Integer(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Self::Float(n)
+    }
+}
+
+impl From<Bitstring> for Value {
+    fn from(bits: Bitstring) -> Self {
+        Self::Bitstring(Rc::new(bits))
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(x) => write!(f, "{x}"),
+            Self::Float(x) => write!(f, "{x:?}"),
+            Self::Atom(x) => {
+                let mut chars = x.chars();
+                let bare = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@');
+                if bare {
+                    write!(f, "{x}")
+                } else {
+                    write!(f, "'{}'", x.replace('\\', "\\\\").replace('\'', "\\'"))
+                }
+            }
+            Self::Nil => write!(f, "[]"),
+            Self::Cons(cell) => {
+                write!(f, "[{}", cell.0)?;
+                let mut tail = &cell.1;
+                loop {
+                    match tail {
+                        Self::Nil => break,
+                        Self::Cons(cell) => {
+                            write!(f, ",{}", cell.0)?;
+                            tail = &cell.1;
+                        }
+                        _ => {
+                            write!(f, "|{tail}")?;
+                            break;
+                        }
+                    }
+                }
+                write!(f, "]")
+            }
+            Self::Tuple(elements) => {
+                write!(f, "{{")?;
+                for (i, x) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                write!(f, "}}")
+            }
+            Self::Bitstring(bits) => {
+                write!(f, "<<")?;
+                let bytes = bits.as_bytes();
+                for (i, byte) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    match bits.len() - i * 8 {
+                        n @ 1..=7 => write!(f, "{}:{n}", byte >> (8 - n))?,
+                        _ => write!(f, "{byte}")?,
+                    }
+                }
+                write!(f, ">>")
+            }
+            Self::MatchContext(context) => write!(f, "#MatchContext<{}>", context.position()),
+            Self::Pid(x) => write!(f, "<0.{x}.0>"),
+            Self::Reference(x) => write!(f, "#Ref<0.0.0.{x}>"),
+            Self::CatchTag(label) => write!(f, "#CatchTag<{}>", label.value),
+            Self::RawStacktrace(_) => write!(f, "#RawStacktrace<>"),
+        }
+    }
+}
@@ -10,8 +10,76 @@ use byteorder::ReadBytesExt as _;
 use num::BigInt;
 use std::io::{Read, Write};
 
+// Allows `beam_asm!`, which refers to `::beamcode`, to be used inside this crate.
+extern crate self as beamcode;
+
+#[cfg(feature = "beam_file")]
+pub mod beam;
+pub mod builder;
+pub mod call_graph;
+pub mod cfg;
+pub mod defuse;
+pub mod diff;
+pub mod disasm;
+pub mod dot;
+pub mod etf;
+pub mod flow;
+pub mod frame;
 pub mod instruction;
+pub mod interp;
+pub mod liveness;
+pub mod module;
+pub mod operand;
+pub mod peephole;
+pub mod repro;
+pub mod stats;
 pub mod term;
+pub mod transform;
+pub mod types;
+pub mod verify;
+pub mod visit;
+
+/// Assembles instructions written in a syntax similar to Erlang's `.S` files.
+///
+/// Each statement is an opcode name followed by its operands and terminated by `;`.
+/// The macro evaluates to a `Vec<Instruction>` (no `int_code_end` is appended).
+/// Unknown opcode names are rejected at compile time.
+///
+/// Operands are written as follows:
+///
+/// | Syntax                         | Operand                                          |
+/// |--------------------------------|--------------------------------------------------|
+/// | `3`                            | an unsigned number (e.g., arity or live)         |
+/// | `{x,0}`, `{y,0}`, `{fr,0}`     | a register                                       |
+/// | `{f,3}`                        | a label                                          |
+/// | `{atom,2}`, `{literal,0}`      | an atom or a literal by index                    |
+/// | `{integer,-1}`                 | an integer                                       |
+/// | `nil`, `[]`                    | `[]` (i.e., `{atom,0}`)                          |
+/// | `[{atom,1}, {f,3}]`            | a list (`[]` is an empty one for list operands)  |
+/// | `{list,[2, {f,3}]}`            | a list term (e.g., for `select_tuple_arity`)     |
+/// | `{alloc,[{words,1},{floats,0}]}` | an allocation list                             |
+/// | `foo`                          | another atom, numbered from 1 by appearance      |
+///
+/// # Examples
+///
+/// ```
+/// use beamcode::beam_asm;
+///
+/// let code = beam_asm! {
+///     label 1;
+///     func_info foo bar 0;
+///     label 2;
+///     move {x,0} {y,0};
+///     return;
+/// };
+/// assert_eq!(code[1].to_string(), "func_info atom(1), atom(2), 0");
+/// assert_eq!(code[3].to_string(), "move x(0), y(0)");
+/// ```
+///
+/// ```compile_fail
+/// let code = beamcode::beam_asm! { no_such_opcode {x,0}; };
+/// ```
+pub use beamcode_derive::beam_asm;
 
 /// This trait allows decoding an object from a byte sequence.
 pub trait Decode: Sized {
@@ -49,6 +117,15 @@ pub enum DecodeError {
     #[error("invalid Unicode codepoint: {value}")]
     InvalidUnicodeCodepoint { value: u32 },
 
+    #[error("unknown Type chunk version: {version}")]
+    UnknownTypeChunkVersion { version: u32 },
+
+    #[error("unknown external term format version: {version}")]
+    UnknownExternalTermFormatVersion { version: u8 },
+
+    #[error("unsupported external term format tag: {tag}")]
+    UnsupportedExternalTermTag { tag: u8 },
+
     #[error(transparent)]
     NumError(#[from] num::bigint::TryFromBigIntError<BigInt>),
 
@@ -0,0 +1,388 @@
+//! Liveness analysis of registers.
+//!
+//! A register is live at a point if its value may be read later without being written first.
+//! The analysis is a backward dataflow analysis over the [`Cfg`] of a function and takes into
+//! account that calls and instructions that may garbage collect clobber X registers
+//! (see [`Instruction::preserved_x_registers()`]), that `trim` renumbers Y registers, and that
+//! Y registers do not survive the deallocation of the stack frame.
+//!
+//! Y registers read by an exception handler are considered live in every block reachable from the
+//! `try` or `catch` instruction that installs it.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::defuse::RegisterId;
+//! use beamcode::liveness::Liveness;
+//!
+//! let code = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 2;
+//!     label 2;
+//!     move {x,1} {x,2};
+//!     test_heap 2 2;
+//!     put_list {x,0} nil {x,0};
+//!     return;
+//! };
+//! let liveness = Liveness::new(&code);
+//!
+//! // `x1` is dead after the `move`, so the `live` operand of `test_heap` could be 1.
+//! let live = liveness.live_out(4);
+//! assert_eq!(live.iter().collect::<Vec<_>>(), [RegisterId::X(0)]);
+//! assert_eq!(live.x_len(), 1);
+//! ```
+use crate::cfg::{BlockId, Cfg, EdgeKind};
+use crate::defuse::RegisterId;
+use crate::instruction::Instruction;
+use crate::term::Term;
+
+/// A set of registers represented as bitsets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterSet {
+    x: BitSet,
+    y: BitSet,
+    fr: BitSet,
+}
+
+impl RegisterSet {
+    /// Makes an empty [`RegisterSet`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, register: RegisterId) -> bool {
+        let (bits, i) = self.bits(register);
+        bits.contains(i)
+    }
+
+    /// Adds a register and returns `true` if it was not in the set.
+    pub fn insert(&mut self, register: RegisterId) -> bool {
+        let (bits, i) = self.bits_mut(register);
+        bits.insert(i)
+    }
+
+    /// Removes a register and returns `true` if it was in the set.
+    pub fn remove(&mut self, register: RegisterId) -> bool {
+        let (bits, i) = self.bits_mut(register);
+        bits.remove(i)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty() && self.y.is_empty() && self.fr.is_empty()
+    }
+
+    /// Adds the registers in `other` and returns `true` if the set has changed.
+    pub fn union_with(&mut self, other: &Self) -> bool {
+        let x = self.x.union_with(&other.x);
+        let y = self.y.union_with(&other.y);
+        let fr = self.fr.union_with(&other.fr);
+        x || y || fr
+    }
+
+    /// Returns the registers in the order of X, Y and floating point registers, each ascending.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = RegisterId> {
+        self.x
+            .iter()
+            .map(RegisterId::X)
+            .chain(self.y.iter().map(RegisterId::Y))
+            .chain(self.fr.iter().map(RegisterId::Fr))
+    }
+
+    /// Returns one more than the highest X register in the set, or 0 if there is none.
+    ///
+    /// This is the smallest value that can be given as the `live` operand at this point.
+    pub fn x_len(&self) -> usize {
+        self.x.len()
+    }
+
+    fn bits(&self, register: RegisterId) -> (&BitSet, usize) {
+        match register {
+            RegisterId::X(i) => (&self.x, i),
+            RegisterId::Y(i) => (&self.y, i),
+            RegisterId::Fr(i) => (&self.fr, i),
+        }
+    }
+
+    fn bits_mut(&mut self, register: RegisterId) -> (&mut BitSet, usize) {
+        match register {
+            RegisterId::X(i) => (&mut self.x, i),
+            RegisterId::Y(i) => (&mut self.y, i),
+            RegisterId::Fr(i) => (&mut self.fr, i),
+        }
+    }
+}
+
+// The last word is non-zero so that equal sets have equal representations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn contains(&self, i: usize) -> bool {
+        self.0.get(i / 64).is_some_and(|w| w & (1 << (i % 64)) != 0)
+    }
+
+    fn insert(&mut self, i: usize) -> bool {
+        if self.0.len() <= i / 64 {
+            self.0.resize(i / 64 + 1, 0);
+        }
+        let old = self.0[i / 64];
+        self.0[i / 64] |= 1 << (i % 64);
+        old != self.0[i / 64]
+    }
+
+    fn remove(&mut self, i: usize) -> bool {
+        let removed = self.contains(i);
+        if removed {
+            self.0[i / 64] &= !(1 << (i % 64));
+            self.normalize();
+        }
+        removed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn union_with(&mut self, other: &Self) -> bool {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        let mut changed = false;
+        for (w, o) in self.0.iter_mut().zip(&other.0) {
+            changed |= *o & !*w != 0;
+            *w |= o;
+        }
+        changed
+    }
+
+    // Removes the elements not less than `n`.
+    fn truncate(&mut self, n: usize) {
+        self.0.truncate(n.div_ceil(64));
+        if !n.is_multiple_of(64) {
+            if let Some(w) = self.0.get_mut(n / 64) {
+                *w &= (1 << (n % 64)) - 1;
+            }
+        }
+        self.normalize();
+    }
+
+    fn len(&self) -> usize {
+        self.0.last().map_or(0, |w| {
+            (self.0.len() - 1) * 64 + 64 - w.leading_zeros() as usize
+        })
+    }
+
+    fn iter(&self) -> impl '_ + Iterator<Item = usize> {
+        (0..self.len()).filter(|&i| self.contains(i))
+    }
+
+    fn normalize(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+}
+
+/// The registers live before and after each instruction of a function.
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    live_in: Vec<RegisterSet>,
+    live_out: Vec<RegisterSet>,
+}
+
+impl Liveness {
+    /// Analyzes a function (see [`Cfg::new()`]).
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let cfg = Cfg::new(instructions);
+        let protected = protected_blocks(&cfg);
+        let mut live_in = vec![RegisterSet::new(); instructions.len()];
+        let mut live_out = vec![RegisterSet::new(); instructions.len()];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, block) in cfg.blocks().iter().enumerate().rev() {
+                if block.range.is_empty() {
+                    continue;
+                }
+                let mut exit = RegisterSet::new();
+                let mut branch = RegisterSet::new();
+                for edge in &block.successors {
+                    let start = cfg.block(edge.target).range.start;
+                    let Some(target) = live_in.get(start) else {
+                        continue;
+                    };
+                    match edge.kind {
+                        EdgeKind::Exception => {}
+                        EdgeKind::Branch => {
+                            branch.union_with(target);
+                        }
+                        _ => {
+                            exit.union_with(target);
+                        }
+                    }
+                }
+                let mut handler_y = RegisterSet::new();
+                for handler in &protected[i] {
+                    let start = cfg.block(*handler).range.start;
+                    handler_y.y.union_with(&live_in[start].y);
+                }
+
+                let last = block.range.end - 1;
+                for position in block.range.clone().rev() {
+                    let mut out = if position == last {
+                        exit.clone()
+                    } else {
+                        live_in[position + 1].clone()
+                    };
+                    out.union_with(&handler_y);
+                    let mut input = transfer(&instructions[position], &out);
+                    if position == last {
+                        out.union_with(&branch);
+                        input.union_with(&branch);
+                    }
+                    changed |= live_out[position] != out || live_in[position] != input;
+                    live_out[position] = out;
+                    live_in[position] = input;
+                }
+            }
+        }
+        Self { live_in, live_out }
+    }
+
+    /// Returns the registers live before the instruction at `position`.
+    pub fn live_in(&self, position: usize) -> &RegisterSet {
+        &self.live_in[position]
+    }
+
+    /// Returns the registers live after the instruction at `position`.
+    ///
+    /// For an instruction with a fail label, this includes the registers live at the label.
+    pub fn live_out(&self, position: usize) -> &RegisterSet {
+        &self.live_out[position]
+    }
+}
+
+// Returns the handlers whose Y registers are live in each block.
+fn protected_blocks(cfg: &Cfg) -> Vec<Vec<BlockId>> {
+    let mut protected = vec![Vec::new(); cfg.blocks().len()];
+    for (i, block) in cfg.blocks().iter().enumerate() {
+        for edge in &block.successors {
+            if edge.kind != EdgeKind::Exception {
+                continue;
+            }
+            let mut stack = vec![BlockId(i)];
+            while let Some(id) = stack.pop() {
+                if protected[id.0].contains(&edge.target) {
+                    continue;
+                }
+                protected[id.0].push(edge.target);
+                stack.extend(
+                    cfg.block(id)
+                        .successors
+                        .iter()
+                        .filter(|x| x.kind != EdgeKind::Exception)
+                        .map(|x| x.target),
+                );
+            }
+        }
+    }
+    protected
+}
+
+// Computes the registers live before an instruction from those live after it.
+fn transfer(instruction: &Instruction, out: &RegisterSet) -> RegisterSet {
+    let mut live = out.clone();
+    for register in instruction.defs() {
+        live.remove(register);
+    }
+    if let Some(n) = instruction.preserved_x_registers() {
+        live.x.truncate(n);
+    }
+    match instruction {
+        Instruction::Allocate(_)
+        | Instruction::AllocateHeap(_)
+        | Instruction::AllocateZero(_)
+        | Instruction::AllocateHeapZero(_)
+        | Instruction::Deallocate(_)
+        | Instruction::CallLast(_)
+        | Instruction::CallExtLast(_)
+        | Instruction::ApplyLast(_) => live.y = BitSet::default(),
+        Instruction::Trim(x) => {
+            let n = match x.arg1 {
+                Term::Usize(n) => n,
+                _ => 0,
+            };
+            let mut y = BitSet::default();
+            for i in live.y.iter() {
+                y.insert(i + n);
+            }
+            live.y = y;
+        }
+        _ => {}
+    }
+    for register in instruction.uses() {
+        live.insert(register);
+    }
+    live
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    fn registers(set: &RegisterSet) -> Vec<RegisterId> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn liveness_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 2;
+            label 2;
+            allocate 2 2;
+            move {x,1} {y,1};
+            is_nil {f,3} {x,0};
+            trim 1 1;
+            move {y,0} {x,0};
+            deallocate 1;
+            return;
+            label 3;
+            call 1 {f,2};
+            move {x,0} {x,1};
+            move {y,1} {x,0};
+            deallocate 2;
+            return;
+        };
+        let liveness = Liveness::new(&code);
+        assert_eq!(
+            registers(liveness.live_in(3)),
+            [RegisterId::X(0), RegisterId::X(1)]
+        );
+        assert_eq!(
+            registers(liveness.live_out(5)),
+            [RegisterId::X(0), RegisterId::Y(1)]
+        );
+        assert_eq!(registers(liveness.live_in(6)), [RegisterId::Y(1)]);
+        assert_eq!(registers(liveness.live_in(7)), [RegisterId::Y(0)]);
+        assert_eq!(
+            registers(liveness.live_in(11)),
+            [RegisterId::X(0), RegisterId::Y(1)]
+        );
+        assert_eq!(
+            registers(liveness.live_in(12)),
+            [RegisterId::X(0), RegisterId::Y(1)]
+        );
+        assert!(liveness.live_out(15).is_empty());
+
+        let mut set = RegisterSet::new();
+        assert!(set.insert(RegisterId::X(100)));
+        assert!(!set.insert(RegisterId::X(100)));
+        assert_eq!(set.x_len(), 101);
+        assert!(set.remove(RegisterId::X(100)));
+        assert_eq!(set, RegisterSet::new());
+    }
+}
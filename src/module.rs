@@ -0,0 +1,190 @@
+//! The `Code` chunk of a module and the tables that accompany it.
+use crate::instruction::Instruction;
+use crate::term::{Atom, Label};
+use crate::{DecodeError, EncodeError};
+use byteorder::{BigEndian, ReadBytesExt as _};
+
+/// The header of the `Code` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeHeader {
+    /// The instruction set version (always 0 for current compilers).
+    pub version: u32,
+
+    pub opcode_max: u32,
+
+    /// The highest label plus one.
+    pub label_count: u32,
+
+    /// The number of `func_info` instructions.
+    pub function_count: u32,
+}
+
+impl CodeHeader {
+    /// Makes the header for code.
+    ///
+    /// `opcode_max` is the highest opcode used in the code.
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let mut header = Self {
+            version: 0,
+            opcode_max: 0,
+            label_count: 1,
+            function_count: 0,
+        };
+        for instruction in instructions {
+            header.opcode_max = header.opcode_max.max(u32::from(instruction.opcode()));
+            match instruction {
+                Instruction::Label(x) => {
+                    header.label_count = header.label_count.max(x.literal as u32 + 1);
+                }
+                Instruction::FuncInfo(_) => header.function_count += 1,
+                _ => {}
+            }
+        }
+        header
+    }
+
+    /// Decodes the data of a `Code` chunk into the header and the bytecode.
+    ///
+    /// Header fields beyond `function_count` (if any) are skipped.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        let mut reader = bytes;
+        let info_size = reader.read_u32::<BigEndian>()? as usize;
+        let header = Self {
+            version: reader.read_u32::<BigEndian>()?,
+            opcode_max: reader.read_u32::<BigEndian>()?,
+            label_count: reader.read_u32::<BigEndian>()?,
+            function_count: reader.read_u32::<BigEndian>()?,
+        };
+        let bytecode = bytes
+            .get(4 + info_size..)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        Ok((header, bytecode))
+    }
+
+    /// Encodes the header followed by the bytecode as the data of a `Code` chunk.
+    pub fn encode(&self, bytecode: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for word in [
+            16,
+            self.version,
+            self.opcode_max,
+            self.label_count,
+            self.function_count,
+        ] {
+            data.extend_from_slice(&word.to_be_bytes());
+        }
+        data.extend_from_slice(bytecode);
+        data
+    }
+}
+
+/// The contents of the `Code` chunk.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeChunk {
+    pub header: CodeHeader,
+    pub instructions: Vec<Instruction>,
+}
+
+impl CodeChunk {
+    /// Makes a [`CodeChunk`] instance with the header computed by [`CodeHeader::new()`].
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        Self {
+            header: CodeHeader::new(&instructions),
+            instructions,
+        }
+    }
+
+    /// Decodes the data of a `Code` chunk.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (header, bytecode) = CodeHeader::decode(bytes)?;
+        Ok(Self {
+            header,
+            instructions: crate::decode_instructions(bytecode)?,
+        })
+    }
+
+    /// Encodes this into the data of a `Code` chunk.
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let bytecode = crate::encode_instructions(&self.instructions)?;
+        Ok(self.header.encode(&bytecode))
+    }
+}
+
+/// An entry of the import table (`ImpT` chunk).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    pub module: String,
+    pub function: String,
+    pub arity: usize,
+}
+
+impl Import {
+    pub fn new(module: &str, function: &str, arity: usize) -> Self {
+        Self {
+            module: module.to_owned(),
+            function: function.to_owned(),
+            arity,
+        }
+    }
+}
+
+/// An entry of the export (`ExpT`) or local function (`LocT`) table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionEntry {
+    pub function: Atom,
+    pub arity: usize,
+    pub label: Label,
+}
+
+/// An entry of the lambda table (`FunT`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LambdaEntry {
+    pub function: Atom,
+    pub arity: usize,
+    pub label: Label,
+
+    /// The index referred to by `make_fun2` and `make_fun3`.
+    pub index: usize,
+
+    pub num_free: usize,
+    pub old_uniq: u32,
+}
+
+/// The tables of a module that refer to labels in its code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionTables {
+    /// The `ExpT` chunk.
+    pub exports: Vec<FunctionEntry>,
+
+    /// The `LocT` chunk.
+    pub locals: Vec<FunctionEntry>,
+
+    /// The `FunT` chunk.
+    pub lambdas: Vec<LambdaEntry>,
+}
+
+impl FunctionTables {
+    /// Returns the labels of all entries.
+    pub fn labels(&self) -> impl Iterator<Item = Label> + '_ {
+        self.exports
+            .iter()
+            .chain(&self.locals)
+            .map(|x| x.label)
+            .chain(self.lambdas.iter().map(|x| x.label))
+    }
+
+    /// Returns mutable references to the labels of all entries.
+    pub fn labels_mut(&mut self) -> impl Iterator<Item = &mut Label> + '_ {
+        self.exports
+            .iter_mut()
+            .chain(&mut self.locals)
+            .map(|x| &mut x.label)
+            .chain(self.lambdas.iter_mut().map(|x| &mut x.label))
+    }
+}
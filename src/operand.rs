@@ -0,0 +1,244 @@
+//! Uniform views of instruction operands.
+//!
+//! [`Instruction::operands()`](crate::instruction::Instruction::operands) and
+//! [`Instruction::operands_mut()`](crate::instruction::Instruction::operands_mut) yield the
+//! fields of an instruction in declaration order, which allows generic tools to handle every
+//! opcode without per-variant code.
+use crate::term::{Allocation, Atom, Label, List, Register, Term, YRegister};
+use crate::visit::{Visit, VisitMut, Walk, WalkMut};
+use std::iter::Flatten;
+
+/// The maximum number of operands that an instruction has.
+pub const MAX_OPERANDS: usize = 8;
+
+/// A borrowed operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand<'a> {
+    Usize(&'a usize),
+    Atom(&'a Atom),
+    Label(&'a Label),
+    Term(&'a Term),
+    Register(&'a Register),
+    Allocation(&'a Allocation),
+    List(&'a List),
+    YRegisters(&'a [YRegister]),
+}
+
+impl<'a> From<&'a usize> for Operand<'a> {
+    fn from(x: &'a usize) -> Self {
+        Self::Usize(x)
+    }
+}
+
+impl<'a> From<&'a Atom> for Operand<'a> {
+    fn from(x: &'a Atom) -> Self {
+        Self::Atom(x)
+    }
+}
+
+impl<'a> From<&'a Label> for Operand<'a> {
+    fn from(x: &'a Label) -> Self {
+        Self::Label(x)
+    }
+}
+
+impl<'a> From<&'a Term> for Operand<'a> {
+    fn from(x: &'a Term) -> Self {
+        Self::Term(x)
+    }
+}
+
+impl<'a> From<&'a Register> for Operand<'a> {
+    fn from(x: &'a Register) -> Self {
+        Self::Register(x)
+    }
+}
+
+impl<'a> From<&'a Allocation> for Operand<'a> {
+    fn from(x: &'a Allocation) -> Self {
+        Self::Allocation(x)
+    }
+}
+
+impl<'a> From<&'a List> for Operand<'a> {
+    fn from(x: &'a List) -> Self {
+        Self::List(x)
+    }
+}
+
+impl<'a> From<&'a Vec<YRegister>> for Operand<'a> {
+    fn from(x: &'a Vec<YRegister>) -> Self {
+        Self::YRegisters(x)
+    }
+}
+
+impl Walk for Operand<'_> {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Self::Usize(x) => x.accept(visitor),
+            Self::Atom(x) => x.accept(visitor),
+            Self::Label(x) => x.accept(visitor),
+            Self::Term(x) => x.accept(visitor),
+            Self::Register(x) => x.accept(visitor),
+            Self::Allocation(x) => x.accept(visitor),
+            Self::List(x) => x.accept(visitor),
+            Self::YRegisters(x) => {
+                for register in x.iter() {
+                    register.accept(visitor);
+                }
+            }
+        }
+    }
+}
+
+/// A mutably borrowed operand.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperandMut<'a> {
+    Usize(&'a mut usize),
+    Atom(&'a mut Atom),
+    Label(&'a mut Label),
+    Term(&'a mut Term),
+    Register(&'a mut Register),
+    Allocation(&'a mut Allocation),
+    List(&'a mut List),
+    YRegisters(&'a mut Vec<YRegister>),
+}
+
+impl<'a> From<&'a mut usize> for OperandMut<'a> {
+    fn from(x: &'a mut usize) -> Self {
+        Self::Usize(x)
+    }
+}
+
+impl<'a> From<&'a mut Atom> for OperandMut<'a> {
+    fn from(x: &'a mut Atom) -> Self {
+        Self::Atom(x)
+    }
+}
+
+impl<'a> From<&'a mut Label> for OperandMut<'a> {
+    fn from(x: &'a mut Label) -> Self {
+        Self::Label(x)
+    }
+}
+
+impl<'a> From<&'a mut Term> for OperandMut<'a> {
+    fn from(x: &'a mut Term) -> Self {
+        Self::Term(x)
+    }
+}
+
+impl<'a> From<&'a mut Register> for OperandMut<'a> {
+    fn from(x: &'a mut Register) -> Self {
+        Self::Register(x)
+    }
+}
+
+impl<'a> From<&'a mut Allocation> for OperandMut<'a> {
+    fn from(x: &'a mut Allocation) -> Self {
+        Self::Allocation(x)
+    }
+}
+
+impl<'a> From<&'a mut List> for OperandMut<'a> {
+    fn from(x: &'a mut List) -> Self {
+        Self::List(x)
+    }
+}
+
+impl<'a> From<&'a mut Vec<YRegister>> for OperandMut<'a> {
+    fn from(x: &'a mut Vec<YRegister>) -> Self {
+        Self::YRegisters(x)
+    }
+}
+
+impl WalkMut for OperandMut<'_> {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        match self {
+            Self::Usize(x) => x.accept_mut(visitor),
+            Self::Atom(x) => x.accept_mut(visitor),
+            Self::Label(x) => x.accept_mut(visitor),
+            Self::Term(x) => x.accept_mut(visitor),
+            Self::Register(x) => x.accept_mut(visitor),
+            Self::Allocation(x) => x.accept_mut(visitor),
+            Self::List(x) => x.accept_mut(visitor),
+            Self::YRegisters(x) => x.walk_mut(visitor),
+        }
+    }
+}
+
+/// An iterator over the operands of an instruction.
+#[derive(Debug)]
+pub struct Operands<'a>(Flatten<std::array::IntoIter<Option<Operand<'a>>, MAX_OPERANDS>>);
+
+impl<'a> Operands<'a> {
+    #[doc(hidden)]
+    pub fn new(items: [Option<Operand<'a>>; MAX_OPERANDS]) -> Self {
+        Self(items.into_iter().flatten())
+    }
+}
+
+impl<'a> Iterator for Operands<'a> {
+    type Item = Operand<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// An iterator over the mutable operands of an instruction.
+#[derive(Debug)]
+pub struct OperandsMut<'a>(Flatten<std::array::IntoIter<Option<OperandMut<'a>>, MAX_OPERANDS>>);
+
+impl<'a> OperandsMut<'a> {
+    #[doc(hidden)]
+    pub fn new(items: [Option<OperandMut<'a>>; MAX_OPERANDS]) -> Self {
+        Self(items.into_iter().flatten())
+    }
+}
+
+impl<'a> Iterator for OperandsMut<'a> {
+    type Item = OperandMut<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, Instruction};
+    use crate::term::XRegister;
+
+    #[test]
+    fn operands_works() {
+        let mut instruction = Instruction::TestArity(instruction::TestArity {
+            label: Label { value: 3 },
+            arg1: Term::XRegister(XRegister { value: 0, ty: None }),
+            arity: 2,
+        });
+        let operands = instruction.operands().collect::<Vec<_>>();
+        assert_eq!(
+            operands,
+            [
+                Operand::Label(&Label { value: 3 }),
+                Operand::Term(&Term::XRegister(XRegister { value: 0, ty: None })),
+                Operand::Usize(&2)
+            ]
+        );
+
+        for operand in instruction.operands_mut() {
+            if let OperandMut::Usize(arity) = operand {
+                *arity += 1;
+            }
+        }
+        assert!(instruction.operands().any(|x| x == Operand::Usize(&3)));
+        assert_eq!(
+            Instruction::Return(instruction::Return {})
+                .operands()
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Pattern-based peephole optimization.
+//!
+//! A [`Rule`] looks at a [`Window`] of instructions and may replace a prefix of it.
+//! [`optimize()`] applies rules at every position until none of them matches anymore.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::peephole::{self, default_rules};
+//!
+//! let mut code = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 1;
+//!     label 2;
+//!     move {x,0} {x,0};
+//!     jump {f,3};
+//!     label 3;
+//!     return;
+//!     move {x,0} {x,1};
+//! };
+//! assert_eq!(peephole::optimize(&mut code, &default_rules()).unwrap(), 3);
+//! assert_eq!(code.len(), 5);
+//! ```
+use crate::defuse::RegisterId;
+use crate::instruction::{self, Instruction};
+use crate::term::{Label, Term};
+
+/// The instructions visible to a [`Rule`] at a position.
+#[derive(Debug, Clone, Copy)]
+pub struct Window<'a> {
+    /// The instructions from the position up to and including the first one whose
+    /// [`Instruction::successors()`] is not [`Successors::FallThrough`] (or up to the next
+    /// `label` or `int_code_end` instruction).
+    ///
+    /// Control reaches each of these instructions once the first one is executed.
+    ///
+    /// [`Successors::FallThrough`]: crate::flow::Successors::FallThrough
+    pub instructions: &'a [Instruction],
+
+    /// The instructions from the position up to (but not including) the next `label` or
+    /// `int_code_end` instruction, which starts with [`Window::instructions`].
+    ///
+    /// Control may leave at any of these instructions that is not
+    /// [`Successors::FallThrough`], and the instructions following a terminator are
+    /// unreachable. Rules looking beyond [`Window::instructions`] must take this into account.
+    ///
+    /// [`Successors::FallThrough`]: crate::flow::Successors::FallThrough
+    pub run: &'a [Instruction],
+
+    /// The labels defined right after [`Window::run`].
+    pub next_labels: &'a [Label],
+}
+
+/// A rewrite made by a [`Rule`].
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    /// The number of instructions at the start of the window to be replaced.
+    ///
+    /// This must be between 1 and the length of [`Window::run`].
+    pub consumed: usize,
+
+    pub replacement: Vec<Instruction>,
+}
+
+/// Errors of [`optimize()`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("rule {rule} consumed {consumed} instructions out of {available}")]
+    InvalidConsumedCount {
+        rule: &'static str,
+        consumed: usize,
+        available: usize,
+    },
+}
+
+/// A peephole rule.
+pub trait Rule {
+    /// Returns the name of this rule.
+    fn name(&self) -> &'static str;
+
+    /// Tries to rewrite the start of the window.
+    ///
+    /// A rule must eventually stop matching its own replacements, or [`optimize()`] never
+    /// terminates.
+    fn apply(&self, window: Window) -> Option<Rewrite>;
+}
+
+/// Returns the built-in rules.
+pub fn default_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(RemoveSelfMove),
+        Box::new(RemoveJumpToNext),
+        Box::new(FuseTaggedTuple),
+        Box::new(RemoveUnreachable),
+    ]
+}
+
+/// Applies rules until no rule matches, and returns the number of rewrites made.
+///
+/// At each position, the first matching rule in `rules` is applied.
+/// If a rule returns an invalid [`Rewrite::consumed`], `instructions` are left unchanged.
+pub fn optimize(
+    instructions: &mut Vec<Instruction>,
+    rules: &[Box<dyn Rule>],
+) -> Result<usize, Error> {
+    let mut count = 0;
+    let mut current = instructions.clone();
+    loop {
+        let mut block_ends = vec![current.len(); current.len()];
+        let mut run_ends = vec![current.len(); current.len()];
+        for i in (0..current.len()).rev() {
+            let next = current.get(i + 1);
+            if next.is_none_or(is_window_boundary) {
+                run_ends[i] = i + 1;
+                block_ends[i] = i + 1;
+            } else {
+                run_ends[i] = run_ends[i + 1];
+                block_ends[i] = if current[i].successors().is_terminator() {
+                    i + 1
+                } else {
+                    block_ends[i + 1]
+                };
+            }
+        }
+
+        let mut output = Vec::with_capacity(current.len());
+        let mut changed = false;
+        let mut i = 0;
+        while i < current.len() {
+            let end = run_ends[i];
+            let next_labels = current[end..]
+                .iter()
+                .map_while(|x| match x {
+                    Instruction::Label(x) => Some(Label { value: x.literal }),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let window = Window {
+                instructions: &current[i..block_ends[i]],
+                run: &current[i..end],
+                next_labels: &next_labels,
+            };
+            let rewrite = rules
+                .iter()
+                .find_map(|rule| rule.apply(window).map(|rewrite| (rule, rewrite)));
+            if let Some((rule, rewrite)) = rewrite {
+                if !(1..=end - i).contains(&rewrite.consumed) {
+                    return Err(Error::InvalidConsumedCount {
+                        rule: rule.name(),
+                        consumed: rewrite.consumed,
+                        available: end - i,
+                    });
+                }
+                output.extend(rewrite.replacement);
+                i += rewrite.consumed;
+                changed = true;
+                count += 1;
+            } else {
+                output.push(current[i].clone());
+                i += 1;
+            }
+        }
+        current = output;
+        if !changed {
+            *instructions = current;
+            return Ok(count);
+        }
+    }
+}
+
+fn is_window_boundary(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Label(_) | Instruction::IntCodeEnd(_)
+    )
+}
+
+/// Removes `move` instructions whose source and destination are the same register.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveSelfMove;
+
+impl Rule for RemoveSelfMove {
+    fn name(&self) -> &'static str {
+        "remove_self_move"
+    }
+
+    fn apply(&self, window: Window) -> Option<Rewrite> {
+        let Some(Instruction::Move(x)) = window.instructions.first() else {
+            return None;
+        };
+        (RegisterId::from_term(&x.src) == Some(RegisterId::from_register(&x.dst))).then(|| {
+            Rewrite {
+                consumed: 1,
+                replacement: Vec::new(),
+            }
+        })
+    }
+}
+
+/// Removes `jump` instructions to the label that immediately follows them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveJumpToNext;
+
+impl Rule for RemoveJumpToNext {
+    fn name(&self) -> &'static str {
+        "remove_jump_to_next"
+    }
+
+    fn apply(&self, window: Window) -> Option<Rewrite> {
+        let [Instruction::Jump(x)] = window.run else {
+            return None;
+        };
+        window.next_labels.contains(&x.label).then(|| Rewrite {
+            consumed: 1,
+            replacement: Vec::new(),
+        })
+    }
+}
+
+/// Fuses `is_tuple`, `test_arity`, `get_tuple_element` of the first element and `is_eq_exact`
+/// comparing it with an atom into `is_tagged_tuple` followed by the `get_tuple_element`.
+///
+/// All of the tests must share the same fail label, so the rule looks across them in
+/// [`Window::run`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FuseTaggedTuple;
+
+impl Rule for FuseTaggedTuple {
+    fn name(&self) -> &'static str {
+        "fuse_tagged_tuple"
+    }
+
+    fn apply(&self, window: Window) -> Option<Rewrite> {
+        use Instruction as I;
+
+        let (is_tuple, test_arity, get, is_eq) = match window.run {
+            [I::IsTuple(a), I::TestArity(b), I::GetTupleElement(c), I::IsEqExact(d), ..] => {
+                (a, b, c, d)
+            }
+            _ => return None,
+        };
+        let label = is_tuple.label;
+        let source = RegisterId::from_term(&is_tuple.arg1)?;
+        let destination = RegisterId::from_register(&get.destination);
+        let atom = match (&is_eq.arg1, &is_eq.arg2) {
+            (register, Term::Atom(atom)) | (Term::Atom(atom), register)
+                if RegisterId::from_term(register) == Some(destination) =>
+            {
+                *atom
+            }
+            _ => return None,
+        };
+        let valid = test_arity.label == label
+            && is_eq.label == label
+            && RegisterId::from_term(&test_arity.arg1) == Some(source)
+            && RegisterId::from_register(&get.source) == source
+            && get.element == 0;
+        if !valid {
+            return None;
+        }
+        Some(Rewrite {
+            consumed: 4,
+            replacement: vec![
+                Instruction::IsTaggedTuple(instruction::IsTaggedTuple {
+                    label,
+                    register: get.source.clone(),
+                    arity: test_arity.arity,
+                    atom,
+                }),
+                Instruction::GetTupleElement(get.clone()),
+            ],
+        })
+    }
+}
+
+/// Removes instructions that follow a terminator (e.g., `return`, `jump`) before the next label.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveUnreachable;
+
+impl Rule for RemoveUnreachable {
+    fn name(&self) -> &'static str {
+        "remove_unreachable"
+    }
+
+    fn apply(&self, window: Window) -> Option<Rewrite> {
+        let (first, rest) = window.run.split_first()?;
+        if rest.is_empty() || first.successors().falls_through() {
+            return None;
+        }
+        Some(Rewrite {
+            consumed: window.run.len(),
+            replacement: vec![first.clone()],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn optimize_works() {
+        let mut code = beam_asm! {
+            label 1;
+            func_info foo bar 1;
+            label 2;
+            is_tuple {f,1} {x,0};
+            test_arity {f,1} {x,0} 2;
+            get_tuple_element {x,0} 0 {x,1};
+            is_eq_exact {f,1} {x,1} ok;
+            is_tuple {f,1} {x,0};
+            test_arity {f,1} {x,0} 2;
+            get_tuple_element {x,0} 1 {x,1};
+            is_eq_exact {f,1} {x,1} ok;
+            jump {f,3};
+            move {x,1} {x,1};
+            label 3;
+            return;
+            int_code_end;
+        };
+        assert_eq!(optimize(&mut code, &default_rules()), Ok(3));
+        let code = code.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            code,
+            [
+                "label 1",
+                "func_info atom(1), atom(2), 1",
+                "label 2",
+                "is_tagged_tuple f(1), x(0), 2, atom(3)",
+                "get_tuple_element x(0), 0, x(1)",
+                "is_tuple f(1), x(0)",
+                "test_arity f(1), x(0), 2",
+                "get_tuple_element x(0), 1, x(1)",
+                "is_eq_exact f(1), x(1), atom(3)",
+                "label 3",
+                "return",
+                "int_code_end",
+            ]
+        );
+    }
+
+    // Records the window sizes and consumes more than the run of a `return`.
+    #[derive(Default)]
+    struct Invalid(Rc<RefCell<Vec<usize>>>);
+
+    impl Rule for Invalid {
+        fn name(&self) -> &'static str {
+            "invalid"
+        }
+
+        fn apply(&self, window: Window) -> Option<Rewrite> {
+            self.0.borrow_mut().push(window.instructions.len());
+            matches!(window.run, [Instruction::Return(_)]).then(|| Rewrite {
+                consumed: 2,
+                replacement: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn optimize_fails_with_invalid_rewrite() {
+        let mut code = beam_asm! {
+            label 1;
+            func_info foo bar 1;
+            label 2;
+            move {x,0} {x,1};
+            is_nil {f,1} {x,1};
+            move {x,1} {x,0};
+            return;
+        };
+        let sizes = Rc::default();
+        let rules: Vec<Box<dyn Rule>> = vec![Box::new(Invalid(Rc::clone(&sizes)))];
+        let len = code.len();
+        assert_eq!(
+            optimize(&mut code, &rules),
+            Err(Error::InvalidConsumedCount {
+                rule: "invalid",
+                consumed: 2,
+                available: 1
+            })
+        );
+        assert_eq!(code.len(), len);
+        assert_eq!(*sizes.borrow(), [2, 1, 3, 2, 1, 2, 1]);
+    }
+}
@@ -0,0 +1,508 @@
+//! Checking whether two builds of a module are equivalent.
+//!
+//! Compiling the same source twice does not always produce identical `.beam` files: the `CInf`
+//! chunk records the source path and compile options (and a timestamp in old compilers), the
+//! `Line` chunk records source paths, and the atom table, the literal table and the label
+//! numbering depend on the order in which the compiler visited the code. [`check()`] compares
+//! two builds modulo these parts as follows:
+//!
+//! - the `Code` chunk is compared function by function with [`diff::diff()`], which renumbers
+//!   labels and shows atoms, literals and imports by contents;
+//! - the atom (`AtU8` / `Atom`), literal (`LitT`) and import (`ImpT`) tables are compared
+//!   regardless of the order of their entries;
+//! - the export (`ExpT`), local function (`LocT`) and fun (`FunT`) tables are compared as sets
+//!   of entries with atoms shown by name and labels (and the `old_uniq` of funs) dropped;
+//! - the `CInf` chunk is compared without its `time` and `source` entries and the `outdir` and
+//!   `i` compile options;
+//! - the `Line` chunk is compared with its file names relative to their common directory;
+//! - chunks in [`CheckOptions::ignored_chunks`] are skipped;
+//! - the other chunks are compared byte by byte.
+//!
+//! Each build is given as its raw chunks and its decoded tables (e.g., loaded with
+//! `beam::BeamFile` of the `beam_file` feature as in `examples/repro-beam.rs`).
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::diff::Module;
+//! use beamcode::repro::{self, Build, Chunk, CheckOptions};
+//!
+//! let old_code = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 0;
+//!     label 2;
+//!     move {atom,1} {x,0};
+//!     return;
+//! };
+//! let new_code = beam_asm! {
+//!     label 1;
+//!     func_info {atom,2} {atom,1} 0;
+//!     label 2;
+//!     move {atom,2} {x,0};
+//!     return;
+//! };
+//! let old_atoms = ["foo".to_owned(), "bar".to_owned()];
+//! let new_atoms = ["bar".to_owned(), "foo".to_owned()];
+//! // [{source,"/a"}] and [{source,"/b"}] in the external term format.
+//! let info = |dir| [&b"\x83l\0\0\0\x01h\x02w\x06sourcek\0\x02/"[..], &[dir, b'j']].concat();
+//! let (old_info, new_info) = (info(b'a'), info(b'b'));
+//! let old_chunks = [Chunk::new(*b"CInf", &old_info)];
+//! let new_chunks = [Chunk::new(*b"CInf", &new_info)];
+//! let old = Build {
+//!     chunks: &old_chunks,
+//!     module: Module { code: &old_code, atoms: &old_atoms, ..Default::default() },
+//! };
+//! let new = Build {
+//!     chunks: &new_chunks,
+//!     module: Module { code: &new_code, atoms: &new_atoms, ..Default::default() },
+//! };
+//! assert!(repro::check(&old, &new, &CheckOptions::default()).is_empty());
+//! ```
+use crate::diff::{self, DiffOptions, Module};
+use crate::etf;
+use crate::module::Import;
+use crate::term::Term;
+use crate::Decode as _;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A chunk of a BEAM file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    pub id: [u8; 4],
+    pub data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    pub fn new(id: [u8; 4], data: &'a [u8]) -> Self {
+        Self { id, data }
+    }
+
+    /// Returns the chunk ID as a string (e.g., `"Code"`).
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.id).into_owned()
+    }
+}
+
+/// A build of a module to be compared by [`check()`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Build<'a> {
+    /// The raw chunks in the order of the file.
+    pub chunks: &'a [Chunk<'a>],
+
+    /// The decoded `Code`, atom, `LitT` and `ImpT` chunks.
+    pub module: Module<'a>,
+}
+
+impl Build<'_> {
+    fn chunk(&self, id: &[u8; 4]) -> Option<&Chunk<'_>> {
+        self.chunks.iter().find(|x| x.id == *id)
+    }
+}
+
+/// Options for [`check()`].
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// The IDs of chunks that are not compared.
+    pub ignored_chunks: Vec<[u8; 4]>,
+}
+
+/// A part where two builds differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// A chunk that exists only in the old build.
+    OnlyInOld { chunk: String },
+
+    /// A chunk that exists only in the new build.
+    OnlyInNew { chunk: String },
+
+    /// A chunk whose canonical contents differ.
+    Chunk { chunk: String },
+
+    /// A function whose normalized code differs (or that exists only in one of the builds).
+    Function { name: String, arity: usize },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OnlyInOld { chunk } => write!(f, "chunk {chunk}: only in the old build"),
+            Self::OnlyInNew { chunk } => write!(f, "chunk {chunk}: only in the new build"),
+            Self::Chunk { chunk } => write!(f, "chunk {chunk}: contents differ"),
+            Self::Function { name, arity } => write!(f, "function {name}/{arity}: code differs"),
+        }
+    }
+}
+
+/// Compares two builds of a module and returns the parts that differ.
+///
+/// Chunk differences come first in the order of the old build (followed by chunks only in the
+/// new build), and function differences follow in the order of [`diff::diff()`]. An empty
+/// result means the builds are equivalent.
+pub fn check(old: &Build, new: &Build, options: &CheckOptions) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    let ignored = |id: &[u8; 4]| options.ignored_chunks.contains(id);
+    for chunk in old.chunks.iter().filter(|x| !ignored(&x.id)) {
+        let name = chunk.name();
+        match new.chunk(&chunk.id) {
+            None => differences.push(Difference::OnlyInOld { chunk: name }),
+            Some(other) if !chunk_equal(old, chunk, new, other) => {
+                differences.push(Difference::Chunk { chunk: name });
+            }
+            Some(_) => {}
+        }
+    }
+    for chunk in new.chunks.iter().filter(|x| !ignored(&x.id)) {
+        if old.chunk(&chunk.id).is_none() {
+            differences.push(Difference::OnlyInNew {
+                chunk: chunk.name(),
+            });
+        }
+    }
+
+    let code = *b"Code";
+    if !ignored(&code) && old.chunk(&code).is_some() == new.chunk(&code).is_some() {
+        let diff = diff::diff(&old.module, &new.module, &DiffOptions { lines: true });
+        differences.extend(
+            diff.functions
+                .into_iter()
+                .filter(|x| !x.is_equal())
+                .map(|x| Difference::Function {
+                    name: x.name,
+                    arity: x.arity,
+                }),
+        );
+    }
+    differences
+}
+
+fn chunk_equal(old: &Build, old_chunk: &Chunk, new: &Build, new_chunk: &Chunk) -> bool {
+    let (old_module, new_module) = (&old.module, &new.module);
+    match &old_chunk.id {
+        // Compared function by function in `check()`.
+        b"Code" => true,
+        b"AtU8" | b"Atom" => sorted(old_module.atoms.iter()) == sorted(new_module.atoms.iter()),
+        b"LitT" => sorted(old_module.literals.iter()) == sorted(new_module.literals.iter()),
+        b"ImpT" => {
+            let key = |x: &Import| (x.module.clone(), x.function.clone(), x.arity);
+            sorted(old_module.imports.iter().map(key)) == sorted(new_module.imports.iter().map(key))
+        }
+        id @ (b"ExpT" | b"LocT" | b"FunT") => {
+            match (
+                function_table(id, old_chunk.data, old_module.atoms),
+                function_table(id, new_chunk.data, new_module.atoms),
+            ) {
+                (Some(old), Some(new)) => old == new,
+                _ => old_chunk.data == new_chunk.data,
+            }
+        }
+        b"CInf" => match (compile_info(old_chunk.data), compile_info(new_chunk.data)) {
+            (Some(old), Some(new)) => old == new,
+            _ => old_chunk.data == new_chunk.data,
+        },
+        b"Line" => match (line_table(old_chunk.data), line_table(new_chunk.data)) {
+            (Some(old), Some(new)) => old == new,
+            _ => old_chunk.data == new_chunk.data,
+        },
+        _ => old_chunk.data == new_chunk.data,
+    }
+}
+
+fn sorted<T: Ord>(items: impl Iterator<Item = T>) -> Vec<T> {
+    let mut items = items.collect::<Vec<_>>();
+    items.sort();
+    items
+}
+
+// Decodes an `ExpT`, `LocT` or `FunT` chunk into a set of entries without labels.
+//
+// Each entry of `ExpT` and `LocT` is `{Function, Arity, Label}`, and that of `FunT` is
+// `{Function, Arity, Label, Index, NumFree, OldUniq}` (all 32-bit big endian numbers).
+fn function_table(
+    id: &[u8; 4],
+    data: &[u8],
+    atoms: &[String],
+) -> Option<BTreeSet<(String, Vec<u32>)>> {
+    let words = data
+        .chunks(4)
+        .map(|x| Some(u32::from_be_bytes(x.try_into().ok()?)))
+        .collect::<Option<Vec<_>>>()?;
+    let (&count, entries) = words.split_first()?;
+    let width = if id == b"FunT" { 6 } else { 3 };
+    if entries.len() != count as usize * width {
+        return None;
+    }
+    entries
+        .chunks(width)
+        .map(|entry| {
+            let name = atoms.get((entry[0] as usize).checked_sub(1)?)?.clone();
+            let mut rest = vec![entry[1]];
+            if width == 6 {
+                rest.extend_from_slice(&entry[3..5]);
+            }
+            Some((name, rest))
+        })
+        .collect()
+}
+
+// Decodes a `CInf` chunk (`[{Key, Value}]`) without the entries that depend on the build
+// environment: `time`, `source`, and the `outdir` and `i` compile options.
+fn compile_info(data: &[u8]) -> Option<Vec<etf::Term>> {
+    let is_key = |term: &etf::Term, keys: &[&str]| match term {
+        etf::Term::Tuple(x) if x.len() == 2 => x[0].as_atom().is_some_and(|x| keys.contains(&x)),
+        _ => false,
+    };
+    let mut entries = Vec::new();
+    for entry in etf::decode(data).ok()?.as_list()? {
+        match entry {
+            _ if is_key(entry, &["time", "source"]) => {}
+            etf::Term::Tuple(x) if is_key(entry, &["options"]) => {
+                let options = x[1]
+                    .as_list()?
+                    .iter()
+                    .filter(|x| !is_key(x, &["outdir", "i"]))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let options = if options.is_empty() {
+                    etf::Term::Nil
+                } else {
+                    etf::Term::List(options, Box::new(etf::Term::Nil))
+                };
+                entries.push(etf::Term::Tuple(vec![x[0].clone(), options]));
+            }
+            _ => entries.push(entry.clone()),
+        }
+    }
+    Some(entries)
+}
+
+// Splits a `Line` chunk into the part before the file names and the file names relative to their
+// common directory (see `relative_paths()`).
+//
+// The chunk consists of five 32-bit words (`Version`, `Bits`, `NumLineInstrs`, `NumLines` and
+// `NumFnames`), `NumLines` line numbers in the compact term format (each preceded by the
+// atom-tagged file index if the file changes), and `NumFnames` file names as
+// `<<Len:16, Name/binary>>`.
+fn line_table(data: &[u8]) -> Option<(&[u8], Vec<String>)> {
+    let word = |i: usize| {
+        Some(u32::from_be_bytes(
+            data.get(i * 4..i * 4 + 4)?.try_into().ok()?,
+        ))
+    };
+    let (line_count, file_count) = (word(3)?, word(4)?);
+    let mut reader = data.get(20..)?;
+    let mut lines = 0;
+    while lines < line_count {
+        if let Term::Integer(_) = Term::decode(&mut reader).ok()? {
+            lines += 1;
+        }
+    }
+    let (items, mut reader) = data.split_at(data.len() - reader.len());
+
+    let mut files = Vec::new();
+    for _ in 0..file_count {
+        let len = usize::from(u16::from_be_bytes(reader.get(..2)?.try_into().ok()?));
+        let name = String::from_utf8_lossy(reader.get(2..2 + len)?);
+        files.push(name.into_owned());
+        reader = &reader[2 + len..];
+    }
+    reader.is_empty().then_some((items, relative_paths(&files)))
+}
+
+// Strips the longest directory prefix shared by all paths (e.g., the source root of a build), so
+// that builds in different directories compare equal while `a/x.hrl` and `b/x.hrl` stay distinct.
+fn relative_paths(paths: &[String]) -> Vec<String> {
+    let components = paths
+        .iter()
+        .map(|x| x.split(['/', '\\']).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let max = components.iter().map(|x| x.len() - 1).min().unwrap_or(0);
+    let common = (0..max)
+        .take_while(|&i| components.iter().all(|x| x[i] == components[0][i]))
+        .count();
+    components.iter().map(|x| x[common..].join("/")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    #[test]
+    fn check_works() {
+        let old_code = beam_asm! {
+            label 1;
+            func_info m foo 0;
+            label 2;
+            move {literal,0} {x,0};
+            call_ext_only 0 0;
+            label 3;
+            func_info m bar 0;
+            label 4;
+            move {literal,1} {x,0};
+            return;
+        };
+        let new_code = beam_asm! {
+            label 11;
+            func_info {atom,1} {atom,3} 0;
+            label 12;
+            move {literal,1} {x,0};
+            call_ext_only 0 1;
+            label 13;
+            func_info {atom,1} {atom,2} 0;
+            label 14;
+            move {literal,1} {x,1};
+            return;
+        };
+        let old_atoms = ["m", "foo", "bar"].map(String::from);
+        let new_atoms = ["m", "bar", "foo"].map(String::from);
+        let old_literals = [vec![131, 106], vec![131, 97, 1]];
+        let new_literals = [vec![131, 97, 1], vec![131, 106]];
+        let old_imports = [Import::new("erlang", "self", 0)];
+        let new_imports = [
+            Import::new("erlang", "+", 2),
+            Import::new("erlang", "self", 0),
+        ];
+
+        // ExpT: [{foo, 0, 2}, {bar, 0, 4}] and [{bar, 0, 14}, {foo, 0, 12}].
+        let old_exports = [0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2]
+            .into_iter()
+            .chain([0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4])
+            .collect::<Vec<u8>>();
+        let new_exports = [0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 14]
+            .into_iter()
+            .chain([0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 12])
+            .collect::<Vec<u8>>();
+        // CInf: [{options,[{outdir,Dir},debug_info]},{source,Dir ++ "/m.erl"}].
+        let string = |x: &str| [&[107, 0, x.len() as u8], x.as_bytes()].concat();
+        let compile_info = |dir: &str, option: &str| {
+            let mut bytes = vec![131, 108, 0, 0, 0, 2, 104, 2, 119, 7];
+            bytes.extend_from_slice(b"options");
+            bytes.extend_from_slice(&[108, 0, 0, 0, 2, 104, 2, 119, 6]);
+            bytes.extend_from_slice(b"outdir");
+            bytes.extend(string(dir));
+            bytes.extend_from_slice(&[119, option.len() as u8]);
+            bytes.extend_from_slice(option.as_bytes());
+            bytes.extend_from_slice(&[106, 104, 2, 119, 6]);
+            bytes.extend_from_slice(b"source");
+            bytes.extend(string(&format!("{dir}/m.erl")));
+            bytes.push(106);
+            bytes
+        };
+        let old_info = compile_info("/tmp/a", "debug_info");
+        let new_info = compile_info("/tmp/b", "debug_info");
+
+        // Line: a single line 5 in the first of `files`.
+        let lines = |files: &[&str]| {
+            let mut bytes = [0, 0, 0, 1, files.len() as u32]
+                .map(u32::to_be_bytes)
+                .concat();
+            bytes.push(0x51);
+            for name in files {
+                bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+                bytes.extend_from_slice(name.as_bytes());
+            }
+            bytes
+        };
+        let old_lines = lines(&["/tmp/a/src/m.erl", "/tmp/a/include/x.hrl"]);
+        let new_lines = lines(&["/tmp/b/src/m.erl", "/tmp/b/include/x.hrl"]);
+        let old_chunks = [
+            Chunk::new(*b"AtU8", &[0]),
+            Chunk::new(*b"Code", &[1]),
+            Chunk::new(*b"ExpT", &old_exports),
+            Chunk::new(*b"ImpT", &[]),
+            Chunk::new(*b"LitT", &[]),
+            Chunk::new(*b"CInf", &old_info),
+            Chunk::new(*b"Line", &old_lines),
+            Chunk::new(*b"Dbgi", &[]),
+        ];
+        let new_chunks = [
+            Chunk::new(*b"AtU8", &[1]),
+            Chunk::new(*b"Code", &[2]),
+            Chunk::new(*b"ExpT", &new_exports),
+            Chunk::new(*b"ImpT", &[]),
+            Chunk::new(*b"LitT", &[]),
+            Chunk::new(*b"CInf", &new_info),
+            Chunk::new(*b"Line", &new_lines),
+            Chunk::new(*b"Docs", &[]),
+        ];
+        let old = Build {
+            chunks: &old_chunks,
+            module: Module {
+                code: &old_code,
+                atoms: &old_atoms,
+                literals: &old_literals,
+                imports: &old_imports,
+            },
+        };
+        let new = Build {
+            chunks: &new_chunks,
+            module: Module {
+                code: &new_code,
+                atoms: &new_atoms,
+                literals: &new_literals,
+                imports: &new_imports,
+            },
+        };
+
+        let differences = check(&old, &new, &CheckOptions::default());
+        assert_eq!(
+            differences
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "chunk ImpT: contents differ",
+                "chunk Dbgi: only in the old build",
+                "chunk Docs: only in the new build",
+                "function bar/0: code differs",
+            ]
+        );
+
+        assert!(check(&old, &old, &CheckOptions::default()).is_empty());
+
+        // Other compile options and line numbers are compared.
+        let new_info = compile_info("/tmp/b", "warnings_as_errors");
+        let mut new_lines = lines(&["/tmp/b/src/m.erl", "/tmp/b/include/x.hrl"]);
+        new_lines[20] = 0x61;
+        let new_chunks = [
+            Chunk::new(*b"CInf", &new_info),
+            Chunk::new(*b"Line", &new_lines),
+        ];
+        let new = Build {
+            chunks: &new_chunks,
+            ..new
+        };
+        let differences = check(&old, &new, &CheckOptions::default());
+        for chunk in ["CInf", "Line"] {
+            assert!(differences.contains(&Difference::Chunk {
+                chunk: chunk.to_owned()
+            }));
+        }
+        let options = CheckOptions {
+            ignored_chunks: vec![*b"CInf", *b"Line"],
+        };
+        assert!(!check(&old, &new, &options)
+            .iter()
+            .any(|x| matches!(x, Difference::Chunk { .. })));
+
+        // Files with the same name in different directories are distinguished.
+        let new_lines = lines(&["/tmp/b/src/m.erl", "/tmp/b/src/x.hrl"]);
+        let new_chunks = [Chunk::new(*b"Line", &new_lines)];
+        let old = Build {
+            chunks: &old_chunks[6..7],
+            ..old
+        };
+        let new = Build {
+            chunks: &new_chunks,
+            ..old
+        };
+        assert_eq!(
+            check(&old, &new, &CheckOptions::default()),
+            [Difference::Chunk {
+                chunk: "Line".to_owned()
+            }]
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Opcode and operand usage statistics.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::stats::Stats;
+//!
+//! let code = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 1;
+//!     label 2;
+//!     int_band {f,0} {x,0} {integer,1} {x,0};
+//!     return;
+//! };
+//! let mut stats = Stats::default();
+//! stats.add_module(&code).unwrap();
+//! assert_eq!(stats.opcodes["label"].count, 2);
+//! assert_eq!(stats.operand_kinds["x"], 2);
+//! assert_eq!(stats.deprecated().map(|x| x.0).collect::<Vec<_>>(), ["int_band"]);
+//! ```
+use crate::instruction::Instruction;
+use crate::operand::Operand;
+use crate::term::{Allocation, Register, Term};
+use crate::{Encode, EncodeError};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// The usage of an opcode.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpcodeStats {
+    pub opcode: u8,
+
+    /// The number of occurrences.
+    pub count: usize,
+
+    /// The total encoded size of the occurrences in bytes.
+    pub bytes: usize,
+
+    /// The number of modules where the opcode occurs.
+    pub modules: usize,
+
+    /// See [`Instruction::is_deprecated()`].
+    pub deprecated: bool,
+}
+
+/// Opcode and operand usage aggregated over modules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// The number of modules added.
+    pub modules: usize,
+
+    pub instructions: usize,
+
+    /// The total encoded size of the instructions in bytes.
+    pub bytes: usize,
+
+    /// The usage of each opcode keyed by its name (e.g., `"is_nil"`).
+    pub opcodes: BTreeMap<String, OpcodeStats>,
+
+    /// The number of operands of each kind keyed by its name (see [`operand_kind()`]).
+    pub operand_kinds: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    /// Adds the code of a module.
+    pub fn add_module(&mut self, instructions: &[Instruction]) -> Result<(), EncodeError> {
+        let mut module = Self {
+            modules: 1,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        for instruction in instructions {
+            buf.clear();
+            instruction.encode(&mut buf)?;
+            module.instructions += 1;
+            module.bytes += buf.len();
+
+            let opcode = module
+                .opcodes
+                .entry(instruction.name().to_owned())
+                .or_insert_with(|| OpcodeStats {
+                    opcode: instruction.opcode(),
+                    modules: 1,
+                    deprecated: instruction.is_deprecated(),
+                    ..Default::default()
+                });
+            opcode.count += 1;
+            opcode.bytes += buf.len();
+            for operand in instruction.operands() {
+                *module
+                    .operand_kinds
+                    .entry(operand_kind(&operand).to_owned())
+                    .or_default() += 1;
+            }
+        }
+        self.merge(&module);
+        Ok(())
+    }
+
+    /// Adds the statistics collected elsewhere (e.g., in another thread).
+    pub fn merge(&mut self, other: &Self) {
+        self.modules += other.modules;
+        self.instructions += other.instructions;
+        self.bytes += other.bytes;
+        for (name, stats) in &other.opcodes {
+            let entry = self
+                .opcodes
+                .entry(name.clone())
+                .or_insert_with(|| OpcodeStats {
+                    opcode: stats.opcode,
+                    deprecated: stats.deprecated,
+                    ..Default::default()
+                });
+            entry.count += stats.count;
+            entry.bytes += stats.bytes;
+            entry.modules += stats.modules;
+        }
+        for (kind, count) in &other.operand_kinds {
+            *self.operand_kinds.entry(kind.clone()).or_default() += count;
+        }
+    }
+
+    /// Returns the deprecated opcodes that occur.
+    pub fn deprecated(&self) -> impl Iterator<Item = (&str, &OpcodeStats)> {
+        self.opcodes
+            .iter()
+            .filter(|x| x.1.deprecated)
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+
+    /// Formats [`Stats::opcodes`] as CSV with the header
+    /// `name,opcode,count,bytes,modules,deprecated`.
+    pub fn opcodes_csv(&self) -> String {
+        let mut out = "name,opcode,count,bytes,modules,deprecated\n".to_owned();
+        for (name, x) in &self.opcodes {
+            let _ = writeln!(
+                out,
+                "{name},{},{},{},{},{}",
+                x.opcode, x.count, x.bytes, x.modules, x.deprecated
+            );
+        }
+        out
+    }
+
+    /// Formats [`Stats::operand_kinds`] as CSV with the header `kind,count`.
+    pub fn operand_kinds_csv(&self) -> String {
+        let mut out = "kind,count\n".to_owned();
+        for (kind, count) in &self.operand_kinds {
+            let _ = writeln!(out, "{kind},{count}");
+        }
+        out
+    }
+}
+
+/// Returns the name of the encoded kind of an operand (e.g., `"x"` for X registers and
+/// `"allocation_list"` for allocation lists).
+///
+/// Lists of Y registers (e.g., of `init_yregs`) are counted as `"list"`.
+pub fn operand_kind(operand: &Operand) -> &'static str {
+    match operand {
+        Operand::Usize(_) => "usize",
+        Operand::Atom(_) => "atom",
+        Operand::Label(_) => "label",
+        Operand::Term(x) => match x {
+            Term::Usize(_) => "usize",
+            Term::Integer(_) => "integer",
+            Term::Atom(_) => "atom",
+            Term::XRegister(_) => "x",
+            Term::YRegister(_) => "y",
+            Term::Label(_) => "label",
+            Term::Character(_) => "character",
+            Term::List(_) => "list",
+            Term::FloatingPointRegister(_) => "fr",
+            Term::AllocationList(_) => "allocation_list",
+            Term::Literal(_) => "literal",
+            Term::TypedRegister(_) => "typed_register",
+        },
+        Operand::Register(x) => match x {
+            Register::X(_) => "x",
+            Register::Y(_) => "y",
+            Register::Typed(_) => "typed_register",
+        },
+        Operand::Allocation(x) => match x {
+            Allocation::Words(_) => "usize",
+            Allocation::List(_) => "allocation_list",
+        },
+        Operand::List(_) | Operand::YRegisters(_) => "list",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    #[test]
+    fn stats_works() {
+        let foo = beam_asm! {
+            label 1;
+            func_info foo bar 1;
+            label 2;
+            allocate 1 1;
+            move {x,0} {y,0};
+            put_string 3 0 {x,0};
+            deallocate 1;
+            return;
+        };
+        let bar = beam_asm! {
+            label 1;
+            func_info bar baz 0;
+            label 2;
+            move {literal,0} {x,0};
+            return;
+        };
+        let mut stats = Stats::default();
+        stats.add_module(&foo).unwrap();
+        let mut other = Stats::default();
+        other.add_module(&bar).unwrap();
+        stats.merge(&other);
+
+        assert_eq!(stats.modules, 2);
+        assert_eq!(stats.instructions, 13);
+        assert_eq!(
+            stats.opcodes["move"],
+            OpcodeStats {
+                opcode: 64,
+                count: 2,
+                bytes: 7,
+                modules: 2,
+                deprecated: false,
+            }
+        );
+        assert_eq!(stats.opcodes["return"].bytes, 2);
+        assert_eq!(
+            stats.bytes,
+            stats.opcodes.values().map(|x| x.bytes).sum::<usize>()
+        );
+        assert_eq!(stats.operand_kinds["x"], 3);
+        assert_eq!(stats.operand_kinds["literal"], 1);
+        assert_eq!(
+            stats.deprecated().map(|x| x.0).collect::<Vec<_>>(),
+            ["put_string"]
+        );
+        assert_eq!(
+            stats.operand_kinds_csv(),
+            "kind,count\natom,4\nliteral,1\nusize,11\nx,3\ny,1\n"
+        );
+        assert!(stats.opcodes_csv().contains("\nput_string,68,1,4,1,true\n"));
+    }
+}
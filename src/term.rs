@@ -80,8 +80,11 @@ impl TermKind {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Term {
     Usize(usize),
+    #[cfg_attr(feature = "serde", serde(with = "serde_bigint"))]
     Integer(BigInt),
     Atom(Atom),
     XRegister(XRegister),
@@ -121,7 +124,75 @@ impl Decode for Term {
     }
 }
 
+impl From<usize> for Term {
+    fn from(x: usize) -> Self {
+        Self::Usize(x)
+    }
+}
+
+impl From<BigInt> for Term {
+    fn from(x: BigInt) -> Self {
+        Self::Integer(x)
+    }
+}
+
+impl From<Atom> for Term {
+    fn from(x: Atom) -> Self {
+        Self::Atom(x)
+    }
+}
+
+impl From<XRegister> for Term {
+    fn from(x: XRegister) -> Self {
+        Self::XRegister(x)
+    }
+}
+
+impl From<YRegister> for Term {
+    fn from(x: YRegister) -> Self {
+        Self::YRegister(x)
+    }
+}
+
+impl From<Label> for Term {
+    fn from(x: Label) -> Self {
+        Self::Label(x)
+    }
+}
+
+impl From<List> for Term {
+    fn from(x: List) -> Self {
+        Self::List(x)
+    }
+}
+
+impl From<FloatingPointRegister> for Term {
+    fn from(x: FloatingPointRegister) -> Self {
+        Self::FloatingPointRegister(x)
+    }
+}
+
+impl From<AllocationList> for Term {
+    fn from(x: AllocationList) -> Self {
+        Self::AllocationList(x)
+    }
+}
+
+impl From<Literal> for Term {
+    fn from(x: Literal) -> Self {
+        Self::Literal(x)
+    }
+}
+
+impl From<TypedRegister> for Term {
+    fn from(x: TypedRegister) -> Self {
+        Self::TypedRegister(x)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Allocation {
     Words(usize),
     List(AllocationList),
@@ -139,7 +210,20 @@ impl Decode for Allocation {
     }
 }
 
+impl From<usize> for Allocation {
+    fn from(x: usize) -> Self {
+        Self::Words(x)
+    }
+}
+
+impl From<AllocationList> for Allocation {
+    fn from(x: AllocationList) -> Self {
+        Self::List(x)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AllocationList {
     pub items: Vec<AllocationListItem>,
 }
@@ -167,6 +251,8 @@ impl Encode for AllocationList {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum AllocationListItem {
     Words(usize),
     Floats(usize),
@@ -205,6 +291,7 @@ impl Encode for AllocationListItem {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatingPointRegister {
     pub value: usize,
 }
@@ -226,6 +313,8 @@ impl Encode for FloatingPointRegister {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TypedRegister {
     X { register: XRegister, ty: usize },
     Y { register: YRegister, ty: usize },
@@ -268,6 +357,8 @@ impl Encode for TypedRegister {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Register {
     X(XRegister),
     Y(YRegister),
@@ -292,6 +383,24 @@ impl Decode for Register {
     }
 }
 
+impl From<XRegister> for Register {
+    fn from(x: XRegister) -> Self {
+        Self::X(x)
+    }
+}
+
+impl From<YRegister> for Register {
+    fn from(x: YRegister) -> Self {
+        Self::Y(x)
+    }
+}
+
+impl From<TypedRegister> for Register {
+    fn from(x: TypedRegister) -> Self {
+        Self::Typed(x)
+    }
+}
+
 impl Decode for usize {
     fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::Usize])?;
@@ -325,6 +434,7 @@ impl Encode for char {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Literal {
     pub value: usize,
 }
@@ -360,6 +470,7 @@ impl Encode for BigInt {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Atom {
     pub value: usize,
 }
@@ -379,6 +490,7 @@ impl Encode for Atom {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XRegister {
     pub value: usize,
     pub ty: Option<usize>,
@@ -399,6 +511,7 @@ impl Encode for XRegister {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct YRegister {
     pub value: usize,
     pub ty: Option<usize>,
@@ -435,6 +548,7 @@ impl Encode for Vec<YRegister> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Label {
     pub value: usize,
 }
@@ -454,10 +568,17 @@ impl Encode for Label {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct List<T = Term> {
     pub items: Vec<T>,
 }
 
+impl<T> From<Vec<T>> for List<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self { items }
+    }
+}
+
 impl<T: Decode> Decode for List<T> {
     fn decode_with_tag<R: Read>(reader: &mut R, tag: u8) -> Result<Self, DecodeError> {
         TermKind::from_tag(tag).expect(&[TermKind::List])?;
@@ -537,6 +658,23 @@ fn encode_integer_bytes<W: Write>(
     Ok(())
 }
 
+#[cfg(feature = "serde")]
+mod serde_bigint {
+    //! Integers are (de)serialized as decimal strings so that arbitrarily large values survive
+    //! formats like JSON that only have 64-bit numbers.
+    use num::BigInt;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
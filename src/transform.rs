@@ -0,0 +1,488 @@
+//! Transformations of module code.
+use crate::call_graph::CallGraph;
+use crate::cfg::{BlockId, Cfg};
+use crate::instruction::Instruction;
+use crate::module::FunctionTables;
+use crate::term::Label;
+use crate::visit::{LabelCollector, VisitMut, WalkMut};
+use std::collections::{HashMap, HashSet};
+
+/// Errors of [`renumber_labels()`] and the transformations based on it.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// A label operand or table entry refers to a label that no `label` instruction defines.
+    #[error("undefined label: {label}")]
+    UndefinedLabel { label: usize },
+}
+
+/// The mapping from old labels to new ones made by [`renumber_labels()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelMap {
+    labels: HashMap<usize, usize>,
+    label_count: usize,
+}
+
+impl LabelMap {
+    /// Returns the new label for an old one.
+    ///
+    /// Label 0 (i.e., "no label") is always mapped to itself.
+    pub fn get(&self, old: Label) -> Option<Label> {
+        if old.value == 0 {
+            return Some(old);
+        }
+        self.labels.get(&old.value).map(|&value| Label { value })
+    }
+
+    /// Returns the number of labels to be written to the header of the `Code` chunk.
+    ///
+    /// As label numbers start from 1, this is the highest label plus one.
+    pub fn label_count(&self) -> usize {
+        self.label_count
+    }
+
+    fn renumber(&self, old: usize) -> usize {
+        self.labels.get(&old).copied().unwrap_or(old)
+    }
+
+    fn check(&self, label: Label) -> Result<(), Error> {
+        match self.get(label) {
+            Some(_) => Ok(()),
+            None => Err(Error::UndefinedLabel { label: label.value }),
+        }
+    }
+}
+
+/// Renumbers labels so that they are dense and start from 1.
+///
+/// Labels are numbered in the order they are defined by [`Instruction::Label`], and every
+/// [`Label`] operand (including those in lists such as `select_val` destinations) is rewritten.
+/// If an operand refers to an undefined label, [`Error::UndefinedLabel`] is returned and the
+/// instructions are left unchanged, as the reference could collide with a new number.
+///
+/// Tables outside of the code chunk that refer to labels (i.e., the export, local and lambda
+/// tables) are not updated; use [`renumber_module_labels()`] for them.
+///
+/// # Examples
+///
+/// ```
+/// use beamcode::instruction::{self, Instruction};
+/// use beamcode::term::Label;
+/// use beamcode::transform;
+///
+/// let mut code = vec![
+///     Instruction::Label(instruction::Label { literal: 10 }),
+///     Instruction::Jump(instruction::Jump { label: Label { value: 10 } }),
+/// ];
+/// let map = transform::renumber_labels(&mut code).unwrap();
+/// assert_eq!(map.get(Label { value: 10 }), Some(Label { value: 1 }));
+/// assert_eq!(map.label_count(), 2);
+/// assert!(matches!(&code[1], Instruction::Jump(x) if x.label.value == 1));
+/// ```
+pub fn renumber_labels(instructions: &mut [Instruction]) -> Result<LabelMap, Error> {
+    let map = label_map(instructions)?;
+    renumber(instructions, &map);
+    Ok(map)
+}
+
+/// Renumbers labels as [`renumber_labels()`] does and rewrites the labels of the export, local
+/// and lambda tables accordingly.
+///
+/// Entries referring to undefined labels are reported as [`Error::UndefinedLabel`] too, in
+/// which case neither the instructions nor `tables` are changed.
+pub fn renumber_module_labels(
+    instructions: &mut [Instruction],
+    tables: &mut FunctionTables,
+) -> Result<LabelMap, Error> {
+    let map = label_map(instructions)?;
+    for label in tables.labels() {
+        map.check(label)?;
+    }
+    renumber(instructions, &map);
+    for label in tables.labels_mut() {
+        label.value = map.renumber(label.value);
+    }
+    Ok(map)
+}
+
+// Numbers the defined labels and checks that every label operand refers to one of them.
+fn label_map(instructions: &[Instruction]) -> Result<LabelMap, Error> {
+    let mut map = LabelMap::default();
+    for instruction in instructions {
+        match instruction {
+            Instruction::Label(x) if x.literal != 0 => {
+                let next = map.labels.len() + 1;
+                map.labels.entry(x.literal).or_insert(next);
+            }
+            _ => {}
+        }
+    }
+    map.label_count = map.labels.len() + 1;
+    for instruction in instructions {
+        for label in LabelCollector::collect(instruction) {
+            map.check(label)?;
+        }
+    }
+    Ok(map)
+}
+
+fn renumber(instructions: &mut [Instruction], map: &LabelMap) {
+    let mut renumberer = Renumberer { map };
+    for instruction in instructions.iter_mut() {
+        instruction.accept_mut(&mut renumberer);
+    }
+}
+
+/// Removes functions and blocks that are never executed, then renumbers labels and rewrites
+/// `tables` accordingly.
+///
+/// The roots are the functions in the export and lambda tables and those containing `on_load`
+/// or `nif_start`. They are kept along with the functions they refer to (e.g., by local calls).
+/// Within a kept function, the blocks unreachable from its entry or header are removed.
+/// Entries of the local function table for removed functions are dropped.
+///
+/// Undefined labels are reported as [`renumber_module_labels()`] does, but only after the
+/// instructions have been removed.
+///
+/// # Examples
+///
+/// ```
+/// use beamcode::beam_asm;
+/// use beamcode::module::{FunctionEntry, FunctionTables};
+/// use beamcode::term::{Atom, Label};
+/// use beamcode::transform;
+///
+/// let mut code = beam_asm! {
+///     label 1;
+///     func_info foo bar 0;
+///     label 2;
+///     return;
+///     label 3;
+///     func_info foo baz 0;
+///     label 4;
+///     return;
+///     int_code_end;
+/// };
+/// let entry = |name, label| FunctionEntry {
+///     function: Atom { value: name },
+///     arity: 0,
+///     label: Label { value: label },
+/// };
+/// let mut tables = FunctionTables {
+///     exports: vec![entry(3, 4)],
+///     locals: vec![entry(2, 2)],
+///     ..Default::default()
+/// };
+/// let map = transform::eliminate_dead_code(&mut code, &mut tables).unwrap();
+/// assert_eq!(map.get(Label { value: 2 }), None);
+/// assert_eq!(tables.exports, [entry(3, 2)]);
+/// assert_eq!(tables.locals, []);
+/// assert_eq!(code.len(), 5);
+/// ```
+pub fn eliminate_dead_code(
+    instructions: &mut Vec<Instruction>,
+    tables: &mut FunctionTables,
+) -> Result<LabelMap, Error> {
+    let graph = CallGraph::new(instructions, &tables.lambdas);
+    let functions = graph.functions();
+    let mut stack = tables
+        .exports
+        .iter()
+        .map(|x| x.label)
+        .chain(tables.lambdas.iter().map(|x| x.label))
+        .filter_map(|label| graph.function_of_label(label))
+        .collect::<Vec<_>>();
+    for (i, function) in functions.iter().enumerate() {
+        let is_special = instructions[function.range.clone()]
+            .iter()
+            .any(|x| matches!(x, Instruction::OnLoad(_) | Instruction::NifStart(_)));
+        if is_special {
+            stack.push(i);
+        }
+    }
+
+    // Instructions outside of functions (e.g., `int_code_end`) are kept.
+    let mut keep = vec![true; instructions.len()];
+    for function in functions {
+        keep[function.range.clone()].fill(false);
+    }
+    let mut visited = vec![false; functions.len()];
+    while let Some(i) = stack.pop() {
+        if std::mem::replace(&mut visited[i], true) {
+            continue;
+        }
+        let offset = functions[i].range.start;
+        let live = live_instructions(&instructions[functions[i].range.clone()]);
+        for (position, _) in live.iter().enumerate().filter(|(_, live)| **live) {
+            keep[offset + position] = true;
+            for label in LabelCollector::collect(&instructions[offset + position]) {
+                stack.extend(graph.function_of_label(label));
+            }
+        }
+    }
+
+    let mut keep = keep.into_iter();
+    instructions.retain(|_| keep.next().unwrap_or(true));
+    let defined = instructions
+        .iter()
+        .filter_map(|x| match x {
+            Instruction::Label(x) => Some(x.literal),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    tables.locals.retain(|x| defined.contains(&x.label.value));
+    renumber_module_labels(instructions, tables)
+}
+
+// Returns whether each instruction of a function is reachable from its entry or header.
+fn live_instructions(instructions: &[Instruction]) -> Vec<bool> {
+    let cfg = Cfg::new(instructions);
+    let mut stack = vec![cfg.entry()];
+    for (i, block) in cfg.blocks().iter().enumerate() {
+        if instructions[block.range.clone()]
+            .iter()
+            .any(|x| matches!(x, Instruction::FuncInfo(_)))
+        {
+            stack.push(BlockId(i));
+        }
+    }
+
+    let mut live = vec![false; instructions.len()];
+    let mut visited = vec![false; cfg.blocks().len()];
+    while let Some(id) = stack.pop() {
+        if cfg.blocks().is_empty() || std::mem::replace(&mut visited[id.0], true) {
+            continue;
+        }
+        let block = cfg.block(id);
+        live[block.range.clone()].fill(true);
+        stack.extend(block.successors.iter().map(|x| x.target));
+        for instruction in &instructions[block.range.clone()] {
+            for label in LabelCollector::collect(instruction) {
+                stack.extend(cfg.block_of_label(label));
+            }
+        }
+    }
+    live
+}
+
+#[derive(Debug)]
+struct Renumberer<'a> {
+    map: &'a LabelMap,
+}
+
+impl VisitMut for Renumberer<'_> {
+    fn visit_instruction_mut(&mut self, instruction: &mut Instruction) {
+        if let Instruction::Label(x) = instruction {
+            x.literal = self.map.renumber(x.literal);
+        }
+        instruction.walk_mut(self);
+    }
+
+    fn visit_label_mut(&mut self, label: &mut Label) {
+        label.value = self.map.renumber(label.value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+    use crate::instruction;
+    use crate::module::{FunctionEntry, LambdaEntry};
+    use crate::term::{Atom, List, Term, XRegister};
+
+    #[test]
+    fn renumber_labels_works() {
+        let mut code = vec![
+            Instruction::Label(instruction::Label { literal: 5 }),
+            Instruction::SelectVal(instruction::SelectVal {
+                arg: Term::XRegister(XRegister { value: 0, ty: None }),
+                fail_label: Label { value: 9 },
+                destinations: List {
+                    items: vec![
+                        Term::Atom(Atom { value: 1 }),
+                        Term::Label(Label { value: 7 }),
+                    ],
+                },
+            }),
+            Instruction::Label(instruction::Label { literal: 7 }),
+            Instruction::Bif1(instruction::Bif1 {
+                arg1: Term::Label(Label { value: 0 }),
+                arg2: Term::Usize(0),
+                arg3: Term::Atom(Atom { value: 1 }),
+                arg4: Term::XRegister(XRegister { value: 0, ty: None }),
+            }),
+            Instruction::Label(instruction::Label { literal: 9 }),
+            Instruction::Jump(instruction::Jump {
+                label: Label { value: 7 },
+            }),
+        ];
+        let map = renumber_labels(&mut code).unwrap();
+        assert_eq!(map.get(Label { value: 5 }), Some(Label { value: 1 }));
+        assert_eq!(map.get(Label { value: 7 }), Some(Label { value: 2 }));
+        assert_eq!(map.get(Label { value: 9 }), Some(Label { value: 3 }));
+        assert_eq!(map.get(Label { value: 20 }), None);
+        assert_eq!(map.get(Label { value: 6 }), None);
+        assert_eq!(map.label_count(), 4);
+        assert_eq!(
+            code.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            [
+                "label 1",
+                "select_val x(0), f(3), [atom(1), f(2)]",
+                "label 2",
+                "bif1 f(0), 0, atom(1), x(0)",
+                "label 3",
+                "jump f(2)"
+            ]
+        );
+
+        // A dangling reference is not renumbered into a defined label.
+        let mut code = beam_asm! {
+            label 5;
+            label 7;
+            label 9;
+            jump {f,2};
+        };
+        assert_eq!(
+            renumber_labels(&mut code),
+            Err(Error::UndefinedLabel { label: 2 })
+        );
+        assert_eq!(code[0].to_string(), "label 5");
+    }
+
+    #[test]
+    fn renumber_module_labels_works() {
+        let mut code = beam_asm! {
+            label 3;
+            func_info foo bar 0;
+            label 4;
+            return;
+            label 7;
+            func_info foo baz 0;
+            label 8;
+            return;
+        };
+        let entry = |label| FunctionEntry {
+            function: Atom { value: 2 },
+            arity: 0,
+            label: Label { value: label },
+        };
+        let mut tables = FunctionTables {
+            exports: vec![entry(4)],
+            locals: vec![entry(8)],
+            lambdas: vec![LambdaEntry {
+                function: Atom { value: 3 },
+                arity: 0,
+                label: Label { value: 8 },
+                index: 0,
+                num_free: 0,
+                old_uniq: 0,
+            }],
+        };
+        let map = renumber_module_labels(&mut code, &mut tables).unwrap();
+        assert_eq!(map.label_count(), 5);
+        assert_eq!(
+            tables.labels().map(|x| x.value).collect::<Vec<_>>(),
+            [2, 4, 4]
+        );
+
+        // A dangling table entry is reported without changing anything.
+        tables.locals.push(entry(9));
+        assert_eq!(
+            renumber_module_labels(&mut code, &mut tables),
+            Err(Error::UndefinedLabel { label: 9 })
+        );
+        assert_eq!(
+            tables.labels().map(|x| x.value).collect::<Vec<_>>(),
+            [2, 4, 9, 4]
+        );
+    }
+
+    #[test]
+    fn eliminate_dead_code_works() {
+        let mut code = beam_asm! {
+            label 1;
+            func_info foo bar 0;
+            label 2;
+            call_only 0 {f,5};
+            move nil {x,0};
+            label 3;
+            func_info foo baz 0;
+            label 4;
+            return;
+            label 5;
+            func_info foo qux 0;
+            label 6;
+            is_nil {f,5} {x,0};
+            return;
+            label 7;
+            return;
+            label 8;
+            func_info foo init 0;
+            label 9;
+            on_load;
+            return;
+            int_code_end;
+        };
+        let entry = |name, label| FunctionEntry {
+            function: Atom { value: name },
+            arity: 0,
+            label: Label { value: label },
+        };
+        let mut tables = FunctionTables {
+            exports: vec![entry(2, 2)],
+            locals: vec![entry(3, 4), entry(4, 6), entry(5, 9)],
+            lambdas: vec![LambdaEntry {
+                function: Atom { value: 4 },
+                arity: 0,
+                label: Label { value: 6 },
+                index: 0,
+                num_free: 0,
+                old_uniq: 0,
+            }],
+        };
+        let map = eliminate_dead_code(&mut code, &mut tables).unwrap();
+        assert_eq!(map.get(Label { value: 4 }), None);
+        assert_eq!(map.get(Label { value: 7 }), None);
+        assert_eq!(map.get(Label { value: 9 }), Some(Label { value: 6 }));
+        assert_eq!(map.label_count(), 7);
+        assert_eq!(
+            code.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            [
+                "label 1",
+                "func_info atom(1), atom(2), 0",
+                "label 2",
+                "call_only 0, f(3)",
+                "label 3",
+                "func_info atom(1), atom(4), 0",
+                "label 4",
+                "is_nil f(3), x(0)",
+                "return",
+                "label 5",
+                "func_info atom(1), atom(5), 0",
+                "label 6",
+                "on_load",
+                "return",
+                "int_code_end",
+            ]
+        );
+
+        // The tables still refer to the entries of the functions with the same names.
+        assert_eq!(tables.locals.len(), 2);
+        let graph = CallGraph::new(&code, &tables.lambdas);
+        let lambdas = tables
+            .lambdas
+            .iter()
+            .map(|x| (x.function, x.arity, x.label));
+        for (name, arity, label) in tables
+            .exports
+            .iter()
+            .chain(&tables.locals)
+            .map(|x| (x.function, x.arity, x.label))
+            .chain(lambdas)
+        {
+            let function = &graph.functions()[graph.function_of_label(label).unwrap()];
+            assert_eq!(function.entry, Some(label));
+            assert_eq!((function.name, function.arity), (name, arity));
+        }
+    }
+}
@@ -0,0 +1,1470 @@
+//! Type and value range inference.
+//!
+//! [`TypeAnalysis`] is a forward dataflow analysis over the [`Cfg`] of a function that infers the
+//! possible values of each register before each instruction. A [`Type`] is a union of kinds of
+//! terms, refined by the set of possible atoms, the range of integers, the arity of tuples and
+//! the keys known to be present in maps.
+//!
+//! Types are narrowed along the edges of type tests (`is_*`, `test_arity`, `is_tagged_tuple`,
+//! `has_map_fields`, `get_map_elements`), comparisons with constants (`is_eq_exact`,
+//! `is_ne_exact`, `is_lt`, `is_ge`) and `select_val`/`select_tuple_arity`, and by instructions
+//! that only accept certain values (e.g., `get_tuple_element`). Values built by the function
+//! (e.g., by `move` or `put_tuple2`) are also tracked. Everything else, such as the arguments,
+//! the results of calls and BIFs, literals and the registers at exception handlers, is
+//! considered to be of any type.
+//!
+//! The annotations of typed registers refer to the `Type` chunk written by the compiler. The
+//! chunk can be decoded by [`decode_type_chunk()`] and compared with the inferred types by
+//! [`TypeAnalysis::check_annotations()`].
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::defuse::RegisterId;
+//! use beamcode::types::TypeAnalysis;
+//!
+//! let code = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 1;
+//!     label 2;
+//!     select_val {x,0} {f,1} [ok, {f,3}, error, {f,3}];
+//!     label 3;
+//!     return;
+//! };
+//! let analysis = TypeAnalysis::new(&code);
+//! let state = analysis.state(4).unwrap();
+//! assert_eq!(state.get(RegisterId::X(0)).to_string(), "atom(3, 4)");
+//! ```
+//!
+//! # References
+//!
+//! - [erlang/otp/erts/emulator/beam/beam_types.h](https://github.com/erlang/otp/blob/master/erts/emulator/beam/beam_types.h)
+use crate::cfg::{Cfg, EdgeKind};
+use crate::defuse::RegisterId;
+use crate::flow::Successors;
+use crate::instruction::Instruction;
+use crate::operand::Operand;
+use crate::term::{self, Label, List, Register, Term, TypedRegister};
+use crate::DecodeError;
+use byteorder::{BigEndian, ReadBytesExt as _};
+use num::{BigInt, ToPrimitive};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// Flags of the `Type` chunk entries.
+const HAS_LOWER_BOUND: u16 = 1 << 13;
+const HAS_UPPER_BOUND: u16 = 1 << 14;
+const HAS_UNIT: u16 = 1 << 15;
+
+/// A set of kinds of terms.
+///
+/// The bits are the same as those of the `Type` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Kinds(u16);
+
+impl Kinds {
+    pub const NONE: Self = Self(0);
+    pub const ATOM: Self = Self(1 << 0);
+    pub const BITSTRING: Self = Self(1 << 1);
+    pub const MATCH_CONTEXT: Self = Self(1 << 2);
+    pub const CONS: Self = Self(1 << 3);
+    pub const FLOAT: Self = Self(1 << 4);
+    pub const FUN: Self = Self(1 << 5);
+    pub const INTEGER: Self = Self(1 << 6);
+    pub const MAP: Self = Self(1 << 7);
+    pub const NIL: Self = Self(1 << 8);
+    pub const PID: Self = Self(1 << 9);
+    pub const PORT: Self = Self(1 << 10);
+    pub const REFERENCE: Self = Self(1 << 11);
+    pub const TUPLE: Self = Self(1 << 12);
+    pub const ANY: Self = Self((1 << 13) - 1);
+
+    const NAMES: [&'static str; 13] = [
+        "atom",
+        "bitstring",
+        "match_context",
+        "cons",
+        "float",
+        "fun",
+        "integer",
+        "map",
+        "nil",
+        "pid",
+        "port",
+        "reference",
+        "tuple",
+    ];
+
+    /// Makes a [`Kinds`] instance from bits, ignoring unknown ones.
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits & Self::ANY.0)
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if every kind in `other` is in this set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if this set and `other` have a kind in common.
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+/// A key of a map that is known at compile time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MapKey {
+    Atom(term::Atom),
+    Integer(BigInt),
+    Literal(term::Literal),
+}
+
+impl MapKey {
+    fn from_term(term: &Term) -> Option<Self> {
+        match term {
+            Term::Atom(x) => Some(Self::Atom(*x)),
+            Term::Integer(x) => Some(Self::Integer(x.clone())),
+            Term::Literal(x) => Some(Self::Literal(*x)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MapKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Atom(x) => write!(f, "{x}"),
+            Self::Integer(x) => write!(f, "{x}"),
+            Self::Literal(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// A set of terms.
+///
+/// The refinements (e.g., [`Type::atoms()`]) only describe the terms of their kind, so the
+/// integer range of `integer(0..9) | atom` is still `0..9`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Type {
+    kinds: Kinds,
+    atoms: Option<BTreeSet<usize>>,
+    min: Option<i64>,
+    max: Option<i64>,
+    arity: Option<usize>,
+    map_keys: BTreeSet<MapKey>,
+}
+
+impl Type {
+    /// Makes a [`Type`] instance that contains every term.
+    pub fn any() -> Self {
+        Self::from_kinds(Kinds::ANY)
+    }
+
+    /// Makes a [`Type`] instance that contains no term.
+    pub fn none() -> Self {
+        Self::from_kinds(Kinds::NONE)
+    }
+
+    /// Makes a [`Type`] instance that contains every term of the given kinds.
+    pub fn from_kinds(kinds: Kinds) -> Self {
+        Self {
+            kinds,
+            atoms: None,
+            min: None,
+            max: None,
+            arity: None,
+            map_keys: BTreeSet::new(),
+        }
+    }
+
+    /// Makes a [`Type`] instance that contains a single atom.
+    ///
+    /// The atom with the index 0 is `nil` (`[]`), which is not an atom in Erlang.
+    pub fn atom(atom: term::Atom) -> Self {
+        if atom.value == 0 {
+            return Self::from_kinds(Kinds::NIL);
+        }
+        Self {
+            atoms: Some(BTreeSet::from([atom.value])),
+            ..Self::from_kinds(Kinds::ATOM)
+        }
+    }
+
+    /// Makes a [`Type`] instance that contains the integers in a range (`None` means unbounded).
+    pub fn integer(min: Option<i64>, max: Option<i64>) -> Self {
+        Self {
+            min,
+            max,
+            ..Self::from_kinds(Kinds::INTEGER)
+        }
+        .normalize()
+    }
+
+    /// Makes a [`Type`] instance that contains the tuples of the arity (or any tuple).
+    pub fn tuple(arity: Option<usize>) -> Self {
+        Self {
+            arity,
+            ..Self::from_kinds(Kinds::TUPLE)
+        }
+    }
+
+    /// Makes a [`Type`] instance that contains the maps with the keys.
+    pub fn map(keys: impl IntoIterator<Item = MapKey>) -> Self {
+        Self {
+            map_keys: keys.into_iter().collect(),
+            ..Self::from_kinds(Kinds::MAP)
+        }
+    }
+
+    pub fn kinds(&self) -> Kinds {
+        self.kinds
+    }
+
+    /// Returns the indices of the possible atoms, or `None` if any atom is possible.
+    pub fn atoms(&self) -> Option<&BTreeSet<usize>> {
+        self.atoms.as_ref()
+    }
+
+    /// Returns the bounds of the possible integers (`None` means unbounded).
+    pub fn integer_range(&self) -> (Option<i64>, Option<i64>) {
+        (self.min, self.max)
+    }
+
+    /// Returns the arity of the possible tuples, if known.
+    pub fn arity(&self) -> Option<usize> {
+        self.arity
+    }
+
+    /// Returns the keys that the possible maps have.
+    pub fn map_keys(&self) -> &BTreeSet<MapKey> {
+        &self.map_keys
+    }
+
+    pub fn is_any(&self) -> bool {
+        *self == Self::any()
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Returns the smallest type that contains the terms of both types.
+    pub fn join(&self, other: &Self) -> Self {
+        let has = |ty: &Self, kind| ty.kinds.contains(kind);
+        let pick = |kind| match (has(self, kind), has(other, kind)) {
+            (true, true) => None,
+            (true, false) => Some(self),
+            (false, _) => Some(other),
+        };
+        let mut ty = Self::from_kinds(self.kinds.union(other.kinds));
+        ty.atoms = match pick(Kinds::ATOM) {
+            Some(x) => x.atoms.clone(),
+            None => match (&self.atoms, &other.atoms) {
+                (Some(a), Some(b)) => Some(a.union(b).copied().collect()),
+                _ => None,
+            },
+        };
+        (ty.min, ty.max) = match pick(Kinds::INTEGER) {
+            Some(x) => (x.min, x.max),
+            None => (
+                self.min.zip(other.min).map(|(a, b)| a.min(b)),
+                self.max.zip(other.max).map(|(a, b)| a.max(b)),
+            ),
+        };
+        ty.arity = match pick(Kinds::TUPLE) {
+            Some(x) => x.arity,
+            None => self.arity.filter(|_| self.arity == other.arity),
+        };
+        ty.map_keys = match pick(Kinds::MAP) {
+            Some(x) => x.map_keys.clone(),
+            None => self
+                .map_keys
+                .intersection(&other.map_keys)
+                .cloned()
+                .collect(),
+        };
+        ty.normalize()
+    }
+
+    /// Returns the terms in both types.
+    pub fn meet(&self, other: &Self) -> Self {
+        let mut ty = Self::from_kinds(self.kinds.intersection(other.kinds));
+        ty.atoms = match (&self.atoms, &other.atoms) {
+            (Some(a), Some(b)) => Some(a.intersection(b).copied().collect()),
+            (Some(x), None) | (None, Some(x)) => Some(x.clone()),
+            (None, None) => None,
+        };
+        ty.min = self.min.max(other.min);
+        ty.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        ty.arity = match (self.arity, other.arity) {
+            (Some(a), Some(b)) if a != b => {
+                ty.kinds = ty.kinds.difference(Kinds::TUPLE);
+                None
+            }
+            (a, b) => a.or(b),
+        };
+        ty.map_keys = self.map_keys.union(&other.map_keys).cloned().collect();
+        ty.normalize()
+    }
+
+    /// Returns `true` if every term of this type is in `other`.
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        let has = |kind| self.kinds.contains(kind);
+        let atoms = match (&self.atoms, &other.atoms) {
+            (_, None) => true,
+            (Some(a), Some(b)) => a.is_subset(b),
+            (None, Some(_)) => false,
+        };
+        let min = match (self.min, other.min) {
+            (_, None) => true,
+            (Some(a), Some(b)) => a >= b,
+            (None, Some(_)) => false,
+        };
+        let max = match (self.max, other.max) {
+            (_, None) => true,
+            (Some(a), Some(b)) => a <= b,
+            (None, Some(_)) => false,
+        };
+        other.kinds.contains(self.kinds)
+            && (!has(Kinds::ATOM) || atoms)
+            && (!has(Kinds::INTEGER) || (min && max))
+            && (!has(Kinds::TUPLE) || other.arity.is_none() || self.arity == other.arity)
+            && (!has(Kinds::MAP) || other.map_keys.is_subset(&self.map_keys))
+    }
+
+    /// Removes the kinds.
+    pub fn without(&self, kinds: Kinds) -> Self {
+        Self {
+            kinds: self.kinds.difference(kinds),
+            ..self.clone()
+        }
+        .normalize()
+    }
+
+    // Removes `value` if it is a single term (e.g., the integer 3 or the atom `ok`).
+    fn exclude(&self, value: &Self) -> Self {
+        let mut ty = self.clone();
+        if value.kinds == Kinds::NIL {
+            ty.kinds = ty.kinds.difference(Kinds::NIL);
+        } else if value.kinds == Kinds::ATOM {
+            if let (Some(atoms), Some(value)) = (&mut ty.atoms, &value.atoms) {
+                if value.len() == 1 {
+                    atoms.retain(|x| !value.contains(x));
+                }
+            }
+        } else if value.kinds == Kinds::INTEGER && value.min.is_some() && value.min == value.max {
+            if ty.min == value.min {
+                ty.min = ty.min.and_then(|x| x.checked_add(1));
+            }
+            if ty.max == value.max {
+                ty.max = ty.max.and_then(|x| x.checked_sub(1));
+            }
+        }
+        ty.normalize()
+    }
+
+    // Drops the refinements that the `Type` chunk cannot express.
+    fn to_annotation(&self) -> Self {
+        Self {
+            min: self.min,
+            max: self.max,
+            ..Self::from_kinds(self.kinds)
+        }
+    }
+
+    // Resets the refinements of absent kinds and removes the kinds left without terms.
+    fn normalize(mut self) -> Self {
+        if self.atoms.as_ref().is_some_and(|x| x.is_empty()) {
+            self.kinds = self.kinds.difference(Kinds::ATOM);
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if min > max {
+                self.kinds = self.kinds.difference(Kinds::INTEGER);
+            }
+        }
+        if !self.kinds.contains(Kinds::ATOM) {
+            self.atoms = None;
+        }
+        if !self.kinds.contains(Kinds::INTEGER) {
+            self.min = None;
+            self.max = None;
+        }
+        if !self.kinds.contains(Kinds::TUPLE) {
+            self.arity = None;
+        }
+        if !self.kinds.contains(Kinds::MAP) {
+            self.map_keys.clear();
+        }
+        self
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_any() {
+            return write!(f, "any");
+        }
+        if self.is_none() {
+            return write!(f, "none");
+        }
+        let mut first = true;
+        for (i, name) in Kinds::NAMES.iter().enumerate() {
+            let kind = Kinds(1 << i);
+            if !self.kinds.contains(kind) {
+                continue;
+            }
+            if !first {
+                write!(f, " | ")?;
+            }
+            first = false;
+            write!(f, "{name}")?;
+            match kind {
+                Kinds::ATOM => {
+                    if let Some(atoms) = &self.atoms {
+                        let atoms = atoms.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                        write!(f, "({})", atoms.join(", "))?;
+                    }
+                }
+                Kinds::INTEGER if self.min.is_some() || self.max.is_some() => {
+                    write!(f, "(")?;
+                    if let Some(min) = self.min {
+                        write!(f, "{min}")?;
+                    }
+                    write!(f, "..")?;
+                    if let Some(max) = self.max {
+                        write!(f, "{max}")?;
+                    }
+                    write!(f, ")")?;
+                }
+                Kinds::TUPLE => {
+                    if let Some(arity) = self.arity {
+                        write!(f, "/{arity}")?;
+                    }
+                }
+                Kinds::MAP if !self.map_keys.is_empty() => {
+                    let keys = self.map_keys.iter().map(|x| x.to_string());
+                    write!(f, "({})", keys.collect::<Vec<_>>().join(", "))?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The types of the registers before an instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeState {
+    // Registers of any type are omitted.
+    registers: BTreeMap<RegisterId, Type>,
+}
+
+impl TypeState {
+    /// Returns the type of a register.
+    pub fn get(&self, register: RegisterId) -> Type {
+        match register {
+            RegisterId::Fr(_) => Type::from_kinds(Kinds::FLOAT),
+            _ => self
+                .registers
+                .get(&register)
+                .cloned()
+                .unwrap_or_else(Type::any),
+        }
+    }
+
+    /// Returns the registers whose types are narrower than any in ascending order.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (RegisterId, &Type)> {
+        self.registers.iter().map(|(register, ty)| (*register, ty))
+    }
+
+    fn join(&self, other: &Self) -> Self {
+        let mut registers = BTreeMap::new();
+        for (register, ty) in &self.registers {
+            if let Some(other) = other.registers.get(register) {
+                let ty = ty.join(other);
+                if !ty.is_any() {
+                    registers.insert(*register, ty);
+                }
+            }
+        }
+        Self { registers }
+    }
+
+    fn set(&mut self, register: RegisterId, ty: Type) {
+        if ty.is_any() || matches!(register, RegisterId::Fr(_)) {
+            self.registers.remove(&register);
+        } else {
+            self.registers.insert(register, ty);
+        }
+    }
+
+    // Narrows the type of a register and returns `false` (leaving it unchanged) if no term is
+    // left.
+    fn refine(&mut self, register: RegisterId, ty: &Type) -> bool {
+        self.replace(register, self.get(register).meet(ty))
+    }
+
+    fn replace(&mut self, register: RegisterId, ty: Type) -> bool {
+        if ty.is_none() {
+            return false;
+        }
+        self.set(register, ty);
+        true
+    }
+
+    fn refine_term(&mut self, term: &Term, ty: &Type) -> bool {
+        match RegisterId::from_term(term) {
+            Some(register) => self.refine(register, ty),
+            None => !self.term_type(term).meet(ty).is_none(),
+        }
+    }
+
+    fn term_type(&self, term: &Term) -> Type {
+        match term {
+            Term::Atom(x) => Type::atom(*x),
+            Term::Integer(x) => {
+                let value = x.to_i64();
+                Type::integer(value, value)
+            }
+            Term::Character(x) => {
+                let value = Some(i64::from(u32::from(*x)));
+                Type::integer(value, value)
+            }
+            _ => match RegisterId::from_term(term) {
+                Some(register) => self.get(register),
+                None => Type::any(),
+            },
+        }
+    }
+
+    /// Updates the state by executing an instruction (on the success path if it is a test).
+    fn execute(&mut self, instruction: &Instruction) {
+        use Instruction as I;
+
+        // The types of the written registers are computed before any register changes.
+        let nil = || Type::from_kinds(Kinds::NIL);
+        let results = match instruction {
+            I::Move(x) => vec![(RegisterId::from_register(&x.dst), self.term_type(&x.src))],
+            I::Swap(x) => match (
+                RegisterId::from_term(&x.arg1),
+                RegisterId::from_term(&x.arg2),
+            ) {
+                (Some(a), Some(b)) => vec![(a, self.get(b)), (b, self.get(a))],
+                _ => Vec::new(),
+            },
+            I::PutList(x) => vec![(
+                RegisterId::from_register(&x.destination),
+                Type::from_kinds(Kinds::CONS),
+            )],
+            I::PutTuple2(x) => vec![(
+                RegisterId::from_register(&x.destination),
+                Type::tuple(Some(x.elements.items.len())),
+            )],
+            I::PutMapAssoc(x) => self.put_map(&x.arg2, &x.arg3, &x.arg5),
+            I::PutMapExact(x) => self.put_map(&x.arg2, &x.arg3, &x.arg5),
+            I::Init(x) => RegisterId::from_term(&x.arg1)
+                .map(|r| (r, nil()))
+                .into_iter()
+                .collect(),
+            I::InitYregs(x) => x
+                .registers
+                .iter()
+                .map(|r| (RegisterId::Y(r.value), nil()))
+                .collect(),
+            I::MakeFun2(_) => vec![(RegisterId::X(0), Type::from_kinds(Kinds::FUN))],
+            I::MakeFun3(x) => RegisterId::from_term(&x.arg2)
+                .map(|r| (r, Type::from_kinds(Kinds::FUN)))
+                .into_iter()
+                .collect(),
+            I::BsStartMatch3(x) => vec![(
+                RegisterId::from_register(&x.destination),
+                Type::from_kinds(Kinds::MATCH_CONTEXT),
+            )],
+            I::BsStartMatch4(x) => RegisterId::from_term(&x.arg4)
+                .map(|r| (r, Type::from_kinds(Kinds::MATCH_CONTEXT)))
+                .into_iter()
+                .collect(),
+            I::BsGetTail(x) => vec![(
+                RegisterId::from_register(&x.destination),
+                Type::from_kinds(Kinds::BITSTRING),
+            )],
+
+            // The source operands of these instructions must be of the types.
+            I::GetTupleElement(x) => {
+                self.refine(RegisterId::from_register(&x.source), &Type::tuple(None));
+                Vec::new()
+            }
+            I::GetList(x) => {
+                self.refine_term(&x.source, &Type::from_kinds(Kinds::CONS));
+                Vec::new()
+            }
+            I::GetHd(x) => {
+                self.refine_term(&x.arg1, &Type::from_kinds(Kinds::CONS));
+                Vec::new()
+            }
+            I::GetTl(x) => {
+                self.refine_term(&x.arg1, &Type::from_kinds(Kinds::CONS));
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+
+        if let Some(n) = instruction.preserved_x_registers() {
+            self.registers
+                .retain(|register, _| !matches!(register, RegisterId::X(i) if *i >= n));
+        }
+        for register in instruction.defs() {
+            self.registers.remove(&register);
+        }
+        match instruction {
+            I::Allocate(_)
+            | I::AllocateHeap(_)
+            | I::AllocateZero(_)
+            | I::AllocateHeapZero(_)
+            | I::Deallocate(_)
+            | I::CallLast(_)
+            | I::CallExtLast(_)
+            | I::ApplyLast(_) => {
+                self.registers
+                    .retain(|register, _| !matches!(register, RegisterId::Y(_)));
+            }
+            I::Trim(x) => {
+                let n = match x.arg1 {
+                    Term::Usize(n) => n,
+                    _ => 0,
+                };
+                let registers = std::mem::take(&mut self.registers);
+                for (register, ty) in registers {
+                    match register {
+                        RegisterId::Y(i) if i < n => {}
+                        RegisterId::Y(i) => self.set(RegisterId::Y(i - n), ty),
+                        _ => self.set(register, ty),
+                    }
+                }
+            }
+            _ => {}
+        }
+        for (register, ty) in results {
+            self.set(register, ty);
+        }
+    }
+
+    fn put_map(&self, source: &Term, destination: &Term, list: &Term) -> Vec<(RegisterId, Type)> {
+        let Some(destination) = RegisterId::from_term(destination) else {
+            return Vec::new();
+        };
+        let mut keys = self.term_type(source).map_keys;
+        keys.extend(map_type(list, 2).map_keys);
+        vec![(destination, Type::map(keys))]
+    }
+
+    // Narrows the types by the outcome of a test and returns `false` if the outcome is
+    // impossible.
+    fn test(&mut self, instruction: &Instruction, passed: bool) -> bool {
+        use Instruction as I;
+
+        if let Some((term, kinds, exact)) = kind_test(instruction) {
+            if passed {
+                return self.refine_term(term, &Type::from_kinds(kinds));
+            }
+            return match RegisterId::from_term(term) {
+                Some(register) if exact => {
+                    self.replace(register, self.get(register).without(kinds))
+                }
+                _ => true,
+            };
+        }
+        match instruction {
+            I::IsEqExact(x) => self.test_equal(&x.arg1, &x.arg2, passed),
+            I::IsNeExact(x) => self.test_equal(&x.arg1, &x.arg2, !passed),
+            I::IsLt(x) => self.test_less(&x.arg2, &x.arg3, passed),
+            I::IsGe(x) => self.test_less(&x.arg2, &x.arg3, !passed),
+            I::TestArity(x) if passed => self.refine_term(&x.arg1, &Type::tuple(Some(x.arity))),
+            I::TestArity(x) => {
+                let ty = self.term_type(&x.arg1);
+                ty.arity != Some(x.arity) && self.refine_term(&x.arg1, &Type::tuple(None))
+            }
+            I::IsTaggedTuple(x) if passed => {
+                let register = RegisterId::from_register(&x.register);
+                self.refine(register, &Type::tuple(Some(x.arity)))
+            }
+            I::HasMapFields(x) if passed => self.refine_term(&x.arg2, &map_type(&x.arg3, 1)),
+            I::GetMapElement(x) if passed => self.refine_term(&x.arg2, &map_type(&x.arg3, 2)),
+            _ => true,
+        }
+    }
+
+    fn test_equal(&mut self, a: &Term, b: &Term, equal: bool) -> bool {
+        let (ta, tb) = (self.term_type(a), self.term_type(b));
+        if equal {
+            let ty = ta.meet(&tb);
+            return self.refine_term(a, &ty) && self.refine_term(b, &ty);
+        }
+        let mut feasible = true;
+        if let Some(register) = RegisterId::from_term(a) {
+            feasible &= self.replace(register, ta.exclude(&tb));
+        }
+        if let Some(register) = RegisterId::from_term(b) {
+            feasible &= self.replace(register, tb.exclude(&ta));
+        }
+        feasible
+    }
+
+    // Narrows the integer ranges by `a < b` (or `a >= b` if `less` is `false`).
+    fn test_less(&mut self, a: &Term, b: &Term, less: bool) -> bool {
+        let (ta, tb) = (self.term_type(a), self.term_type(b));
+        let bounded = |min: Option<i64>, max: Option<i64>| Type {
+            min,
+            max,
+            ..Type::any()
+        };
+        let integer = |ty: &Type| ty.kinds == Kinds::INTEGER;
+        let mut feasible = true;
+        if less {
+            if integer(&tb) {
+                let max = tb.max.and_then(|x| x.checked_sub(1));
+                feasible &= max.is_none() || self.refine_term(a, &bounded(None, max));
+            }
+            if integer(&ta) {
+                let min = ta.min.and_then(|x| x.checked_add(1));
+                feasible &= min.is_none() || self.refine_term(b, &bounded(min, None));
+            }
+        } else {
+            if integer(&tb) && tb.min.is_some() {
+                feasible &= self.refine_term(a, &bounded(tb.min, None));
+            }
+            if integer(&ta) && ta.max.is_some() {
+                feasible &= self.refine_term(b, &bounded(None, ta.max));
+            }
+        }
+        feasible
+    }
+
+    // Returns the state at the destinations of a `select_*` instruction with the given labels,
+    // or `None` if they are unreachable.
+    fn select(&self, instruction: &Instruction, labels: &[Label]) -> Option<Self> {
+        let (arg, tuple) = match instruction {
+            Instruction::SelectVal(x) => (&x.arg, false),
+            Instruction::SelectTupleArity(x) => (&x.arg1, true),
+            _ => return Some(self.clone()),
+        };
+        let Successors::Select { fail, destinations } = instruction.successors() else {
+            return Some(self.clone());
+        };
+        let value_type = |value: &Term| match value {
+            Term::Usize(arity) if tuple => Type::tuple(Some(*arity)),
+            _ => self.term_type(value),
+        };
+
+        let mut states = Vec::new();
+        for (value, label) in &destinations {
+            let mut state = self.clone();
+            if labels.contains(label) && state.refine_term(arg, &value_type(value)) {
+                states.push(state);
+            }
+        }
+        if labels.contains(&fail) {
+            let mut state = self.clone();
+            let feasible = match RegisterId::from_term(arg) {
+                Some(register) if tuple => state.refine(register, &Type::tuple(None)),
+                Some(register) => {
+                    let ty = destinations
+                        .iter()
+                        .fold(state.get(register), |ty, (value, _)| {
+                            ty.exclude(&value_type(value))
+                        });
+                    state.replace(register, ty)
+                }
+                None => true,
+            };
+            if feasible {
+                states.push(state);
+            }
+        }
+        states.into_iter().reduce(|a, b| a.join(&b))
+    }
+
+    // Returns the state after taking an edge from the last instruction of a block, or `None` if
+    // the edge cannot be taken.
+    fn successor(
+        &self,
+        instruction: &Instruction,
+        kind: EdgeKind,
+        labels: &[Label],
+    ) -> Option<Self> {
+        let mut state = self.clone();
+        match kind {
+            EdgeKind::Exception => Some(Self::default()),
+            EdgeKind::Select => self.select(instruction, labels),
+            EdgeKind::Branch => state.test(instruction, false).then_some(state),
+            EdgeKind::FallThrough | EdgeKind::Jump => {
+                if !state.test(instruction, true) {
+                    return None;
+                }
+                state.execute(instruction);
+                Some(state)
+            }
+        }
+    }
+}
+
+// Returns the operand, the kinds and whether failing means that the operand has none of them.
+fn kind_test(instruction: &Instruction) -> Option<(&Term, Kinds, bool)> {
+    use Instruction as I;
+
+    let test = match instruction {
+        I::IsInteger(x) => (&x.arg1, Kinds::INTEGER, true),
+        I::IsFloat(x) => (&x.arg1, Kinds::FLOAT, true),
+        I::IsNumber(x) => (&x.arg1, Kinds::INTEGER.union(Kinds::FLOAT), true),
+        I::IsAtom(x) => (&x.arg1, Kinds::ATOM, true),
+        I::IsBoolean(x) => (&x.arg2, Kinds::ATOM, false),
+        I::IsNil(x) => (&x.arg1, Kinds::NIL, true),
+        I::IsList(x) => (&x.arg1, Kinds::NIL.union(Kinds::CONS), true),
+        I::IsNonemptyList(x) => (&x.arg1, Kinds::CONS, true),
+        I::IsTuple(x) => (&x.arg1, Kinds::TUPLE, true),
+        I::IsBinary(x) => (&x.arg1, Kinds::BITSTRING, false),
+        I::IsBitstr(x) => (&x.arg2, Kinds::BITSTRING, true),
+        I::IsMap(x) => (&x.arg2, Kinds::MAP, true),
+        I::IsPid(x) => (&x.arg1, Kinds::PID, true),
+        I::IsReference(x) => (&x.arg1, Kinds::REFERENCE, true),
+        I::IsPort(x) => (&x.arg1, Kinds::PORT, true),
+        I::IsFunction(x) => (&x.arg2, Kinds::FUN, true),
+        I::IsFunction2(x) => (&x.arg2, Kinds::FUN, false),
+        _ => return None,
+    };
+    Some(test)
+}
+
+// Makes the type of the maps with the keys at every `step` elements of the list.
+fn map_type(list: &Term, step: usize) -> Type {
+    match list {
+        Term::List(List { items }) => {
+            Type::map(items.iter().step_by(step).filter_map(MapKey::from_term))
+        }
+        _ => Type::from_kinds(Kinds::MAP),
+    }
+}
+
+/// A difference between a type annotation in the `Type` chunk and the inferred type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    /// The index of the instruction with the annotated register.
+    pub position: usize,
+
+    pub register: RegisterId,
+
+    /// The index of the annotation in the `Type` chunk.
+    pub index: usize,
+
+    pub annotated: Type,
+
+    pub inferred: Type,
+
+    pub kind: DiscrepancyKind,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}: annotated={}, inferred={}",
+            self.position, self.register, self.kind, self.annotated, self.inferred
+        )
+    }
+}
+
+/// The kind of a [`Discrepancy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum DiscrepancyKind {
+    /// No term satisfies both types, so the annotation is likely stale (or the code is
+    /// unreachable).
+    #[error("stale annotation")]
+    Stale,
+
+    /// The inferred type is strictly narrower than the annotation.
+    #[error("imprecise annotation")]
+    Imprecise,
+}
+
+/// The result of the type inference of a function.
+#[derive(Debug, Clone)]
+pub struct TypeAnalysis {
+    states: Vec<Option<TypeState>>,
+}
+
+impl TypeAnalysis {
+    /// Analyzes a function (see [`Cfg::new()`]).
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let cfg = Cfg::new(instructions);
+        let mut states = vec![None; instructions.len()];
+        if cfg.blocks().is_empty() {
+            return Self { states };
+        }
+
+        let mut block_states = vec![None; cfg.blocks().len()];
+        block_states[cfg.entry().0] = Some(TypeState::default());
+        let mut worklist = vec![cfg.entry()];
+        while let Some(id) = worklist.pop() {
+            let Some(mut state) = block_states[id.0].clone() else {
+                continue;
+            };
+            let block = cfg.block(id);
+            let Some(last) = block.range.clone().last() else {
+                continue;
+            };
+            for instruction in &instructions[block.range.start..last] {
+                state.execute(instruction);
+            }
+            for edge in &block.successors {
+                let labels = &cfg.block(edge.target).labels;
+                let Some(output) = state.successor(&instructions[last], edge.kind, labels) else {
+                    continue;
+                };
+                let new = match &block_states[edge.target.0] {
+                    None => output,
+                    Some(old) => old.join(&output),
+                };
+                if block_states[edge.target.0].as_ref() != Some(&new) {
+                    block_states[edge.target.0] = Some(new);
+                    worklist.push(edge.target);
+                }
+            }
+        }
+
+        for (block, state) in cfg.blocks().iter().zip(block_states) {
+            let Some(mut state) = state else {
+                continue;
+            };
+            for position in block.range.clone() {
+                states[position] = Some(state.clone());
+                state.execute(&instructions[position]);
+            }
+        }
+        Self { states }
+    }
+
+    /// Returns the types before the instruction, or `None` if it is unreachable.
+    pub fn state(&self, position: usize) -> Option<&TypeState> {
+        self.states.get(position).and_then(|x| x.as_ref())
+    }
+
+    /// Compares the annotations of the typed registers in the function with the inferred types.
+    ///
+    /// `types` is the decoded `Type` chunk. Only the kinds and integer ranges are compared since
+    /// the chunk has no other refinements, and annotations narrower than the inferred types are
+    /// not reported since the compiler may know more (e.g., the return types of local calls).
+    /// Discrepancies are returned in instruction order.
+    pub fn check_annotations(
+        &self,
+        instructions: &[Instruction],
+        types: &[Type],
+    ) -> Vec<Discrepancy> {
+        let mut discrepancies = Vec::new();
+        for (position, instruction) in instructions.iter().enumerate() {
+            let Some(state) = self.state(position) else {
+                continue;
+            };
+            let mut registers = Vec::new();
+            for operand in instruction.operands() {
+                match operand {
+                    Operand::Term(Term::TypedRegister(x))
+                    | Operand::Register(Register::Typed(x)) => {
+                        registers.push(*x);
+                    }
+                    Operand::Term(Term::List(list)) | Operand::List(list) => {
+                        registers.extend(list.items.iter().filter_map(|x| match x {
+                            Term::TypedRegister(x) => Some(*x),
+                            _ => None,
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+            for typed in registers {
+                let (register, index) = match typed {
+                    TypedRegister::X { register, ty } => (RegisterId::X(register.value), ty),
+                    TypedRegister::Y { register, ty } => (RegisterId::Y(register.value), ty),
+                };
+                let Some(annotated) = types.get(index) else {
+                    continue;
+                };
+                let inferred = state.get(register);
+                let kind = if inferred.meet(annotated).is_none() {
+                    DiscrepancyKind::Stale
+                } else {
+                    let inferred = inferred.to_annotation();
+                    if inferred != *annotated && inferred.is_subset_of(annotated) {
+                        DiscrepancyKind::Imprecise
+                    } else {
+                        continue;
+                    }
+                };
+                discrepancies.push(Discrepancy {
+                    position,
+                    register,
+                    index,
+                    annotated: annotated.clone(),
+                    inferred,
+                    kind,
+                });
+            }
+        }
+        discrepancies
+    }
+}
+
+/// Decodes the `Type` chunk (versions 1 and 2).
+///
+/// The unit of bitstrings is ignored.
+pub fn decode_type_chunk(bytes: &[u8]) -> Result<Vec<Type>, DecodeError> {
+    let mut reader = bytes;
+    let version = reader.read_u32::<BigEndian>()?;
+    if !(1..=2).contains(&version) {
+        return Err(DecodeError::UnknownTypeChunkVersion { version });
+    }
+    let count = reader.read_u32::<BigEndian>()?;
+    let mut types = Vec::new();
+    for _ in 0..count {
+        let bits = reader.read_u16::<BigEndian>()?;
+        let mut ty = Type::from_kinds(Kinds::from_bits(bits));
+        if bits & HAS_LOWER_BOUND != 0 {
+            ty.min = Some(reader.read_i64::<BigEndian>()?);
+        }
+        if bits & HAS_UPPER_BOUND != 0 {
+            ty.max = Some(reader.read_i64::<BigEndian>()?);
+        }
+        if bits & HAS_UNIT != 0 {
+            reader.read_u8()?;
+        }
+        types.push(ty.normalize());
+    }
+    Ok(types)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+    use crate::instruction::{IsGe, IsLt, PutMapAssoc, PutMapExact, SelectVal, Trim};
+    use crate::term::{Atom, Literal, XRegister};
+
+    fn x(value: usize) -> Term {
+        Term::XRegister(XRegister { value, ty: None })
+    }
+
+    fn a(value: usize) -> Term {
+        Term::Atom(Atom { value })
+    }
+
+    fn i(value: i64) -> Term {
+        Term::Integer(BigInt::from(value))
+    }
+
+    fn l(value: usize) -> Term {
+        Term::Label(Label { value })
+    }
+
+    fn list(items: Vec<Term>) -> Term {
+        Term::List(List { items })
+    }
+
+    fn atoms(atoms: &[usize]) -> Type {
+        Type {
+            atoms: Some(atoms.iter().copied().collect()),
+            ..Type::from_kinds(Kinds::ATOM)
+        }
+    }
+
+    fn int(min: i64, max: i64) -> Type {
+        Type::integer(Some(min), Some(max))
+    }
+
+    fn keys(atoms: &[usize]) -> Type {
+        Type::map(atoms.iter().map(|&value| MapKey::Atom(Atom { value })))
+    }
+
+    fn state(registers: Vec<(RegisterId, Type)>) -> TypeState {
+        let mut state = TypeState::default();
+        for (register, ty) in registers {
+            state.set(register, ty);
+        }
+        state
+    }
+
+    fn show(state: &TypeState) -> Vec<String> {
+        state.iter().map(|(r, ty)| format!("{r}: {ty}")).collect()
+    }
+
+    #[test]
+    fn type_analysis_works() {
+        let mut code = beam_asm! {
+            label 1;
+            func_info foo bar 2;
+            label 2;
+            is_integer {f,1} {x,0};
+            is_lt {f,1} {x,0} {integer,10};
+            is_ge {f,1} {x,0} {integer,0};
+            test_arity {f,3} {x,1} 2;
+            get_tuple_element {x,1} 0 {x,2};
+            select_val {x,2} {f,3} [ok, {f,4}, error, {f,4}];
+            label 3;
+            move nil {x,0};
+            return;
+            label 4;
+            is_eq_exact {f,3} {x,2} ok;
+            move {x,2} {x,0};
+            return;
+        };
+        let x = |value, ty| {
+            Term::TypedRegister(TypedRegister::X {
+                register: XRegister { value, ty: None },
+                ty,
+            })
+        };
+        if let Instruction::Move(m) = &mut code[14] {
+            m.src = x(2, 1);
+        }
+        if let Instruction::GetTupleElement(g) = &mut code[7] {
+            g.source = Register::Typed(TypedRegister::X {
+                register: XRegister { value: 1, ty: None },
+                ty: 0,
+            });
+        }
+        let analysis = TypeAnalysis::new(&code);
+
+        let state = analysis.state(7).unwrap();
+        assert_eq!(state.get(RegisterId::X(0)), Type::integer(Some(0), Some(9)));
+        assert_eq!(state.get(RegisterId::X(1)).to_string(), "tuple/2");
+
+        let state = analysis.state(10).unwrap();
+        assert_eq!(state.get(RegisterId::X(0)).to_string(), "integer(0..9)");
+        assert_eq!(state.get(RegisterId::X(1)).to_string(), "tuple");
+        assert_eq!(state.get(RegisterId::X(2)).to_string(), "any");
+
+        let state = analysis.state(13).unwrap();
+        assert_eq!(state.get(RegisterId::X(2)).to_string(), "atom(3, 4)");
+        let state = analysis.state(14).unwrap();
+        assert_eq!(
+            state.get(RegisterId::X(2)),
+            Type::atom(term::Atom { value: 3 })
+        );
+        assert_eq!(
+            state.iter().map(|(x, _)| x).collect::<Vec<_>>(),
+            [RegisterId::X(0), RegisterId::X(1), RegisterId::X(2)]
+        );
+
+        let mut chunk = vec![0, 0, 0, 2, 0, 0, 0, 2];
+        chunk.extend((Kinds::TUPLE.bits() | Kinds::MAP.bits()).to_be_bytes());
+        chunk.extend((Kinds::INTEGER.bits() | HAS_LOWER_BOUND).to_be_bytes());
+        chunk.extend(0i64.to_be_bytes());
+        let types = decode_type_chunk(&chunk).unwrap();
+        assert_eq!(types[1], Type::integer(Some(0), None));
+        assert!(decode_type_chunk(&[0, 0, 0, 9, 0, 0, 0, 0]).is_err());
+
+        let discrepancies = analysis.check_annotations(&code, &types);
+        assert_eq!(
+            discrepancies
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "7: x(1): imprecise annotation: annotated=map | tuple, inferred=tuple/2",
+                "14: x(2): stale annotation: annotated=integer(0..), inferred=atom(3)",
+            ]
+        );
+    }
+
+    #[test]
+    fn lattice_operations_work() {
+        let nil = Type::from_kinds(Kinds::NIL);
+        let atom = Type::from_kinds(Kinds::ATOM);
+        let unbounded = Type::integer(None, None);
+        #[rustfmt::skip]
+        let table: Vec<(Type, Type, &str, &str, bool)> = vec![
+            // (a, b, a.join(b), a.meet(b), a.is_subset_of(b))
+            (atoms(&[1, 2]), atoms(&[2, 3]), "atom(1, 2, 3)", "atom(2)", false),
+            (atoms(&[1]), atom.clone(), "atom", "atom(1)", true),
+            (atom.clone(), atoms(&[1]), "atom", "atom(1)", false),
+            (atoms(&[1]), atoms(&[2]), "atom(1, 2)", "none", false),
+            (int(0, 9), int(5, 20), "integer(0..20)", "integer(5..9)", false),
+            (int(0, 3), int(5, 9), "integer(0..9)", "none", false),
+            (int(5, 9), int(0, 9), "integer(0..9)", "integer(5..9)", true),
+            (int(0, 3), Type::any(), "any", "integer(0..3)", true),
+            (Type::integer(Some(0), None), Type::integer(None, Some(9)), "integer", "integer(0..9)", false),
+            (int(0, 9), unbounded.clone(), "integer", "integer(0..9)", true),
+            (unbounded, int(0, 9), "integer", "integer(0..9)", false),
+            (atoms(&[1]), int(0, 9), "atom(1) | integer(0..9)", "none", false),
+            (Type::tuple(Some(2)), Type::tuple(Some(3)), "tuple", "none", false),
+            (Type::tuple(Some(2)), Type::tuple(None), "tuple", "tuple/2", true),
+            (keys(&[1, 2]), keys(&[1]), "map(atom(1))", "map(atom(1), atom(2))", true),
+            (keys(&[1]), keys(&[1, 2]), "map(atom(1))", "map(atom(1), atom(2))", false),
+            (nil.clone(), Type::none(), "nil", "none", false),
+            (Type::none(), nil.clone(), "nil", "none", true),
+            (nil.clone(), atoms(&[1]), "atom(1) | nil", "none", false),
+        ];
+        for (a, b, join, meet, subset) in table {
+            assert_eq!(a.join(&b).to_string(), join, "{a} join {b}");
+            assert_eq!(b.join(&a).to_string(), join, "{b} join {a}");
+            assert_eq!(a.meet(&b).to_string(), meet, "{a} meet {b}");
+            assert_eq!(b.meet(&a).to_string(), meet, "{b} meet {a}");
+            assert_eq!(a.is_subset_of(&b), subset, "{a} is_subset_of {b}");
+        }
+    }
+
+    #[test]
+    fn select_val_fail_edges_exclude_values() {
+        let nil = Type::from_kinds(Kinds::NIL);
+        let list_type = nil.join(&Type::from_kinds(Kinds::CONS));
+        #[rustfmt::skip]
+        let table: Vec<(Type, Vec<Term>, Option<&str>)> = vec![
+            (int(0, 9), vec![i(0), i(9)], Some("integer(1..8)")),
+            (int(0, 9), vec![i(9), i(0)], Some("integer(1..8)")),
+            (int(0, 9), vec![i(5)], Some("integer(0..9)")),
+            (int(0, 1), vec![i(0), i(1)], None),
+            (atoms(&[1, 2]), vec![a(1)], Some("atom(2)")),
+            (atoms(&[1, 2]), vec![a(1), a(2)], None),
+            (Type::from_kinds(Kinds::ATOM), vec![a(1)], Some("atom")),
+            (list_type, vec![a(0)], Some("cons")),
+            (Type::any(), vec![a(1), i(0)], Some("any")),
+        ];
+        for (ty, values, expected) in table {
+            let destinations = values.iter().flat_map(|v| [v.clone(), l(2)]).collect();
+            let instruction = Instruction::SelectVal(SelectVal {
+                arg: x(0),
+                fail_label: Label { value: 1 },
+                destinations: List {
+                    items: destinations,
+                },
+            });
+            let state = state(vec![(RegisterId::X(0), ty.clone())]);
+            let fail = state.select(&instruction, &[Label { value: 1 }]);
+            assert_eq!(
+                fail.map(|x| x.get(RegisterId::X(0)).to_string()).as_deref(),
+                expected,
+                "{ty} without {values:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn comparisons_narrow_integer_ranges() {
+        let ge = |a, b| {
+            Instruction::IsGe(IsGe {
+                arg1: l(1),
+                arg2: a,
+                arg3: b,
+            })
+        };
+        let lt = |a, b| {
+            Instruction::IsLt(IsLt {
+                arg1: l(1),
+                arg2: a,
+                arg3: b,
+            })
+        };
+        #[rustfmt::skip]
+        let table: Vec<(Type, Instruction, bool, Option<&str>)> = vec![
+            // (type of x(0), test, passed, type of x(0) after the test)
+            (int(0, 9), ge(x(0), i(5)), true, Some("integer(5..9)")),
+            (int(0, 9), ge(x(0), i(5)), false, Some("integer(0..4)")),
+            (int(0, 9), ge(i(5), x(0)), true, Some("integer(0..5)")),
+            (int(0, 9), ge(i(5), x(0)), false, Some("integer(6..9)")),
+            (int(0, 9), ge(x(0), i(10)), true, None),
+            (int(0, 9), ge(x(0), i(0)), false, None),
+            (Type::integer(None, None), ge(x(0), i(5)), true, Some("integer(5..)")),
+            (Type::integer(None, None), ge(x(0), i(5)), false, Some("integer(..4)")),
+            (int(0, 9), ge(x(0), a(1)), true, Some("integer(0..9)")),
+            (int(0, 9), lt(x(0), i(5)), true, Some("integer(0..4)")),
+            (int(0, 9), lt(x(0), i(5)), false, Some("integer(5..9)")),
+            (int(0, 9), lt(x(0), i(0)), true, None),
+        ];
+        for (ty, instruction, passed, expected) in table {
+            let mut state = state(vec![(RegisterId::X(0), ty.clone())]);
+            let feasible = state.test(&instruction, passed);
+            let actual = feasible.then(|| state.get(RegisterId::X(0)).to_string());
+            assert_eq!(
+                actual.as_deref(),
+                expected,
+                "{ty}: {instruction} ({passed})"
+            );
+        }
+    }
+
+    #[test]
+    fn trim_shifts_y_registers() {
+        let registers = || {
+            vec![
+                (RegisterId::X(0), atoms(&[1])),
+                (RegisterId::Y(0), atoms(&[2])),
+                (RegisterId::Y(1), atoms(&[3])),
+                (RegisterId::Y(2), atoms(&[4])),
+            ]
+        };
+        let table: Vec<(usize, usize, Vec<&str>)> = vec![
+            (
+                0,
+                3,
+                vec![
+                    "x(0): atom(1)",
+                    "y(0): atom(2)",
+                    "y(1): atom(3)",
+                    "y(2): atom(4)",
+                ],
+            ),
+            (
+                1,
+                2,
+                vec!["x(0): atom(1)", "y(0): atom(3)", "y(1): atom(4)"],
+            ),
+            (2, 1, vec!["x(0): atom(1)", "y(0): atom(4)"]),
+            (3, 0, vec!["x(0): atom(1)"]),
+        ];
+        for (n, remaining, expected) in table {
+            let mut state = state(registers());
+            state.execute(&Instruction::Trim(Trim {
+                arg1: Term::Usize(n),
+                arg2: Term::Usize(remaining),
+            }));
+            assert_eq!(show(&state), expected, "trim {n} {remaining}");
+        }
+    }
+
+    #[test]
+    fn put_map_adds_keys() {
+        let literal = Term::Literal(Literal { value: 0 });
+        let assoc = |src, pairs| {
+            Instruction::PutMapAssoc(PutMapAssoc {
+                arg1: l(0),
+                arg2: src,
+                arg3: x(2),
+                arg4: Term::Usize(2),
+                arg5: list(pairs),
+            })
+        };
+        let exact = |src, pairs| {
+            Instruction::PutMapExact(PutMapExact {
+                arg1: l(0),
+                arg2: src,
+                arg3: x(2),
+                arg4: Term::Usize(2),
+                arg5: list(pairs),
+            })
+        };
+        #[rustfmt::skip]
+        let table: Vec<(Instruction, &str)> = vec![
+            (assoc(x(0), vec![a(2), x(1)]), "map(atom(1), atom(2))"),
+            (assoc(x(0), vec![a(1), x(1)]), "map(atom(1))"),
+            (assoc(x(1), vec![a(2), x(1)]), "map(atom(2))"),
+            (assoc(literal.clone(), vec![]), "map"),
+            (exact(x(0), vec![i(7), x(1), literal.clone(), x(0)]), "map(atom(1), 7, literal(0))"),
+            (exact(x(0), vec![x(1), x(1)]), "map(atom(1))"),
+        ];
+        for (instruction, expected) in table {
+            let mut state = state(vec![(RegisterId::X(0), keys(&[1]))]);
+            state.execute(&instruction);
+            assert_eq!(
+                state.get(RegisterId::X(2)).to_string(),
+                expected,
+                "{instruction}"
+            );
+        }
+    }
+
+    #[test]
+    fn exception_edges_reset_types() {
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 1;
+            label 2;
+            allocate 2 1;
+            is_integer {f,1} {x,0};
+            move {x,0} {y,1};
+            try {y,0} {f,3};
+            move nil {x,0};
+            try_end {y,0};
+            deallocate 2;
+            return;
+            label 3;
+            try_case {y,0};
+            deallocate 2;
+            return;
+        };
+        let analysis = TypeAnalysis::new(&code);
+        let table: Vec<(usize, Vec<&str>)> = vec![
+            (7, vec!["x(0): integer", "y(1): integer"]),
+            (8, vec!["x(0): nil", "y(1): integer"]),
+            (11, vec![]),
+            (12, vec![]),
+        ];
+        for (position, expected) in table {
+            let state = analysis.state(position).unwrap();
+            assert_eq!(show(state), expected, "position {position}");
+        }
+    }
+
+    #[test]
+    fn decode_type_chunk_works() {
+        let entry = |bits: u16, bounds: &[i64], unit: Option<u8>| {
+            let mut bytes = bits.to_be_bytes().to_vec();
+            for bound in bounds {
+                bytes.extend(bound.to_be_bytes());
+            }
+            bytes.extend(unit);
+            bytes
+        };
+        let chunk = |version: u32, entries: &[Vec<u8>]| {
+            let mut bytes = [version, entries.len() as u32]
+                .map(u32::to_be_bytes)
+                .concat();
+            bytes.extend(entries.concat());
+            bytes
+        };
+        let bitstring = Kinds::BITSTRING.bits() | HAS_UNIT;
+        let integer = Kinds::INTEGER.bits() | HAS_LOWER_BOUND | HAS_UPPER_BOUND;
+        #[rustfmt::skip]
+        let table: Vec<(Vec<u8>, Option<Vec<&str>>)> = vec![
+            (chunk(1, &[]), Some(vec![])),
+            (chunk(2, &[entry(bitstring, &[], Some(8)), entry(integer, &[-1, 1], None)]), Some(vec!["bitstring", "integer(-1..1)"])),
+            (chunk(2, &[entry(bitstring | Kinds::NIL.bits(), &[], Some(1))]), Some(vec!["bitstring | nil"])),
+            (chunk(2, &[entry(Kinds::INTEGER.bits() | HAS_UPPER_BOUND, &[5], None)]), Some(vec!["integer(..5)"])),
+            (chunk(2, &[entry(integer, &[3, 1], None)]), Some(vec!["none"])),
+            (chunk(2, &[entry(bitstring, &[], None)]), None),
+            (chunk(2, &[entry(integer, &[0], None)]), None),
+            (chunk(3, &[]), None),
+        ];
+        for (bytes, expected) in table {
+            let types = decode_type_chunk(&bytes)
+                .ok()
+                .map(|x| x.iter().map(|x| x.to_string()).collect::<Vec<_>>());
+            assert_eq!(
+                types,
+                expected.map(|x| x.iter().map(|x| x.to_string()).collect()),
+                "{bytes:?}"
+            );
+        }
+    }
+}
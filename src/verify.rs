@@ -0,0 +1,531 @@
+//! Verification of module code against the checks made by the BEAM loader and `beam_validator`.
+//!
+//! # Examples
+//!
+//! ```
+//! use beamcode::beam_asm;
+//! use beamcode::verify::{self, DiagnosticKind, VerifyOptions};
+//!
+//! let code = beam_asm! {
+//!     label 1;
+//!     func_info foo bar 0;
+//!     label 2;
+//!     return;
+//! };
+//! let diagnostics = verify::verify(&code, &VerifyOptions::default());
+//! assert_eq!(diagnostics.len(), 1);
+//! assert_eq!(diagnostics[0].position, 3);
+//! assert_eq!(diagnostics[0].kind, DiagnosticKind::UninitializedXRegister { register: 0 });
+//! ```
+use crate::cfg::{self, BlockId, Cfg, EdgeKind};
+use crate::defuse::RegisterId;
+use crate::frame::{self, usize_term, Frame, FrameAnalysis, FrameState, HeapNeed};
+use crate::instruction::Instruction;
+use crate::term::{Label, Term};
+use crate::visit::LabelCollector;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+/// Options for [`verify()`].
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// The maximum opcode in the header of the `Code` chunk.
+    ///
+    /// If `None`, opcode numbers are not checked.
+    pub opcode_max: Option<u8>,
+}
+
+/// A problem found by [`verify()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The index of the offending instruction.
+    pub position: usize,
+
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.kind)
+    }
+}
+
+/// The kind of a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DiagnosticKind {
+    #[error("undefined label: {label}")]
+    UndefinedLabel { label: Label },
+
+    #[error("duplicate label: {label}")]
+    DuplicateLabel { label: Label },
+
+    #[error("opcode out of range: opcode={opcode}, opcode_max={opcode_max}")]
+    OpcodeOutOfRange { opcode: u8, opcode_max: u8 },
+
+    #[error("instruction outside of functions")]
+    OutsideFunction,
+
+    #[error("func_info not followed by an entry label")]
+    MissingEntryLabel,
+
+    #[error("call to a label that is not a function entry: {label}")]
+    NotFunctionEntry { label: Label },
+
+    #[error("uninitialized or dead register: x({register})")]
+    UninitializedXRegister { register: usize },
+
+    #[error("uninitialized register: y({register})")]
+    UninitializedYRegister { register: usize },
+
+    #[error("no stack frame")]
+    NoStackFrame,
+
+    #[error("register out of stack frame: y({register}), frame_size={frame_size}")]
+    YRegisterOutOfFrame { register: usize, frame_size: usize },
+
+    #[error("stack frame already allocated")]
+    FrameAlreadyAllocated,
+
+    #[error("stack frame size mismatch: frame_size={frame_size}, size={size}")]
+    FrameSizeMismatch { frame_size: usize, size: usize },
+
+    #[error("stack frame not deallocated")]
+    FrameNotDeallocated,
+
+    #[error("inconsistent stack frames on incoming paths")]
+    InconsistentFrame,
+
+    #[error("trimming more than the stack frame: n={n}, frame_size={frame_size}")]
+    OverTrim { n: usize, frame_size: usize },
+
+    #[error("heap need exceeds the reserved space: need=({need}), available=({available})")]
+    HeapExceeded { need: HeapNeed, available: HeapNeed },
+}
+
+impl From<frame::Error> for DiagnosticKind {
+    fn from(error: frame::Error) -> Self {
+        match error {
+            frame::Error::NoStackFrame => Self::NoStackFrame,
+            frame::Error::FrameAlreadyAllocated => Self::FrameAlreadyAllocated,
+            frame::Error::FrameSizeMismatch { frame_size, size } => {
+                Self::FrameSizeMismatch { frame_size, size }
+            }
+            frame::Error::FrameNotDeallocated => Self::FrameNotDeallocated,
+            frame::Error::InconsistentFrame => Self::InconsistentFrame,
+            frame::Error::OverTrim { n, frame_size } => Self::OverTrim { n, frame_size },
+            frame::Error::HeapExceeded { need, available } => {
+                Self::HeapExceeded { need, available }
+            }
+        }
+    }
+}
+
+/// Verifies module code.
+///
+/// The code must consist of functions (see [`cfg::functions()`]) optionally followed by
+/// `int_code_end`. Diagnostics are returned in instruction order.
+pub fn verify(instructions: &[Instruction], options: &VerifyOptions) -> Vec<Diagnostic> {
+    let mut verifier = Verifier {
+        instructions,
+        diagnostics: Vec::new(),
+    };
+    verifier.check_labels();
+    if let Some(opcode_max) = options.opcode_max {
+        verifier.check_opcodes(opcode_max);
+    }
+    let functions = cfg::functions(instructions);
+    verifier.check_functions(&functions);
+    for function in functions {
+        verifier.check_registers(function);
+    }
+
+    let mut diagnostics = verifier.diagnostics;
+    diagnostics.sort_by_key(|x| x.position);
+    diagnostics.dedup();
+    diagnostics
+}
+
+#[derive(Debug)]
+struct Verifier<'a> {
+    instructions: &'a [Instruction],
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Verifier<'_> {
+    fn report(&mut self, position: usize, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic { position, kind });
+    }
+
+    fn check_labels(&mut self) {
+        let mut defined = HashSet::new();
+        for (position, instruction) in self.instructions.iter().enumerate() {
+            if let Instruction::Label(x) = instruction {
+                let label = Label { value: x.literal };
+                if !defined.insert(label) {
+                    self.report(position, DiagnosticKind::DuplicateLabel { label });
+                }
+            }
+        }
+        for (position, instruction) in self.instructions.iter().enumerate() {
+            for label in LabelCollector::collect(instruction) {
+                if label.value != 0 && !defined.contains(&label) {
+                    self.report(position, DiagnosticKind::UndefinedLabel { label });
+                }
+            }
+        }
+    }
+
+    fn check_opcodes(&mut self, opcode_max: u8) {
+        for (position, instruction) in self.instructions.iter().enumerate() {
+            let opcode = instruction.opcode();
+            if opcode > opcode_max {
+                self.report(
+                    position,
+                    DiagnosticKind::OpcodeOutOfRange { opcode, opcode_max },
+                );
+            }
+        }
+    }
+
+    fn check_functions(&mut self, functions: &[Range<usize>]) {
+        let mut inside = vec![false; self.instructions.len()];
+        let mut entries = HashSet::new();
+        for function in functions {
+            for i in function.clone() {
+                inside[i] = true;
+                if !matches!(self.instructions[i], Instruction::FuncInfo(_)) {
+                    continue;
+                }
+                match self.instructions.get(i + 1) {
+                    Some(Instruction::Label(x)) if i + 1 < function.end => {
+                        entries.insert(Label { value: x.literal });
+                    }
+                    _ => self.report(i, DiagnosticKind::MissingEntryLabel),
+                }
+            }
+        }
+        if let Some(Instruction::IntCodeEnd(_)) = self.instructions.last() {
+            inside[self.instructions.len() - 1] = true;
+        }
+        for i in 0..inside.len() {
+            if !inside[i] && (i == 0 || inside[i - 1]) {
+                self.report(i, DiagnosticKind::OutsideFunction);
+            }
+        }
+
+        for (position, instruction) in self.instructions.iter().enumerate() {
+            let label = match instruction {
+                Instruction::Call(x) => x.label,
+                Instruction::CallOnly(x) => x.label,
+                Instruction::CallLast(x) => match x.arg2 {
+                    Term::Label(label) => label,
+                    _ => continue,
+                },
+                Instruction::MakeFun(x) => match x.arg1 {
+                    Term::Label(label) => label,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if !entries.contains(&label) {
+                self.report(position, DiagnosticKind::NotFunctionEntry { label });
+            }
+        }
+    }
+
+    fn check_registers(&mut self, function: Range<usize>) {
+        let offset = function.start;
+        let instructions: &[Instruction] = self.instructions;
+        let instructions = &instructions[function];
+        let arity = instructions.iter().find_map(|x| match x {
+            Instruction::FuncInfo(x) => Some(x.arity),
+            _ => None,
+        });
+        let cfg = Cfg::new(instructions);
+        if cfg.blocks().is_empty() {
+            return;
+        }
+        let frames = FrameAnalysis::new(instructions);
+        for (position, error) in frames.diagnostics() {
+            self.report(offset + position, error.clone().into());
+        }
+
+        let mut states = vec![None; cfg.blocks().len()];
+        states[cfg.entry().0] = Some(State::entry(arity.unwrap_or(0)));
+        let mut worklist = vec![cfg.entry()];
+        while let Some(id) = worklist.pop() {
+            let state = states[id.0].clone().expect("unreachable");
+            let outputs = simulate(&cfg, &frames, instructions, id, state, &mut |_, _| {});
+            for (target, output) in outputs {
+                let new = match &states[target.0] {
+                    None => output,
+                    Some(old) => old.join(&output),
+                };
+                if states[target.0].as_ref() != Some(&new) {
+                    states[target.0] = Some(new);
+                    worklist.push(target);
+                }
+            }
+        }
+
+        for (i, state) in states.into_iter().enumerate() {
+            if let Some(state) = state {
+                let mut report = |position, kind| self.report(offset + position, kind);
+                simulate(&cfg, &frames, instructions, BlockId(i), state, &mut report);
+            }
+        }
+    }
+}
+
+// The initialized registers. The stack frame is given by `FrameAnalysis`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    x: BTreeSet<usize>,
+    y: BTreeSet<usize>,
+}
+
+impl State {
+    fn entry(arity: usize) -> Self {
+        Self {
+            x: (0..arity).collect(),
+            y: BTreeSet::new(),
+        }
+    }
+
+    fn join(&self, other: &Self) -> Self {
+        Self {
+            x: self.x.intersection(&other.x).copied().collect(),
+            y: self.y.intersection(&other.y).copied().collect(),
+        }
+    }
+
+    // Returns the state at the fail label of the instruction (i.e., before the destination is
+    // written).
+    fn execute(
+        &mut self,
+        instruction: &Instruction,
+        frame: &FrameState,
+        report: &mut impl FnMut(DiagnosticKind),
+    ) -> Self {
+        for register in instruction.uses() {
+            match register {
+                RegisterId::X(i) if !self.x.contains(&i) => {
+                    report(DiagnosticKind::UninitializedXRegister { register: i })
+                }
+                RegisterId::Y(i)
+                    if check_y(frame.frame, i, report)
+                        && frame.frame != Frame::Unknown
+                        && !self.y.contains(&i) =>
+                {
+                    report(DiagnosticKind::UninitializedYRegister { register: i })
+                }
+                _ => {}
+            }
+        }
+
+        let mut frame = frame.clone();
+        frame.execute(instruction, &mut |_| {});
+        match instruction {
+            Instruction::Allocate(_)
+            | Instruction::AllocateHeap(_)
+            | Instruction::AllocateZero(_)
+            | Instruction::AllocateHeapZero(_)
+            | Instruction::Deallocate(_)
+            | Instruction::CallLast(_)
+            | Instruction::CallExtLast(_)
+            | Instruction::ApplyLast(_) => self.y.clear(),
+            Instruction::Trim(x) => {
+                let n = usize_term(&x.arg1);
+                self.y = self.y.iter().filter_map(|i| i.checked_sub(n)).collect();
+            }
+            _ => {}
+        }
+
+        if let Some(n) = instruction.preserved_x_registers() {
+            self.x.retain(|&i| i < n);
+        }
+        let fail_state = self.clone();
+        for register in instruction.defs() {
+            match register {
+                RegisterId::X(i) => {
+                    self.x.insert(i);
+                }
+                RegisterId::Y(i) => {
+                    if check_y(frame.frame, i, report) {
+                        self.y.insert(i);
+                    }
+                }
+                RegisterId::Fr(_) => {}
+            }
+        }
+        fail_state
+    }
+}
+
+fn check_y(frame: Frame, register: usize, report: &mut impl FnMut(DiagnosticKind)) -> bool {
+    match frame {
+        Frame::None => {
+            report(DiagnosticKind::NoStackFrame);
+            false
+        }
+        Frame::Allocated(frame_size) if frame_size <= register => {
+            report(DiagnosticKind::YRegisterOutOfFrame {
+                register,
+                frame_size,
+            });
+            false
+        }
+        _ => true,
+    }
+}
+
+// Executes the instructions of a block and returns the states passed to its successors.
+fn simulate(
+    cfg: &Cfg,
+    frames: &FrameAnalysis,
+    instructions: &[Instruction],
+    id: BlockId,
+    mut state: State,
+    report: &mut impl FnMut(usize, DiagnosticKind),
+) -> Vec<(BlockId, State)> {
+    let unknown = FrameState {
+        frame: Frame::Unknown,
+        heap: HeapNeed::default(),
+    };
+    let block = cfg.block(id);
+    let mut exception_states = HashMap::new();
+    let mut fail_state = state.clone();
+    for position in block.range.clone() {
+        let instruction = &instructions[position];
+        let frame = frames.state(position).unwrap_or(&unknown);
+        fail_state = state.execute(instruction, frame, &mut |kind| report(position, kind));
+
+        let handler = match instruction {
+            Instruction::Try(x) => Some(x.label),
+            Instruction::Catch(x) => match x.arg2 {
+                Term::Label(label) => Some(label),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(target) = handler.and_then(|label| cfg.block_of_label(label)) {
+            let mut handler_state = state.clone();
+            handler_state.x.clear();
+            exception_states.insert(target, handler_state);
+        }
+    }
+
+    block
+        .successors
+        .iter()
+        .map(|edge| {
+            let state = match edge.kind {
+                EdgeKind::Branch => fail_state.clone(),
+                EdgeKind::Exception => exception_states[&edge.target].clone(),
+                _ => state.clone(),
+            };
+            (edge.target, state)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam_asm;
+
+    fn kinds(code: &[Instruction]) -> Vec<(usize, DiagnosticKind)> {
+        verify(
+            code,
+            &VerifyOptions {
+                opcode_max: Some(169),
+            },
+        )
+        .into_iter()
+        .map(|x| (x.position, x.kind))
+        .collect()
+    }
+
+    #[test]
+    fn verify_works() {
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 1;
+            label 2;
+            allocate 1 1;
+            move {x,0} {y,0};
+            is_nil {f,3} {x,0};
+            call 1 {f,2};
+            move {y,0} {x,0};
+            deallocate 1;
+            return;
+            label 3;
+            test_heap 2 0;
+            put_list {x,0} nil {x,0};
+            deallocate 1;
+            return;
+            int_code_end;
+        };
+        assert_eq!(
+            kinds(&code),
+            [(12, DiagnosticKind::UninitializedXRegister { register: 0 })]
+        );
+
+        let code = beam_asm! {
+            label 1;
+            func_info foo bar 0;
+            label 2;
+            is_nil {f,4} {y,0};
+            allocate 2 0;
+            is_nil {f,3} {y,0};
+            call 0 {f,1};
+            label 3;
+            deallocate 1;
+            call_only 0 {f,5};
+            label 4;
+            return;
+            recv_marker_clear {y,0};
+        };
+        assert_eq!(
+            kinds(&code),
+            [
+                (3, DiagnosticKind::NoStackFrame),
+                (5, DiagnosticKind::UninitializedYRegister { register: 0 }),
+                (
+                    6,
+                    DiagnosticKind::NotFunctionEntry {
+                        label: Label { value: 1 }
+                    }
+                ),
+                (
+                    8,
+                    DiagnosticKind::FrameSizeMismatch {
+                        frame_size: 2,
+                        size: 1
+                    }
+                ),
+                (
+                    9,
+                    DiagnosticKind::UndefinedLabel {
+                        label: Label { value: 5 }
+                    }
+                ),
+                (
+                    9,
+                    DiagnosticKind::NotFunctionEntry {
+                        label: Label { value: 5 }
+                    }
+                ),
+                (11, DiagnosticKind::UninitializedXRegister { register: 0 }),
+                (
+                    12,
+                    DiagnosticKind::OpcodeOutOfRange {
+                        opcode: 174,
+                        opcode_max: 169
+                    }
+                ),
+            ]
+        );
+    }
+}
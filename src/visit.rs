@@ -0,0 +1,450 @@
+//! Visitors over instructions and their operands.
+//!
+//! [`Visit`] and [`VisitMut`] have a callback for each kind of operand.
+//! The default implementations walk into the children of the visited node,
+//! so a visitor only needs to override the callbacks it is interested in.
+//!
+//! ```
+//! use beamcode::instruction::Instruction;
+//! use beamcode::term::Label;
+//! use beamcode::visit::{VisitMut, WalkMut};
+//!
+//! struct ShiftLabels(usize);
+//!
+//! impl VisitMut for ShiftLabels {
+//!     fn visit_instruction_mut(&mut self, instruction: &mut Instruction) {
+//!         if let Instruction::Label(x) = instruction {
+//!             x.literal += self.0;
+//!         }
+//!         instruction.walk_mut(self);
+//!     }
+//!
+//!     fn visit_label_mut(&mut self, label: &mut Label) {
+//!         if label.value != 0 {
+//!             label.value += self.0;
+//!         }
+//!     }
+//! }
+//! ```
+use crate::instruction::Instruction;
+use crate::term::{
+    Allocation, AllocationList, Atom, FloatingPointRegister, Label, List, Literal, Register, Term,
+    TypedRegister, XRegister, YRegister,
+};
+use num::BigInt;
+
+/// This trait allows visiting instructions and their operands.
+pub trait Visit {
+    fn visit_instruction(&mut self, instruction: &Instruction) {
+        instruction.walk(self);
+    }
+
+    fn visit_term(&mut self, term: &Term) {
+        term.walk(self);
+    }
+
+    fn visit_label(&mut self, _label: &Label) {}
+
+    fn visit_atom(&mut self, _atom: &Atom) {}
+
+    fn visit_literal(&mut self, _literal: &Literal) {}
+
+    /// Visits a register operand.
+    ///
+    /// Note that registers appearing as [`Term`]s (e.g., source operands) are not passed to this
+    /// method but directly to [`Visit::visit_x_register()`] or [`Visit::visit_y_register()`].
+    fn visit_register(&mut self, register: &Register) {
+        register.walk(self);
+    }
+
+    fn visit_typed_register(&mut self, register: &TypedRegister) {
+        register.walk(self);
+    }
+
+    fn visit_x_register(&mut self, _register: &XRegister) {}
+
+    fn visit_y_register(&mut self, _register: &YRegister) {}
+
+    fn visit_floating_point_register(&mut self, _register: &FloatingPointRegister) {}
+}
+
+/// This trait allows visiting and modifying instructions and their operands.
+pub trait VisitMut {
+    fn visit_instruction_mut(&mut self, instruction: &mut Instruction) {
+        instruction.walk_mut(self);
+    }
+
+    fn visit_term_mut(&mut self, term: &mut Term) {
+        term.walk_mut(self);
+    }
+
+    fn visit_label_mut(&mut self, _label: &mut Label) {}
+
+    fn visit_atom_mut(&mut self, _atom: &mut Atom) {}
+
+    fn visit_literal_mut(&mut self, _literal: &mut Literal) {}
+
+    /// Visits a register operand.
+    ///
+    /// Note that registers appearing as [`Term`]s (e.g., source operands) are not passed to this
+    /// method but directly to [`VisitMut::visit_x_register_mut()`] or
+    /// [`VisitMut::visit_y_register_mut()`].
+    fn visit_register_mut(&mut self, register: &mut Register) {
+        register.walk_mut(self);
+    }
+
+    fn visit_typed_register_mut(&mut self, register: &mut TypedRegister) {
+        register.walk_mut(self);
+    }
+
+    fn visit_x_register_mut(&mut self, _register: &mut XRegister) {}
+
+    fn visit_y_register_mut(&mut self, _register: &mut YRegister) {}
+
+    fn visit_floating_point_register_mut(&mut self, _register: &mut FloatingPointRegister) {}
+}
+
+/// This trait is implemented by the nodes that a [`Visit`] visitor can traverse.
+pub trait Walk {
+    /// Calls the visitor callback corresponding to `self`.
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        self.walk(visitor);
+    }
+
+    /// Visits the children of `self`.
+    fn walk<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
+}
+
+/// This trait is implemented by the nodes that a [`VisitMut`] visitor can traverse.
+pub trait WalkMut {
+    /// Calls the visitor callback corresponding to `self`.
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.walk_mut(visitor);
+    }
+
+    /// Visits the children of `self`.
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, _visitor: &mut V) {}
+}
+
+impl Walk for usize {}
+
+impl WalkMut for usize {}
+
+impl Walk for char {}
+
+impl WalkMut for char {}
+
+impl Walk for BigInt {}
+
+impl WalkMut for BigInt {}
+
+impl Walk for Allocation {}
+
+impl WalkMut for Allocation {}
+
+impl Walk for AllocationList {}
+
+impl WalkMut for AllocationList {}
+
+impl Walk for Term {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_term(self);
+    }
+
+    fn walk<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Self::Usize(x) => x.accept(visitor),
+            Self::Integer(x) => x.accept(visitor),
+            Self::Atom(x) => x.accept(visitor),
+            Self::XRegister(x) => x.accept(visitor),
+            Self::YRegister(x) => x.accept(visitor),
+            Self::Label(x) => x.accept(visitor),
+            Self::Character(x) => x.accept(visitor),
+            Self::List(x) => x.accept(visitor),
+            Self::FloatingPointRegister(x) => x.accept(visitor),
+            Self::AllocationList(x) => x.accept(visitor),
+            Self::Literal(x) => x.accept(visitor),
+            Self::TypedRegister(x) => x.accept(visitor),
+        }
+    }
+}
+
+impl WalkMut for Term {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_term_mut(self);
+    }
+
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        match self {
+            Self::Usize(x) => x.accept_mut(visitor),
+            Self::Integer(x) => x.accept_mut(visitor),
+            Self::Atom(x) => x.accept_mut(visitor),
+            Self::XRegister(x) => x.accept_mut(visitor),
+            Self::YRegister(x) => x.accept_mut(visitor),
+            Self::Label(x) => x.accept_mut(visitor),
+            Self::Character(x) => x.accept_mut(visitor),
+            Self::List(x) => x.accept_mut(visitor),
+            Self::FloatingPointRegister(x) => x.accept_mut(visitor),
+            Self::AllocationList(x) => x.accept_mut(visitor),
+            Self::Literal(x) => x.accept_mut(visitor),
+            Self::TypedRegister(x) => x.accept_mut(visitor),
+        }
+    }
+}
+
+impl Walk for Label {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_label(self);
+    }
+}
+
+impl WalkMut for Label {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_label_mut(self);
+    }
+}
+
+impl Walk for Atom {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_atom(self);
+    }
+}
+
+impl WalkMut for Atom {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_atom_mut(self);
+    }
+}
+
+impl Walk for Literal {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_literal(self);
+    }
+}
+
+impl WalkMut for Literal {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_literal_mut(self);
+    }
+}
+
+impl Walk for Register {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_register(self);
+    }
+
+    fn walk<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Self::X(x) => x.accept(visitor),
+            Self::Y(x) => x.accept(visitor),
+            Self::Typed(x) => x.accept(visitor),
+        }
+    }
+}
+
+impl WalkMut for Register {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_register_mut(self);
+    }
+
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        match self {
+            Self::X(x) => x.accept_mut(visitor),
+            Self::Y(x) => x.accept_mut(visitor),
+            Self::Typed(x) => x.accept_mut(visitor),
+        }
+    }
+}
+
+impl Walk for TypedRegister {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_typed_register(self);
+    }
+
+    fn walk<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Self::X { register, .. } => register.accept(visitor),
+            Self::Y { register, .. } => register.accept(visitor),
+        }
+    }
+}
+
+impl WalkMut for TypedRegister {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_typed_register_mut(self);
+    }
+
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        match self {
+            Self::X { register, .. } => register.accept_mut(visitor),
+            Self::Y { register, .. } => register.accept_mut(visitor),
+        }
+    }
+}
+
+impl Walk for XRegister {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_x_register(self);
+    }
+}
+
+impl WalkMut for XRegister {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_x_register_mut(self);
+    }
+}
+
+impl Walk for YRegister {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_y_register(self);
+    }
+}
+
+impl WalkMut for YRegister {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_y_register_mut(self);
+    }
+}
+
+impl Walk for FloatingPointRegister {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_floating_point_register(self);
+    }
+}
+
+impl WalkMut for FloatingPointRegister {
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_floating_point_register_mut(self);
+    }
+}
+
+impl<T: Walk> Walk for List<T> {
+    fn walk<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        self.items.walk(visitor);
+    }
+}
+
+impl<T: WalkMut> WalkMut for List<T> {
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.items.walk_mut(visitor);
+    }
+}
+
+impl<T: Walk> Walk for Vec<T> {
+    fn walk<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        for x in self {
+            x.accept(visitor);
+        }
+    }
+}
+
+impl<T: WalkMut> WalkMut for Vec<T> {
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        for x in self {
+            x.accept_mut(visitor);
+        }
+    }
+}
+
+// Collects the label operands of an instruction.
+#[derive(Debug, Default)]
+pub(crate) struct LabelCollector {
+    labels: Vec<Label>,
+}
+
+impl LabelCollector {
+    pub(crate) fn collect(instruction: &Instruction) -> Vec<Label> {
+        let mut collector = Self::default();
+        instruction.accept(&mut collector);
+        collector.labels
+    }
+}
+
+impl Visit for LabelCollector {
+    fn visit_label(&mut self, label: &Label) {
+        self.labels.push(*label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{self, Instruction};
+
+    #[test]
+    fn visit_works() {
+        #[derive(Default)]
+        struct Collector {
+            labels: Vec<usize>,
+            x_registers: Vec<usize>,
+            y_registers: Vec<usize>,
+        }
+
+        impl Visit for Collector {
+            fn visit_label(&mut self, label: &Label) {
+                self.labels.push(label.value);
+            }
+
+            fn visit_x_register(&mut self, register: &XRegister) {
+                self.x_registers.push(register.value);
+            }
+
+            fn visit_y_register(&mut self, register: &YRegister) {
+                self.y_registers.push(register.value);
+            }
+        }
+
+        let instructions = [
+            Instruction::SelectVal(instruction::SelectVal {
+                arg: Term::XRegister(XRegister { value: 3, ty: None }),
+                fail_label: Label { value: 10 },
+                destinations: List {
+                    items: vec![
+                        Term::Atom(Atom { value: 1 }),
+                        Term::Label(Label { value: 11 }),
+                        Term::Atom(Atom { value: 2 }),
+                        Term::Label(Label { value: 12 }),
+                    ],
+                },
+            }),
+            Instruction::Move(instruction::Move {
+                src: Term::YRegister(YRegister { value: 1, ty: None }),
+                dst: Register::Typed(TypedRegister::X {
+                    register: XRegister { value: 0, ty: None },
+                    ty: 1,
+                }),
+            }),
+        ];
+        let mut collector = Collector::default();
+        for instruction in &instructions {
+            instruction.accept(&mut collector);
+        }
+        assert_eq!(collector.labels, [10, 11, 12]);
+        assert_eq!(collector.x_registers, [3, 0]);
+        assert_eq!(collector.y_registers, [1]);
+    }
+
+    #[test]
+    fn visit_mut_works() {
+        struct Renumber;
+
+        impl VisitMut for Renumber {
+            fn visit_label_mut(&mut self, label: &mut Label) {
+                label.value *= 2;
+            }
+        }
+
+        let mut instruction = Instruction::IsEqExact(instruction::IsEqExact {
+            label: Label { value: 3 },
+            arg1: Term::Label(Label { value: 4 }),
+            arg2: Term::Atom(Atom { value: 1 }),
+        });
+        instruction.accept_mut(&mut Renumber);
+
+        let Instruction::IsEqExact(x) = instruction else {
+            panic!()
+        };
+        assert_eq!(x.label, Label { value: 6 });
+        assert_eq!(x.arg1, Term::Label(Label { value: 8 }));
+    }
+}
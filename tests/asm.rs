@@ -0,0 +1,74 @@
+use beamcode::beam_asm;
+use beamcode::builder::{x, y, CodeBuilder};
+use beamcode::term::{Atom, Label, Literal, Term};
+
+#[test]
+fn beam_asm_matches_builder() {
+    let code = beam_asm! {
+        label 1;
+        func_info foo bar 1;
+        label 2;
+        allocate 1 1;
+        init_yregs [{y,0}];
+        is_tagged_tuple {f,3} {x,0} 2 ok;
+        select_val {x,0} {f,1} [ok, {f,3}, {integer,-1}, {f,1}];
+        label 3;
+        move {literal,0} {x,0};
+        call_ext_last 1 0 1;
+    };
+
+    let mut b = CodeBuilder::new();
+    let labels = [b.new_label(), b.new_label(), b.new_label()];
+    assert_eq!(labels[2], Label { value: 3 });
+    b.bind(labels[0])
+        .func_info(Atom { value: 1 }, Atom { value: 2 }, 1)
+        .bind(labels[1])
+        .allocate(1, 1)
+        .init_yregs(vec![y(0)])
+        .is_tagged_tuple(labels[2], x(0), 2, Atom { value: 3 })
+        .select_val(
+            x(0),
+            labels[0],
+            vec![
+                Term::Atom(Atom { value: 3 }),
+                Term::Label(labels[2]),
+                Term::Integer((-1).into()),
+                Term::Label(labels[0]),
+            ],
+        )
+        .bind(labels[2])
+        .move_(Literal { value: 0 }, x(0))
+        .call_ext_last(1, 0, 1);
+    let expected = b.into_instructions();
+
+    let to_strings = |code: &[beamcode::instruction::Instruction]| {
+        code.iter().map(|x| x.to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(to_strings(&code), to_strings(&expected));
+}
+
+#[test]
+fn beam_asm_types_lists_by_operand() {
+    let code = beam_asm! {
+        init_yregs [];
+        init_yregs [{y,1}];
+        select_val {x,0} {f,1} [];
+        put_list foo nil {x,0};
+        put_list [] {list,[bar, []]} {x,0};
+        move {integer,-9223372036854775809} {x,0};
+        move {integer,0x10} {x,1};
+    };
+    let code = code.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        code,
+        [
+            "init_yregs []",
+            "init_yregs [y(1)]",
+            "select_val x(0), f(1), []",
+            "put_list atom(1), nil, x(0)",
+            "put_list nil, [atom(2), nil], x(0)",
+            "move -9223372036854775809, x(0)",
+            "move 16, x(1)",
+        ]
+    );
+}
@@ -0,0 +1,318 @@
+use beam_file::chunk::RawChunk;
+use beam_file::RawBeamFile;
+use beamcode::beam_asm;
+use beamcode::instruction::Instruction;
+use beamcode::module::{CodeChunk, CodeHeader};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Makes an empty directory for a test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Runs the command in `dir` and returns the exit code and the standard output.
+fn beamcode(dir: &Path, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_beamcode"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    (output.status.code().unwrap(), stdout)
+}
+
+fn json(stdout: &str) -> serde_json::Value {
+    serde_json::from_str(stdout).unwrap()
+}
+
+// Returns the chunks of module `m` exporting `f/1` (`atom(2)`) with the code.
+fn chunks(code_chunk: Vec<u8>, extra: &[(&[u8; 4], &[u8])]) -> Vec<RawChunk> {
+    let mut atoms = 3u32.to_be_bytes().to_vec();
+    for name in ["m", "f", "erlang"] {
+        atoms.push(name.len() as u8);
+        atoms.extend_from_slice(name.as_bytes());
+    }
+    // erlang:f/0 and f/1 at label 2.
+    let imports = [1u32, 3, 2, 0].map(u32::to_be_bytes).concat();
+    let exports = [1u32, 2, 1, 2].map(u32::to_be_bytes).concat();
+    let mut chunks = vec![
+        RawChunk {
+            id: *b"AtU8",
+            data: atoms,
+        },
+        RawChunk {
+            id: *b"Code",
+            data: code_chunk,
+        },
+        RawChunk {
+            id: *b"ImpT",
+            data: imports,
+        },
+        RawChunk {
+            id: *b"ExpT",
+            data: exports,
+        },
+    ];
+    for (id, data) in extra {
+        chunks.push(RawChunk {
+            id: **id,
+            data: data.to_vec(),
+        });
+    }
+    chunks
+}
+
+fn write_beam(path: &Path, code: Vec<Instruction>, extra: &[(&[u8; 4], &[u8])]) {
+    let code_chunk = CodeChunk::new(code).encode().unwrap();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    RawBeamFile {
+        chunks: chunks(code_chunk, extra),
+    }
+    .to_file(path)
+    .unwrap();
+}
+
+// `f(X) -> X.`
+fn identity() -> Vec<Instruction> {
+    beam_asm! {
+        label 1;
+        func_info {atom,1} {atom,2} 1;
+        label 2;
+        return;
+        int_code_end;
+    }
+}
+
+// `f(_) -> erlang.`
+fn constant() -> Vec<Instruction> {
+    beam_asm! {
+        label 1;
+        func_info {atom,1} {atom,2} 1;
+        label 2;
+        move {atom,3} {x,0};
+        return;
+        int_code_end;
+    }
+}
+
+// `[{vsn,[1]}]` in the external term format.
+const ATTR: &[u8] = b"\x83l\0\0\0\x01h\x02w\x03vsnk\0\x01\x01j";
+
+#[test]
+fn info_and_chunks_work() {
+    let dir = temp_dir("info");
+    write_beam(&dir.join("m.beam"), identity(), &[(b"Attr", ATTR)]);
+
+    let (code, stdout) = beamcode(&dir, &["info", "m.beam"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "m.beam:
+  module: m
+  exports:
+    f/1
+  imports:
+    erlang:f/0
+  attributes:
+    vsn: [1]
+"
+    );
+    let (code, stdout) = beamcode(&dir, &["info", "--format", "json", "m.beam"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        json(&stdout)[0]["attributes"],
+        json(r#"[{"name": "vsn", "value": "[1]"}]"#)
+    );
+
+    let (code, stdout) = beamcode(&dir, &["chunks", "m.beam"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "m.beam:\n  AtU8       15\n  Code       30\n  ImpT       16\n  ExpT       16\n  Attr       18\n"
+    );
+
+    // An `Attr` chunk that is not a list of pairs is an error.
+    write_beam(
+        &dir.join("bad.beam"),
+        identity(),
+        &[(b"Attr", b"\x83a\x01")],
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_beamcode"))
+        .args(["info", "bad.beam"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid 'Attr' chunk in bad.beam"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn directories_are_searched_recursively() {
+    let dir = temp_dir("find");
+    write_beam(&dir.join("lib/b.beam"), identity(), &[]);
+    write_beam(&dir.join("lib/a/c.beam"), identity(), &[]);
+    write_beam(&dir.join("lib/a.beam"), identity(), &[]);
+    std::fs::write(dir.join("lib/README.md"), "not a beam file").unwrap();
+
+    let (code, stdout) = beamcode(&dir, &["roundtrip", "lib"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "lib/a/c.beam: ok (5 instructions)
+lib/a.beam: ok (5 instructions)
+lib/b.beam: ok (5 instructions)
+"
+    );
+}
+
+#[test]
+fn verify_works() {
+    let dir = temp_dir("verify");
+    write_beam(&dir.join("ok.beam"), identity(), &[]);
+    let code = beam_asm! {
+        label 1;
+        func_info {atom,1} {atom,2} 1;
+        label 2;
+        move {x,1} {x,0};
+        return;
+        int_code_end;
+    };
+    write_beam(&dir.join("ng.beam"), code, &[]);
+
+    let (code, stdout) = beamcode(&dir, &["verify", "ok.beam"]);
+    assert_eq!((code, stdout.as_str()), (0, "ok.beam: ok\n"));
+
+    let (code, stdout) = beamcode(&dir, &["verify", "ok.beam", "ng.beam"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        stdout,
+        "ok.beam: ok\nng.beam: 3: uninitialized or dead register: x(1)\n"
+    );
+
+    let (code, stdout) = beamcode(&dir, &["verify", "--format", "json", "ng.beam"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        json(&stdout),
+        json(
+            r#"[{"path": "ng.beam", "diagnostics": ["3: uninitialized or dead register: x(1)"]}]"#
+        )
+    );
+}
+
+#[test]
+fn roundtrip_reports_mismatches() {
+    let dir = temp_dir("roundtrip");
+    write_beam(&dir.join("ok.beam"), identity(), &[]);
+
+    // `move {integer,1} {x,0}` with the integer in the two-byte form instead of the one-byte one.
+    let code = constant();
+    let mut bytecode = beamcode::encode_instructions(&code[..3]).unwrap();
+    bytecode.extend([64, 0x09, 0x01, 0x03]);
+    bytecode.extend(beamcode::encode_instructions(&code[4..]).unwrap());
+    let code_chunk = CodeHeader::new(&code).encode(&bytecode);
+    RawBeamFile {
+        chunks: chunks(code_chunk, &[]),
+    }
+    .to_file(dir.join("ng.beam"))
+    .unwrap();
+
+    let (code, stdout) = beamcode(&dir, &["roundtrip", "ok.beam", "ng.beam"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        stdout,
+        "ok.beam: ok (5 instructions)
+ng.beam: mismatch at 3: move 1, x(0): expected=[64, 9, 1, 3], actual=[64, 17, 3]
+"
+    );
+
+    let (code, stdout) = beamcode(&dir, &["roundtrip", "--format", "json", "ng.beam"]);
+    assert_eq!(code, 1);
+    let report = &json(&stdout)[0];
+    assert_eq!(report["instructions"], 6);
+    assert_eq!(report["mismatch"]["position"], 3);
+    assert_eq!(report["mismatch"]["expected"], json("[64, 9, 1, 3]"));
+}
+
+#[test]
+fn diff_works() {
+    let dir = temp_dir("diff");
+    write_beam(&dir.join("old/m.beam"), identity(), &[]);
+    write_beam(&dir.join("old/sub/n.beam"), identity(), &[]);
+    write_beam(&dir.join("old/only_old.beam"), identity(), &[]);
+    write_beam(&dir.join("new/m.beam"), identity(), &[]);
+    write_beam(&dir.join("new/sub/n.beam"), constant(), &[]);
+    write_beam(&dir.join("new/only_new.beam"), identity(), &[]);
+
+    let (code, stdout) = beamcode(&dir, &["diff", "old/m.beam", "new/m.beam"]);
+    assert_eq!((code, stdout.as_str()), (0, ""));
+
+    let (code, stdout) = beamcode(&dir, &["diff", "old/sub/n.beam", "new/sub/n.beam"]);
+    assert_eq!(code, 1);
+    assert!(stdout.starts_with("--- old/sub/n.beam\n+++ new/sub/n.beam\n"));
+    assert!(stdout.contains("\n label 2\n+move erlang, x(0)\n return\n"));
+
+    // Files are paired by their paths relative to the directories.
+    let (code, stdout) = beamcode(&dir, &["diff", "--format", "json", "old", "new"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        json(&stdout),
+        json(
+            r#"[
+                {"old": "old/m.beam", "new": "new/m.beam", "functions": []},
+                {"old": null, "new": "new/only_new.beam", "functions": []},
+                {"old": "old/only_old.beam", "new": null, "functions": []},
+                {"old": "old/sub/n.beam", "new": "new/sub/n.beam", "functions": [
+                    {"name": "f", "arity": 1, "inserted": 1, "removed": 0, "changed": 0}
+                ]}
+            ]"#
+        )
+    );
+    let (code, stdout) = beamcode(&dir, &["diff", "old", "new"]);
+    assert_eq!(code, 1);
+    assert!(stdout.starts_with("Only in new/only_new.beam\nOnly in old/only_old.beam\n"));
+}
+
+#[test]
+fn strip_works() {
+    let dir = temp_dir("strip");
+    let extra: [(&[u8; 4], &[u8]); 3] = [(b"Attr", ATTR), (b"Dbgi", b"debug"), (b"Docs", b"docs")];
+    write_beam(&dir.join("m.beam"), identity(), &extra);
+    std::fs::create_dir(dir.join("out")).unwrap();
+
+    let (code, stdout) = beamcode(&dir, &["strip", "--output-dir", "out", "m.beam"]);
+    assert_eq!(
+        (code, stdout.as_str()),
+        (0, "out/m.beam: removed [Dbgi, Docs]\n")
+    );
+
+    let (_, stdout) = beamcode(
+        &dir,
+        &["chunks", "--format", "json", "m.beam", "out/m.beam"],
+    );
+    let ids = |report: &serde_json::Value| {
+        report["chunks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["id"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+    let reports = json(&stdout);
+    assert_eq!(
+        ids(&reports[0]),
+        ["AtU8", "Code", "ImpT", "ExpT", "Attr", "Dbgi", "Docs"]
+    );
+    assert_eq!(ids(&reports[1]), ["AtU8", "Code", "ImpT", "ExpT", "Attr"]);
+
+    let (code, stdout) = beamcode(&dir, &["strip", "out/m.beam"]);
+    assert_eq!((code, stdout.as_str()), (0, "out/m.beam: removed []\n"));
+}
@@ -0,0 +1,31 @@
+use beamcode::instruction::Instruction;
+
+#[test]
+fn opcode_names_match_genop_tab() {
+    let mut count = 0;
+    for line in include_str!("genop.tab").lines() {
+        let Some((opcode, name)) = line.split_once(": ") else {
+            continue;
+        };
+        let (deprecated, opcode) = match opcode.strip_prefix('-') {
+            Some(opcode) => (true, opcode),
+            None => (false, opcode),
+        };
+        let opcode: u8 = opcode.parse().unwrap();
+        let name = name.split_once('/').unwrap().0;
+        assert_eq!(
+            Instruction::opcode_name(opcode),
+            Some(name),
+            "opcode {opcode}"
+        );
+        assert_eq!(
+            Instruction::is_deprecated_opcode(opcode),
+            deprecated,
+            "opcode {opcode}"
+        );
+        count += 1;
+    }
+    assert_eq!(count, 180);
+    assert_eq!(Instruction::opcode_name(0), None);
+    assert!(!Instruction::is_deprecated_opcode(0));
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "serde")]
+use beamcode::instruction::{self, Instruction};
+use beamcode::term::{
+    Allocation, AllocationList, AllocationListItem, Atom, Label, List, Literal, Register, Term,
+    TypedRegister, XRegister, YRegister,
+};
+use num::BigInt;
+use std::path::PathBuf;
+
+fn x(value: usize) -> XRegister {
+    XRegister { value, ty: None }
+}
+
+fn y(value: usize) -> YRegister {
+    YRegister { value, ty: None }
+}
+
+fn instructions() -> Vec<Instruction> {
+    vec![
+        Instruction::Label(instruction::Label { literal: 1 }),
+        Instruction::FuncInfo(instruction::FuncInfo {
+            module: Atom { value: 1 },
+            function: Atom { value: 2 },
+            arity: 1,
+        }),
+        Instruction::Label(instruction::Label { literal: 2 }),
+        Instruction::TestHeap(instruction::TestHeap {
+            heap_need: Allocation::List(AllocationList {
+                items: vec![
+                    AllocationListItem::Words(3),
+                    AllocationListItem::Floats(1),
+                    AllocationListItem::Funs(0),
+                ],
+            }),
+            live: 1,
+        }),
+        Instruction::Allocate(instruction::Allocate {
+            stack_need: Allocation::Words(1),
+            live: 1,
+        }),
+        Instruction::Move(instruction::Move {
+            src: Term::XRegister(x(0)),
+            dst: Register::Y(y(0)),
+        }),
+        Instruction::SelectVal(instruction::SelectVal {
+            arg: Term::TypedRegister(TypedRegister::X {
+                register: x(0),
+                ty: 2,
+            }),
+            fail_label: Label { value: 1 },
+            destinations: List {
+                items: vec![
+                    Term::Atom(Atom { value: 3 }),
+                    Term::Label(Label { value: 3 }),
+                    Term::Integer(BigInt::from(-323)),
+                    Term::Label(Label { value: 4 }),
+                ],
+            },
+        }),
+        Instruction::Move(instruction::Move {
+            src: Term::Integer(BigInt::from(9223372036854775808u64)),
+            dst: Register::X(x(1)),
+        }),
+        Instruction::PutTuple2(instruction::PutTuple2 {
+            destination: Register::X(x(0)),
+            elements: List {
+                items: vec![
+                    Term::Literal(Literal { value: 0 }),
+                    Term::Character('λ'),
+                    Term::Usize(7),
+                ],
+            },
+        }),
+        Instruction::InitYregs(instruction::InitYregs {
+            registers: vec![y(1), y(2)],
+        }),
+        Instruction::GetMapElement(instruction::GetMapElement {
+            arg1: Term::Label(Label { value: 1 }),
+            arg2: Term::XRegister(x(0)),
+            arg3: Term::List(List {
+                items: vec![Term::Atom(Atom { value: 4 }), Term::XRegister(x(2))],
+            }),
+        }),
+        Instruction::Deallocate(instruction::Deallocate { n: 1 }),
+        Instruction::Return(instruction::Return {}),
+        Instruction::IntCodeEnd(instruction::IntCodeEnd {}),
+    ]
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+}
+
+#[test]
+fn serialize_instructions_matches_golden_file() {
+    let path = golden_path("instructions.json");
+    let actual = serde_json::to_string_pretty(&instructions()).expect("serialize failure") + "\n";
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &actual).expect("write failure");
+    }
+    let expected = std::fs::read_to_string(&path).expect("read failure");
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn deserialize_golden_file_works() {
+    let json = std::fs::read_to_string(golden_path("instructions.json")).expect("read failure");
+    let decoded: Vec<Instruction> = serde_json::from_str(&json).expect("deserialize failure");
+
+    let expected = beamcode::encode_instructions(&instructions()).expect("encode failure");
+    let actual = beamcode::encode_instructions(&decoded).expect("encode failure");
+    assert_eq!(actual, expected);
+}